@@ -0,0 +1,89 @@
+use crate::reading::{roman_value, ROMAN_LOOKALIKES};
+use std::collections::HashMap;
+
+/// Shortest and longest uppercase words considered acronyms
+const ACRONYM_LENGTH: (usize, usize) = (2, 6);
+
+/// Rewrites the acronyms of a text, voices tend to read them as words or garble them
+#[derive(Debug, Clone, Default)]
+pub struct AcronymFilter {
+    /// Spell the acronyms without an expansion letter by letter, otherwise leave them be
+    pub spell: bool,
+    /// Text read in place of an acronym, e.g. `NATO` as `Nato`
+    pub expansions: HashMap<String, String>,
+}
+
+impl AcronymFilter {
+    /// Rewrite the acronyms of `text`, the document's expansions taking precedence over
+    /// the filter's. Text written mostly in capitals is shouted rather than abbreviated,
+    /// only its expansions are applied.
+    pub fn apply(&self, text: &str, document_expansions: &HashMap<String, String>) -> String {
+        let words: Vec<_> = text
+            .split_whitespace()
+            .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+            .filter(|word| word.chars().any(char::is_alphabetic))
+            .collect();
+        let capitalized = words.iter().filter(|word| is_all_caps(word)).count();
+        let spell = self.spell && capitalized * 2 <= words.len();
+        let mut result = String::with_capacity(text.len());
+        for token in text.split_inclusive(char::is_whitespace) {
+            let word = token.trim_end();
+            let core_start = word.find(char::is_alphanumeric).unwrap_or(word.len());
+            let core_end = word
+                .char_indices()
+                .rev()
+                .find(|(_, c)| c.is_alphanumeric())
+                .map_or(core_start, |(idx, c)| idx + c.len_utf8());
+            let core = &word[core_start..core_end];
+            let expansion = document_expansions
+                .get(core)
+                .or_else(|| self.expansions.get(core));
+            result.push_str(&word[..core_start]);
+            match expansion {
+                Some(expansion) => result.push_str(expansion),
+                None if spell && is_acronym(core) => result.push_str(&spell_out(core)),
+                None => result.push_str(core),
+            }
+            result.push_str(&token[core_end..]);
+        }
+        result
+    }
+}
+
+fn is_all_caps(word: &str) -> bool {
+    word.chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && word.chars().filter(char::is_ascii_uppercase).count() >= ACRONYM_LENGTH.0
+}
+
+/// Whether a word looks like an acronym: capitals, possibly with digits or a plural `s`
+fn is_acronym(word: &str) -> bool {
+    let letters = word.strip_suffix('s').unwrap_or(word);
+    let (min, max) = ACRONYM_LENGTH;
+    (min..=max).contains(&letters.len())
+        && letters.starts_with(|c: char| c.is_ascii_uppercase())
+        && is_all_caps(letters)
+        // Numbered parts and rulers, e.g. World War II, but not acronyms such as CD
+        && (roman_value(letters).is_none() || ROMAN_LOOKALIKES.contains(&letters))
+}
+
+/// `NASA` becomes `N A S A` and `APIs` becomes `A P I's`, digits are kept together
+fn spell_out(acronym: &str) -> String {
+    let (letters, plural) = match acronym.strip_suffix('s') {
+        Some(letters) => (letters, "'s"),
+        None => (acronym, ""),
+    };
+    let mut spelled = String::with_capacity(letters.len() * 2 + plural.len());
+    let mut previous_digit = false;
+    for c in letters.chars() {
+        let is_digit = c.is_ascii_digit();
+        let within_number = is_digit && previous_digit;
+        if !spelled.is_empty() && !within_number {
+            spelled.push(' ');
+        }
+        spelled.push(c);
+        previous_digit = is_digit;
+    }
+    spelled.push_str(plural);
+    spelled
+}
@@ -0,0 +1,59 @@
+use crate::{NeosynthError, NeosynthResult};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use windows::core::{Interface, RuntimeType};
+use windows::Foundation::{IAsyncInfo, IAsyncOperation};
+
+/// An operation being waited for
+struct InFlight(IAsyncInfo);
+
+// WinRT asynchronous operations are agile, they can be cancelled from any thread
+unsafe impl Send for InFlight {}
+unsafe impl Sync for InFlight {}
+
+/// Waits for WinRT asynchronous operations while keeping track of them, so that an
+/// engine can cancel the operations still in flight when it shuts down rather than
+/// letting them outlive it.
+#[derive(Default)]
+pub struct AsyncOps {
+    in_flight: Mutex<HashMap<u64, InFlight>>,
+    next_id: AtomicU64,
+    shut_down: AtomicBool,
+}
+
+impl AsyncOps {
+    /// Block until the operation completes, fails with `Cancelled` once `cancel_all` was called
+    pub fn wait<T: RuntimeType + 'static>(&self, operation: &impl Interface) -> NeosynthResult<T> {
+        let operation: IAsyncOperation<T> = operation.cast()?;
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        {
+            // Checked under the lock so that no operation slips past `cancel_all`
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if self.is_shut_down() {
+                operation.Cancel().ok();
+                return Err(NeosynthError::Cancelled);
+            }
+            in_flight.insert(id, InFlight(operation.cast()?));
+        }
+        let result = operation.get();
+        self.in_flight.lock().unwrap().remove(&id);
+        match result {
+            Err(_) if self.is_shut_down() => Err(NeosynthError::Cancelled),
+            result => Ok(result?),
+        }
+    }
+
+    /// Cancel every operation in flight and refuse to wait for new ones
+    pub fn cancel_all(&self) {
+        let in_flight = self.in_flight.lock().unwrap();
+        self.shut_down.store(true, Ordering::SeqCst);
+        for operation in in_flight.values() {
+            operation.0.Cancel().ok();
+        }
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down.load(Ordering::SeqCst)
+    }
+}
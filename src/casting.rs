@@ -0,0 +1,82 @@
+use crate::fingerprint::fnv1a;
+use crate::lang::match_language;
+use crate::{NeosynthError, NeosynthResult, OperationError, VoiceInfo};
+use std::collections::{HashMap, HashSet};
+use windows::Media::SpeechSynthesis::VoiceInformation;
+
+/// The kind of voice wanted for a speaker, unset attributes match any voice
+#[derive(Debug, Clone, Default)]
+pub struct Role {
+    /// `male` or `female`
+    pub gender: Option<String>,
+    /// Language tag, the region selects a variety, e.g. `en-GB`
+    pub language: Option<String>,
+}
+
+impl Role {
+    /// Build a role from attribute names and values, e.g. `{"gender": "female"}`
+    pub fn from_attributes(attributes: HashMap<String, String>) -> NeosynthResult<Self> {
+        let mut role = Self::default();
+        for (name, value) in attributes {
+            match name.as_str() {
+                "gender" => role.gender = Some(value.to_ascii_lowercase()),
+                "language" => role.language = Some(value),
+                _ => return Err(OperationError(format!("Unknown voice attribute: {}", name))),
+            }
+        }
+        Ok(role)
+    }
+
+    /// The voices playing this role best, only those matching the language most closely are kept
+    fn candidates<'a>(&self, voices: &'a [VoiceInfo]) -> Vec<&'a VoiceInfo> {
+        let mut candidates: Vec<_> = voices
+            .iter()
+            .filter(|v| {
+                self.gender
+                    .as_ref()
+                    .is_none_or(|gender| *gender == v.gender)
+            })
+            .filter_map(|v| match &self.language {
+                Some(language) => match_language(language, &v.language).map(|m| (Some(m), v)),
+                None => Some((None, v)),
+            })
+            .collect();
+        let best_match = candidates.iter().map(|(m, _)| *m).max();
+        candidates.retain(|(m, _)| Some(*m) == best_match);
+        let mut candidates: Vec<_> = candidates.into_iter().map(|(_, v)| v).collect();
+        candidates.sort_by(|a, b| a.id.cmp(&b.id));
+        candidates
+    }
+}
+
+/// Assign voices to the speakers of a dialogue, distinct ones as long as enough voices match.
+///
+/// Each speaker starts looking at a voice picked from the hash of its label, so the same
+/// cast gets the same voices across sessions as long as the installed voices don't change.
+pub fn cast_voices(
+    roles: HashMap<String, Role>,
+    voices: &[VoiceInfo],
+) -> NeosynthResult<HashMap<String, VoiceInfo>> {
+    let mut roles: Vec<_> = roles.into_iter().collect();
+    roles.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut used = HashSet::new();
+    let mut cast = HashMap::new();
+    for (label, role) in roles {
+        let candidates = role.candidates(voices);
+        if candidates.is_empty() {
+            return Err(NeosynthError::Voice(format!(
+                "No installed voice matches speaker {}",
+                label
+            )));
+        }
+        let start = (fnv1a(label.as_bytes()) % candidates.len() as u64) as usize;
+        let voice = (0..candidates.len())
+            .map(|offset| candidates[(start + offset) % candidates.len()])
+            .find(|v| !used.contains(&v.id))
+            // More speakers than voices, some have to share
+            .unwrap_or(candidates[start]);
+        used.insert(voice.id.clone());
+        cast.insert(label, VoiceInfo::from(VoiceInformation::from(voice)));
+    }
+    Ok(cast)
+}
@@ -0,0 +1,160 @@
+use crate::text::escape_xml;
+
+/// Speaking rate of code as a multiple of the rate of the voice
+pub const CODE_RATE: f64 = 0.8;
+
+/// Width of a tab when announcing indentation
+const TAB_WIDTH: usize = 4;
+
+/// Languages whose blocks are delimited by indentation, which has to be announced
+const INDENTED_LANGUAGES: &[&str] = &[
+    "python",
+    "py",
+    "yaml",
+    "yml",
+    "haskell",
+    "nim",
+    "fsharp",
+    "f#",
+    "coffeescript",
+    "makefile",
+];
+
+/// Operators read as a whole, before their individual characters
+const CODE_OPERATORS: &[(&str, &str)] = &[
+    ("->", "arrow"),
+    ("=>", "fat arrow"),
+    ("::", "double colon"),
+    ("==", "equals equals"),
+    ("!=", "not equals"),
+    ("<=", "less or equal"),
+    (">=", "greater or equal"),
+    ("&&", "and and"),
+    ("||", "or or"),
+    ("++", "plus plus"),
+    ("+=", "plus equals"),
+    ("-=", "minus equals"),
+];
+
+/// A block of source code, read slower than text with its symbols named
+#[derive(Debug, Clone)]
+pub struct CodeBlock {
+    pub code: String,
+    /// Programming language, e.g. `python`, changes of indentation are announced for
+    /// the languages where it is significant
+    pub language: Option<String>,
+    /// Announce the number of each line before it
+    pub line_numbers: bool,
+}
+
+impl CodeBlock {
+    fn announces_indentation(&self) -> bool {
+        self.language.as_ref().is_some_and(|language| {
+            INDENTED_LANGUAGES.contains(&language.to_ascii_lowercase().as_str())
+        })
+    }
+}
+
+/// English name of a character spoken in code, voices skip most of them in text
+fn code_symbol_name(c: char) -> Option<&'static str> {
+    let name = match c {
+        '{' => "open brace",
+        '}' => "close brace",
+        '(' => "open paren",
+        ')' => "close paren",
+        '[' => "open bracket",
+        ']' => "close bracket",
+        '<' => "less than",
+        '>' => "greater than",
+        '=' => "equals",
+        '+' => "plus",
+        '-' => "minus",
+        '*' => "star",
+        '/' => "slash",
+        '\\' => "backslash",
+        '%' => "percent",
+        '&' => "ampersand",
+        '|' => "bar",
+        '^' => "caret",
+        '~' => "tilde",
+        '!' => "bang",
+        '?' => "question mark",
+        ':' => "colon",
+        ';' => "semicolon",
+        ',' => "comma",
+        '.' => "dot",
+        '"' => "quote",
+        '\'' => "single quote",
+        '`' => "backtick",
+        '#' => "hash",
+        '@' => "at",
+        '$' => "dollar",
+        '_' => "underscore",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Name the symbols of a line of code, leaving words and numbers to the voice
+fn verbalize_code_line(line: &str) -> String {
+    let mut spoken = String::with_capacity(line.len() * 2);
+    let mut rest = line.trim();
+    while let Some(c) = rest.chars().next() {
+        let operator = CODE_OPERATORS
+            .iter()
+            .find(|(operator, _)| rest.starts_with(operator));
+        let (name, len) = match (operator, code_symbol_name(c)) {
+            (Some((operator, name)), _) => (Some(*name), operator.len()),
+            (None, name) => (name, c.len_utf8()),
+        };
+        match name {
+            Some(name) => {
+                spoken.push(' ');
+                spoken.push_str(name);
+                spoken.push(' ');
+            }
+            None => spoken.push(c),
+        }
+        rest = &rest[len..];
+    }
+    spoken.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Width of the leading whitespace of a line
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+/// An SSML document reading `block` line by line, slower than text and with its symbols
+/// named. Symbol names are only available in English.
+pub fn code_ssml(language: &str, block: &CodeBlock) -> String {
+    let announce_indentation = block.announces_indentation();
+    let mut body = String::new();
+    let mut current_indentation = 0;
+    for (number, line) in block.code.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut spoken = String::new();
+        if block.line_numbers {
+            spoken.push_str(&format!("line {}, ", number + 1));
+        }
+        let width = indentation(line);
+        if announce_indentation && width != current_indentation {
+            spoken.push_str(&format!("indent {}, ", width));
+            current_indentation = width;
+        }
+        spoken.push_str(&verbalize_code_line(line));
+        body.push_str(&escape_xml(&spoken));
+        body.push_str(r#"<break strength="strong"/>"#);
+    }
+    format!(
+        r#"<speak version="1.0" xmlns="http://www.w3.org/2001/10/synthesis" xml:lang="{}"><prosody rate="{:+}%">{}</prosody></speak>"#,
+        escape_xml(language),
+        ((CODE_RATE - 1.0) * 100.0).round(),
+        body
+    )
+}
@@ -0,0 +1,253 @@
+use crate::{NeosynthError, NeosynthResult, OperationError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::env;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Environment variable holding the path of the configuration file
+pub const CONFIG_PATH_VAR: &str = "NEOSYNTH_CONFIG";
+/// Prefix of the environment variables overriding individual settings, e.g. `NEOSYNTH_RATE`
+const ENV_PREFIX: &str = "NEOSYNTH_";
+/// Factor from a rate setting to the WinRT speaking rate
+const RATE_SCALE: f64 = 0.06;
+/// Slowest speaking rate WinRT accepts, half the normal rate
+const MIN_SPEAKING_RATE: f64 = 0.5;
+
+/// Severity of a message sent to the event sink's `log` method
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Default)]
+pub enum LogLevel {
+    #[default]
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl LogLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = crate::NeosynthError;
+
+    fn from_str(level: &str) -> NeosynthResult<Self> {
+        match level.to_ascii_lowercase().as_str() {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warning" | "warn" => Ok(LogLevel::Warning),
+            "error" => Ok(LogLevel::Error),
+            _ => Err(OperationError(format!("Invalid log level: {}", level))),
+        }
+    }
+}
+
+/// Engine defaults read from a configuration file and environment variables.
+///
+/// The configuration file uses a flat subset of TOML, one `key = value` per line.
+/// Tables such as `[logging]` are rejected, and JSON isn't supported:
+///
+/// ```toml
+/// # Voice id, display name, or language tag
+/// voice = "en-US"
+/// rate = 40
+/// pitch = 50
+/// volume = 80
+/// max_text_length = 2000
+/// log_level = "warning"
+/// line_joining = true
+/// bidi_cleanup = false
+/// ```
+///
+/// Each setting can be overridden by an environment variable with the upper cased
+/// key prefixed by `NEOSYNTH_`, e.g. `NEOSYNTH_RATE=60`.
+#[derive(Debug, Clone, Default)]
+pub struct EngineConfig {
+    pub voice: Option<String>,
+    pub rate: Option<f64>,
+    pub pitch: Option<f64>,
+    pub volume: Option<f64>,
+    pub max_text_length: Option<usize>,
+    pub log_level: Option<LogLevel>,
+    pub line_joining: Option<bool>,
+    pub bidi_cleanup: Option<bool>,
+}
+
+impl EngineConfig {
+    const KEYS: [&'static str; 8] = [
+        "voice",
+        "rate",
+        "pitch",
+        "volume",
+        "max_text_length",
+        "log_level",
+        "line_joining",
+        "bidi_cleanup",
+    ];
+
+    /// Check that prosody values are within their 0 to 100 range, and that the rate isn't
+    /// below the slowest speaking rate WinRT accepts
+    pub fn validate(&self) -> NeosynthResult<()> {
+        if let Some(rate) = self.rate {
+            // With some tolerance, the slowest rate read back from WinRT may be rounded down
+            if !rate.is_finite() || rate * RATE_SCALE < MIN_SPEAKING_RATE - 1e-9 || rate > 100.0 {
+                // Rounded up, the bound itself would be rejected
+                let min_rate = (MIN_SPEAKING_RATE / RATE_SCALE * 100.0).ceil() / 100.0;
+                return Err(OperationError(format!(
+                    "rate must be between {} and 100, got {}",
+                    min_rate, rate
+                )));
+            }
+        }
+        for (name, value) in [("pitch", self.pitch), ("volume", self.volume)] {
+            if let Some(value) = value {
+                if !(0.0..=100.0).contains(&value) {
+                    return Err(OperationError(format!(
+                        "{} must be between 0 and 100, got {}",
+                        name, value
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> NeosynthResult<()> {
+        fn parse<T: FromStr>(key: &str, value: &str) -> NeosynthResult<T> {
+            value
+                .parse()
+                .map_err(|_| OperationError(format!("Invalid value for {}: {}", key, value)))
+        }
+        match key {
+            "voice" => self.voice = Some(value.to_string()),
+            "rate" => self.rate = Some(parse(key, value)?),
+            "pitch" => self.pitch = Some(parse(key, value)?),
+            "volume" => self.volume = Some(parse(key, value)?),
+            "max_text_length" => self.max_text_length = Some(parse(key, value)?),
+            "log_level" => self.log_level = Some(value.parse()?),
+            "line_joining" => self.line_joining = Some(parse(key, value)?),
+            "bidi_cleanup" => self.bidi_cleanup = Some(parse(key, value)?),
+            _ => {
+                return Err(OperationError(format!(
+                    "Unknown configuration key: {}",
+                    key
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    pub fn from_toml_str(content: &str) -> NeosynthResult<Self> {
+        let mut config = Self::default();
+        for (lineno, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(OperationError(format!(
+                    "Unsupported table on configuration line {}: {}, settings are top-level keys",
+                    lineno + 1,
+                    line
+                )));
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| {
+                OperationError(format!(
+                    "Invalid configuration line {}: {}",
+                    lineno + 1,
+                    line
+                ))
+            })?;
+            config.set(key.trim(), unquote(value))?;
+        }
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> NeosynthResult<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            NeosynthError::Io(
+                format!("Failed to read configuration file {}", path.display()),
+                e,
+            )
+        })?;
+        Self::from_toml_str(&content)
+    }
+
+    pub fn from_env() -> NeosynthResult<Self> {
+        let mut config = Self::default();
+        for key in Self::KEYS {
+            if let Ok(value) = env::var(format!("{}{}", ENV_PREFIX, key.to_ascii_uppercase())) {
+                config.set(key, value.trim())?;
+            }
+        }
+        Ok(config)
+    }
+
+    /// Combine two configurations, settings present in `other` take precedence
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            voice: other.voice.or(self.voice),
+            rate: other.rate.or(self.rate),
+            pitch: other.pitch.or(self.pitch),
+            volume: other.volume.or(self.volume),
+            max_text_length: other.max_text_length.or(self.max_text_length),
+            log_level: other.log_level.or(self.log_level),
+            line_joining: other.line_joining.or(self.line_joining),
+            bidi_cleanup: other.bidi_cleanup.or(self.bidi_cleanup),
+        }
+    }
+
+    /// Load the configuration file given by `path` or `NEOSYNTH_CONFIG`, then apply environment overrides
+    pub fn load(path: Option<&Path>) -> NeosynthResult<Self> {
+        let file_config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => match env::var_os(CONFIG_PATH_VAR) {
+                Some(path) => Self::from_file(Path::new(&path))?,
+                None => Self::default(),
+            },
+        };
+        Ok(file_config.merge(Self::from_env()?))
+    }
+}
+
+/// Strip trailing comments and quotes from a TOML value
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    if let Some(quoted) = value.strip_prefix('"') {
+        quoted.split('"').next().unwrap_or_default()
+    } else if let Some(quoted) = value.strip_prefix('\'') {
+        quoted.split('\'').next().unwrap_or_default()
+    } else {
+        value.split('#').next().unwrap_or_default().trim()
+    }
+}
+
+impl IntoPy<PyObject> for EngineConfig {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        let dict = PyDict::new(py);
+        let set_item = |key: &str, value: Option<PyObject>| {
+            if let Some(value) = value {
+                dict.set_item(key, value).ok();
+            }
+        };
+        set_item("voice", self.voice.map(|v| v.into_py(py)));
+        set_item("rate", self.rate.map(|v| v.into_py(py)));
+        set_item("pitch", self.pitch.map(|v| v.into_py(py)));
+        set_item("volume", self.volume.map(|v| v.into_py(py)));
+        set_item(
+            "max_text_length",
+            self.max_text_length.map(|v| v.into_py(py)),
+        );
+        set_item("log_level", self.log_level.map(|v| v.name().into_py(py)));
+        set_item("line_joining", self.line_joining.map(|v| v.into_py(py)));
+        set_item("bidi_cleanup", self.bidi_cleanup.map(|v| v.into_py(py)));
+        dict.into()
+    }
+}
@@ -0,0 +1,136 @@
+use crate::code::CodeBlock;
+use crate::{SpeechElement, SpeechUtterance, TextRole};
+use pyo3::prelude::*;
+
+/// Builds an utterance from the blocks of a document, each preceded by a bookmark named
+/// after its kind and number, e.g. `heading2` or `paragraph5`. The rows of tables and the
+/// items of lists get their own bookmarks, e.g. `table1.row3` and `list2.item1`.
+#[pyclass(text_signature = "(footnotes_at_end: bool = True)")]
+#[derive(Default)]
+pub struct DocumentBuilder {
+    utterance: SpeechUtterance,
+    headings: usize,
+    paragraphs: usize,
+    lists: usize,
+    tables: usize,
+    code_blocks: usize,
+    footnotes: Vec<String>,
+    /// Read footnotes after the document rather than where they are added
+    footnotes_at_end: bool,
+}
+
+impl DocumentBuilder {
+    /// Count a block of the given kind, returns the name of its bookmark
+    fn next_bookmark(counter: &mut usize, kind: &str) -> String {
+        *counter += 1;
+        format!("{}{}", kind, counter)
+    }
+
+    fn push_bookmark(&mut self, bookmark: String) {
+        self.utterance.0.push(SpeechElement::Bookmark(bookmark));
+    }
+
+    fn footnote_elements(number: usize, text: &str) -> [SpeechElement; 2] {
+        [
+            SpeechElement::Bookmark(format!("footnote{}", number)),
+            SpeechElement::Text(format!("Footnote {}. {}", number, text.trim())),
+        ]
+    }
+}
+
+#[pymethods]
+impl DocumentBuilder {
+    #[new]
+    #[args(footnotes_at_end = "true")]
+    pub fn new(footnotes_at_end: bool) -> Self {
+        Self {
+            footnotes_at_end,
+            ..Default::default()
+        }
+    }
+    /// Add a heading, always spoken in preview mode
+    #[pyo3(text_signature = "($self, text: str)")]
+    fn add_heading(&mut self, text: String) {
+        let bookmark = Self::next_bookmark(&mut self.headings, "heading");
+        self.push_bookmark(bookmark);
+        self.utterance
+            .0
+            .push(SpeechElement::Structured(TextRole::Heading, text));
+    }
+    /// Add a paragraph, only its first sentence is spoken in preview mode
+    #[pyo3(text_signature = "($self, text: str)")]
+    fn add_paragraph(&mut self, text: String) {
+        let bookmark = Self::next_bookmark(&mut self.paragraphs, "paragraph");
+        self.push_bookmark(bookmark);
+        self.utterance
+            .0
+            .push(SpeechElement::Structured(TextRole::Paragraph, text));
+    }
+    /// Add a list, its items numbered if `ordered`. Unless `announce` is false, it is
+    /// announced with its number of items and unordered items with bullets.
+    #[pyo3(
+        text_signature = "($self, items: list[str], ordered: bool = False, announce: bool = True)"
+    )]
+    #[args(ordered = "false", announce = "true")]
+    fn add_list(&mut self, items: Vec<String>, ordered: bool, announce: bool) {
+        let bookmark = Self::next_bookmark(&mut self.lists, "list");
+        let item_prefix = format!("{}.item", bookmark);
+        self.push_bookmark(bookmark);
+        self.utterance
+            .push_list(&items, ordered, announce, Some(&item_prefix));
+    }
+    /// Add a table, each row read as a sentence: `Row 2, Price: 10 dollars`
+    #[pyo3(text_signature = "($self, rows: list[list[str]], headers: list[str] = None)")]
+    #[args(headers = "None")]
+    fn add_table(&mut self, rows: Vec<Vec<String>>, headers: Option<Vec<String>>) {
+        let bookmark = Self::next_bookmark(&mut self.tables, "table");
+        let row_prefix = format!("{}.row", bookmark);
+        self.push_bookmark(bookmark);
+        self.utterance.push_table(
+            &rows,
+            &headers.unwrap_or_default(),
+            ", ",
+            ".",
+            Some(&row_prefix),
+        );
+    }
+    /// Add source code, read slower than text with its symbols named
+    #[pyo3(text_signature = "($self, text: str, language: str = None, line_numbers: bool = False)")]
+    #[args(language = "None", line_numbers = "false")]
+    fn add_code(&mut self, text: String, language: Option<String>, line_numbers: bool) {
+        let bookmark = Self::next_bookmark(&mut self.code_blocks, "code");
+        self.push_bookmark(bookmark);
+        self.utterance.0.push(SpeechElement::Code(CodeBlock {
+            code: text,
+            language,
+            line_numbers,
+        }));
+    }
+    /// Add a footnote, read after the document or right away depending on
+    /// `footnotes_at_end`. Returns its number, to refer to it in the text.
+    #[pyo3(text_signature = "($self, text: str) -> int")]
+    fn add_footnote(&mut self, text: String) -> usize {
+        self.footnotes.push(text);
+        let number = self.footnotes.len();
+        if !self.footnotes_at_end {
+            let elements = Self::footnote_elements(number, &self.footnotes[number - 1]);
+            self.utterance.0.extend(elements);
+        }
+        number
+    }
+    /// The utterance speaking the document, followed by its footnotes
+    #[pyo3(text_signature = "($self) -> neosynth.SpeechUtterance")]
+    fn build(&self) -> SpeechUtterance {
+        let mut utterance = self.utterance.clone();
+        if self.footnotes_at_end && !self.footnotes.is_empty() {
+            utterance.0.push(SpeechElement::Structured(
+                TextRole::Heading,
+                "Footnotes".to_string(),
+            ));
+            for (idx, text) in self.footnotes.iter().enumerate() {
+                utterance.0.extend(Self::footnote_elements(idx + 1, text));
+            }
+        }
+        utterance
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Characters spoken per second at the normal speaking rate, used until a voice is calibrated
+const DEFAULT_CHARS_PER_SECOND: f64 = 14.0;
+/// Minimum number of measured characters before a voice's own speed is trusted
+const MIN_CALIBRATION_CHARS: f64 = 200.0;
+
+/// Estimates speaking time from character counts, calibrated per voice from synthesized audio
+#[derive(Default)]
+pub struct DurationEstimator {
+    /// Total characters and seconds of audio (normalized to the normal rate) measured per voice
+    measurements: Mutex<HashMap<String, (f64, f64)>>,
+}
+
+impl DurationEstimator {
+    /// Record that `chars` characters took `seconds` to speak at the given speaking rate multiplier
+    pub fn record(&self, voice_id: &str, chars: usize, seconds: f64, rate_multiplier: f64) {
+        if chars == 0 || seconds <= 0.0 {
+            return;
+        }
+        let mut measurements = self.measurements.lock().unwrap();
+        let (total_chars, total_seconds) = measurements.entry(voice_id.to_string()).or_default();
+        *total_chars += chars as f64;
+        *total_seconds += seconds * rate_multiplier;
+    }
+
+    /// Characters per second of the given voice at the normal speaking rate
+    pub fn chars_per_second(&self, voice_id: &str) -> f64 {
+        match self.measurements.lock().unwrap().get(voice_id) {
+            Some((chars, seconds)) if *chars >= MIN_CALIBRATION_CHARS => chars / seconds,
+            _ => DEFAULT_CHARS_PER_SECOND,
+        }
+    }
+
+    /// Estimated seconds needed to speak `chars` characters with the given voice and rate multiplier
+    pub fn estimate(&self, voice_id: &str, chars: usize, rate_multiplier: f64) -> f64 {
+        chars as f64 / (self.chars_per_second(voice_id) * rate_multiplier.max(f64::EPSILON))
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{NeosynthError, NeosynthResult};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// How synthesized audio compares with the audio synthesized before for the same content
+/// and settings
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FingerprintMatch {
+    /// Nothing was synthesized for them before
+    New,
+    Same,
+    /// The audio differs, e.g. because a Windows update changed the voice
+    Changed,
+}
+
+/// Hashes of the audio synthesized for each content and settings, kept in a file to
+/// compare the output of the voices from one run to the next. New fingerprints are
+/// written with `flush`, and when the store is dropped.
+pub struct FingerprintStore {
+    path: PathBuf,
+    /// Audio hashes by hash of the content and settings
+    entries: Mutex<HashMap<u64, u64>>,
+    /// Whether fingerprints were recorded since the file was last written
+    dirty: AtomicBool,
+}
+
+impl FingerprintStore {
+    /// Open the fingerprints saved in the given file, which is created when the first
+    /// fingerprint is recorded
+    pub fn open(path: &Path) -> NeosynthResult<Self> {
+        let entries = match fs::read_to_string(path) {
+            Ok(content) => parse(&content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(NeosynthError::Io(
+                    format!("Failed to read fingerprints from {}", path.display()),
+                    e,
+                ))
+            }
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries: Mutex::new(entries),
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    /// Compare the audio synthesized for the given synthesis key with the one recorded
+    /// before, and record it
+    pub fn record(&self, key: &str, audio: &[u8]) -> FingerprintMatch {
+        let mut entries = self.entries.lock().unwrap();
+        let hash = fnv1a(audio);
+        let found = match entries.insert(fnv1a(key.as_bytes()), hash) {
+            None => FingerprintMatch::New,
+            Some(previous) if previous == hash => FingerprintMatch::Same,
+            Some(_) => FingerprintMatch::Changed,
+        };
+        if found != FingerprintMatch::Same {
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+        found
+    }
+
+    /// Write the fingerprints recorded since the file was last written, if any
+    pub fn flush(&self) -> NeosynthResult<()> {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let entries = self.entries.lock().unwrap();
+        self.save(&entries).map_err(|e| {
+            self.dirty.store(true, Ordering::SeqCst);
+            NeosynthError::Io(
+                format!("Failed to write fingerprints to {}", self.path.display()),
+                e,
+            )
+        })
+    }
+
+    /// Replace the file by renaming, so an interrupted write doesn't lose the fingerprints
+    fn save(&self, entries: &HashMap<u64, u64>) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp_path = self
+            .path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temp_path, serialize(entries))?;
+        fs::rename(&temp_path, &self.path).inspect_err(|_| {
+            fs::remove_file(&temp_path).ok();
+        })
+    }
+}
+
+impl Drop for FingerprintStore {
+    fn drop(&mut self) {
+        // Best effort, there is no one left to report to
+        self.flush().ok();
+    }
+}
+
+/// FNV-1a hash, stable across processes and Rust versions unlike the std hashers
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// One line per entry: the hash of the content and settings, then the hash of the audio
+fn serialize(entries: &HashMap<u64, u64>) -> String {
+    let mut content = String::new();
+    for (key, hash) in entries {
+        content.push_str(&format!("{:016x}\t{:016x}\n", key, hash));
+    }
+    content
+}
+
+/// Lines which can't be parsed are skipped
+fn parse(content: &str) -> HashMap<u64, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, hash) = line.split_once('\t')?;
+            Some((
+                u64::from_str_radix(key, 16).ok()?,
+                u64::from_str_radix(hash, 16).ok()?,
+            ))
+        })
+        .collect()
+}
@@ -0,0 +1,81 @@
+use crate::{NeosynthError, NeosynthResult};
+use std::sync::mpsc;
+use std::thread;
+use windows::core::HSTRING;
+use windows::Win32::Foundation::{CloseHandle, WAIT_ABANDONED, WAIT_OBJECT_0};
+use windows::Win32::System::Threading::{CreateMutexW, ReleaseMutex, WaitForSingleObject};
+
+/// Requests to the thread owning the focus mutex
+enum FocusRequest {
+    /// Acquire the mutex unless it is taken, replying whether it is held
+    TryAcquire(mpsc::Sender<bool>),
+    Release,
+}
+
+/// Audio focus shared by the processes of the current session using neosynth with the same
+/// focus name, only one of them holds it at a time. It is a named mutex, which Windows
+/// releases if the process holding it exits without releasing it.
+pub struct AudioFocus {
+    requests: mpsc::Sender<FocusRequest>,
+}
+
+impl AudioFocus {
+    pub fn new(name: &str) -> NeosynthResult<Self> {
+        let mutex_name = HSTRING::from(format!("Local\\neosynth-focus-{}", name));
+        let (created_tx, created_rx) = mpsc::channel();
+        let (requests, receiver) = mpsc::channel();
+        // A mutex belongs to the thread which acquired it, a single thread acquires and
+        // releases this one whichever thread asks for it
+        thread::spawn(move || {
+            let handle = match unsafe { CreateMutexW(None, false, &mutex_name) } {
+                Ok(handle) => handle,
+                Err(e) => {
+                    created_tx.send(Err(e)).ok();
+                    return;
+                }
+            };
+            created_tx.send(Ok(())).ok();
+            let mut held = false;
+            // Ends once the AudioFocus is dropped
+            for request in receiver {
+                match request {
+                    FocusRequest::TryAcquire(reply) => {
+                        if !held {
+                            let result = unsafe { WaitForSingleObject(handle, 0) };
+                            // An abandoned mutex is acquired all the same
+                            held = result == WAIT_OBJECT_0 || result == WAIT_ABANDONED;
+                        }
+                        reply.send(held).ok();
+                    }
+                    FocusRequest::Release if held => {
+                        unsafe { ReleaseMutex(handle) };
+                        held = false;
+                    }
+                    FocusRequest::Release => {}
+                }
+            }
+            unsafe {
+                if held {
+                    ReleaseMutex(handle);
+                }
+                CloseHandle(handle);
+            }
+        });
+        created_rx.recv().map_err(|_| NeosynthError::Cancelled)??;
+        Ok(Self { requests })
+    }
+
+    /// Take the focus unless another process holds it, returns whether it is held
+    pub fn try_acquire(&self) -> bool {
+        let (reply, response) = mpsc::channel();
+        if self.requests.send(FocusRequest::TryAcquire(reply)).is_err() {
+            return false;
+        }
+        response.recv().unwrap_or(false)
+    }
+
+    /// Let other processes take the focus, if it is held
+    pub fn release(&self) {
+        self.requests.send(FocusRequest::Release).ok();
+    }
+}
@@ -0,0 +1,147 @@
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Number of consecutive failures after which a voice is considered failing
+const FAILING_THRESHOLD: usize = 3;
+
+/// Errors with which synthesis fails because the voice can't be used in this context, rather
+/// than because of what it was asked to speak
+const VOICE_UNAVAILABLE_ERRORS: &[(u32, &str)] = &[
+    // E_ACCESSDENIED
+    (
+        0x80070005,
+        "access denied, the voice may be restricted to other applications",
+    ),
+    // HRESULT_FROM_WIN32(ERROR_ACCESS_DISABLED_BY_POLICY)
+    (0x800704EC, "disabled by policy"),
+    // HRESULT_FROM_WIN32(ERROR_NOT_SUPPORTED)
+    (0x80070032, "not supported in this context or locale"),
+    // HRESULT_FROM_WIN32(ERROR_NOT_FOUND)
+    (
+        0x80070490,
+        "voice data not found, the voice may not be fully installed",
+    ),
+    // REGDB_E_CLASSNOTREG
+    (0x80040154, "voice engine not registered"),
+];
+
+/// Why a voice can't be used, if synthesis failed with the given error code for that reason
+pub fn voice_unavailable_reason(code: i32) -> Option<&'static str> {
+    VOICE_UNAVAILABLE_ERRORS
+        .iter()
+        .find(|(unavailable_code, _)| *unavailable_code as i32 == code)
+        .map(|(_, reason)| *reason)
+}
+
+/// Synthesis success and failure counts for one voice
+#[pyclass(frozen)]
+#[derive(Debug, Clone, Default)]
+pub struct VoiceHealth {
+    #[pyo3(get)]
+    pub voice_id: String,
+    #[pyo3(get)]
+    pub successes: usize,
+    #[pyo3(get)]
+    pub text_failures: usize,
+    #[pyo3(get)]
+    pub ssml_failures: usize,
+    #[pyo3(get)]
+    pub consecutive_failures: usize,
+    /// Number of failures for each error code
+    #[pyo3(get)]
+    pub error_codes: HashMap<i32, usize>,
+    #[pyo3(get)]
+    pub last_error: Option<String>,
+    /// Why the voice can't be used, if its last failure was for such a reason
+    #[pyo3(get)]
+    pub unavailable: Option<String>,
+}
+
+#[pymethods]
+impl VoiceHealth {
+    #[getter]
+    pub fn failures(&self) -> usize {
+        self.text_failures + self.ssml_failures
+    }
+
+    /// One of `healthy`, `degraded` (fails occasionally), `failing` (fails consistently),
+    /// or `unavailable` (can't be used, e.g. restricted to other applications)
+    #[getter]
+    pub fn status(&self) -> &'static str {
+        if self.unavailable.is_some() {
+            "unavailable"
+        } else if self.consecutive_failures >= FAILING_THRESHOLD {
+            "failing"
+        } else if self.failures() * 10 > self.successes {
+            "degraded"
+        } else {
+            "healthy"
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "VoiceHealth(voice_id={:?}, status={:?}, successes={}, failures={})",
+            self.voice_id,
+            self.status(),
+            self.successes,
+            self.failures()
+        )
+    }
+}
+
+/// Collects the synthesis outcomes of each voice
+#[derive(Default)]
+pub struct VoiceHealthTracker(Mutex<HashMap<String, VoiceHealth>>);
+
+impl VoiceHealthTracker {
+    fn update(&self, voice_id: &str, func: impl FnOnce(&mut VoiceHealth)) {
+        let mut voices = self.0.lock().unwrap();
+        let health = voices
+            .entry(voice_id.to_string())
+            .or_insert_with(|| VoiceHealth {
+                voice_id: voice_id.to_string(),
+                ..Default::default()
+            });
+        func(health);
+    }
+
+    pub fn record_success(&self, voice_id: &str) {
+        self.update(voice_id, |health| {
+            health.successes += 1;
+            health.consecutive_failures = 0;
+            health.unavailable = None;
+        });
+    }
+
+    pub fn record_failure(&self, voice_id: &str, is_ssml: bool, code: i32, message: &str) {
+        self.update(voice_id, |health| {
+            if is_ssml {
+                health.ssml_failures += 1;
+            } else {
+                health.text_failures += 1;
+            }
+            health.consecutive_failures += 1;
+            *health.error_codes.entry(code).or_default() += 1;
+            health.last_error = Some(message.to_string());
+            health.unavailable = voice_unavailable_reason(code).map(str::to_string);
+        });
+    }
+
+    pub fn get(&self, voice_id: &str) -> VoiceHealth {
+        self.0
+            .lock()
+            .unwrap()
+            .get(voice_id)
+            .cloned()
+            .unwrap_or_else(|| VoiceHealth {
+                voice_id: voice_id.to_string(),
+                ..Default::default()
+            })
+    }
+
+    pub fn all(&self) -> Vec<VoiceHealth> {
+        self.0.lock().unwrap().values().cloned().collect()
+    }
+}
@@ -0,0 +1,71 @@
+use pyo3::prelude::*;
+
+/// How closely a language tag matches a requested one
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum LanguageMatch {
+    /// Only the primary language subtag is the same, e.g. `en-GB` and `en-US`
+    Primary,
+    /// Both tags are the same after normalization
+    Exact,
+}
+
+/// Normalize the case and separators of a BCP-47 language tag, e.g. `EN_us` becomes `en-US`
+#[pyfunction]
+#[pyo3(text_signature = "(tag: str) -> str")]
+pub fn normalize_language_tag(tag: &str) -> String {
+    tag.trim()
+        .split(['-', '_'])
+        .filter(|subtag| !subtag.is_empty())
+        .enumerate()
+        .map(|(idx, subtag)| {
+            let is_alpha = subtag.chars().all(|c| c.is_ascii_alphabetic());
+            let is_digit = subtag.chars().all(|c| c.is_ascii_digit());
+            if idx == 0 {
+                subtag.to_ascii_lowercase()
+            } else if subtag.len() == 4 && is_alpha {
+                // Script subtag, e.g. `Latn`
+                let (first, rest) = subtag.split_at(1);
+                first.to_ascii_uppercase() + &rest.to_ascii_lowercase()
+            } else if (subtag.len() == 2 && is_alpha) || (subtag.len() == 3 && is_digit) {
+                // Region subtag, e.g. `GB` or `419`
+                subtag.to_ascii_uppercase()
+            } else {
+                subtag.to_ascii_lowercase()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Get the primary language subtag of a language tag, e.g. `en` for `en-GB`
+#[pyfunction]
+#[pyo3(text_signature = "(tag: str) -> str")]
+pub fn primary_language(tag: &str) -> String {
+    normalize_language_tag(tag)
+        .split('-')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Compare an available language tag against a requested one, falling back from region to language
+pub fn match_language(requested: &str, available: &str) -> Option<LanguageMatch> {
+    let requested = normalize_language_tag(requested);
+    let available = normalize_language_tag(available);
+    if requested.is_empty() || available.is_empty() {
+        None
+    } else if requested == available {
+        Some(LanguageMatch::Exact)
+    } else if primary_language(&requested) == primary_language(&available) {
+        Some(LanguageMatch::Primary)
+    } else {
+        None
+    }
+}
+
+/// Check whether two language tags refer to the same language, ignoring the region
+#[pyfunction]
+#[pyo3(text_signature = "(requested: str, available: str) -> bool")]
+pub fn language_matches(requested: &str, available: &str) -> bool {
+    match_language(requested, available).is_some()
+}
@@ -1,38 +1,147 @@
-use crossbeam_queue::SegQueue;
-use pyo3::exceptions::{PyOSError, PyRuntimeError, PyTypeError};
-use pyo3::intern;
+use acronyms::AcronymFilter;
+use async_ops::AsyncOps;
+use code::CodeBlock;
+use config::{EngineConfig, LogLevel};
+use crossbeam_queue::ArrayQueue;
+use document::DocumentBuilder;
+use estimate::DurationEstimator;
+use fingerprint::{FingerprintMatch, FingerprintStore};
+use focus::AudioFocus;
+use health::{voice_unavailable_reason, VoiceHealth, VoiceHealthTracker};
+use lang::match_language;
+use profile::SpeechProfile;
+use profiler::{Profiler, Stage};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyOSError, PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::pyclass::CompareOp;
+use pyo3::types::{PyDict, PyTuple};
+use quiet::{QueueFullPolicy, QuietHours, QuietMode};
+use reading::ReadingRules;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
+use std::ffi::c_long;
 use std::fmt;
-use std::sync::{Arc, RwLock};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex, OnceLock, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+use symbols::SymbolMode;
+use template::UtteranceTemplate;
+use text::{ListMarker, OffsetMap, PunctuationRuns};
+use tone::Tone;
+use transform::{AudioTransform, PyAudioTransform};
+use voice_cache::VoiceCache;
+use wave::WaveFormat;
 use windows::{
     core::{Interface, HSTRING},
     Foundation::Collections::{CollectionChange, IVectorChangedEventArgs},
     Foundation::Metadata::ApiInformation,
-    Foundation::TypedEventHandler,
+    Foundation::{TimeSpan, TypedEventHandler},
     Media::Core::{MediaCueEventArgs, MediaSource, SpeechCue, TimedMetadataTrack},
     Media::Playback::*,
     Media::SpeechSynthesis::*,
+    Storage::FileProperties::MusicProperties,
     Storage::StorageFile,
     Storage::Streams::InMemoryRandomAccessStream,
 };
 
+pub mod acronyms;
+pub mod async_ops;
+pub mod casting;
+pub mod code;
+pub mod config;
+pub mod document;
+pub mod estimate;
+pub mod fingerprint;
+pub mod focus;
+pub mod health;
+pub mod lang;
+pub mod profile;
+pub mod profiler;
+pub mod quiet;
+pub mod reading;
+pub mod symbols;
+pub mod template;
+pub mod text;
+pub mod tone;
+pub mod transform;
+pub mod voice_cache;
+pub mod wave;
+
 pub type NeosynthResult<T> = Result<T, NeosynthError>;
 pub use NeosynthError::{OperationError, RuntimeError};
 
 #[derive(Debug)]
 pub enum NeosynthError {
-    RuntimeError(String, i32),
+    /// A Windows API call failed
+    RuntimeError(windows::core::Error),
+    /// The operation is not valid with the given arguments or in the current state
     OperationError(String),
+    /// The synthesizer failed to generate speech from text
+    Synthesis(windows::core::Error),
+    /// The synthesizer rejected SSML markup
+    Ssml(windows::core::Error),
+    /// The media player failed to open or play a source
+    Playback(windows::core::Error),
+    /// No installed voice matches, or the voice can't be used
+    Voice(String),
+    /// Reading or writing a file failed, with a description of what was being done
+    Io(String, std::io::Error),
+    /// The installed version of OneCore doesn't support the operation
+    Unsupported(String),
+    /// The operation was abandoned because the synthesizer was closed
+    Cancelled,
+    /// No more utterances can be queued, the maximum number is given
+    QueueFull(usize),
+    /// The voice can't be used in this context, e.g. restricted to other applications or
+    /// locales, nor could any voice of the fallback chain
+    VoiceUnavailable { voice_id: String, reason: String },
+}
+
+impl NeosynthError {
+    /// The underlying Windows error, if the failure came from a Windows API call
+    pub fn windows_error(&self) -> Option<&windows::core::Error> {
+        match self {
+            RuntimeError(e) | Self::Synthesis(e) | Self::Ssml(e) | Self::Playback(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
-impl Error for NeosynthError {}
+impl Error for NeosynthError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(_, e) => Some(e),
+            _ => self.windows_error().map(|e| e as &(dyn Error + 'static)),
+        }
+    }
+}
 
 impl fmt::Display for NeosynthError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let windows_message =
+            |e: &windows::core::Error| format!("{} Code: {}.", e.message(), e.code().0);
         let err_message = match self {
-            RuntimeError(msg, code) => format!("Windows error: {} Code: {}.", msg, code),
+            RuntimeError(e) => format!("Windows error: {}", windows_message(e)),
             OperationError(msg) => format!("Error: {}", msg),
+            Self::Synthesis(e) => format!("Synthesis error: {}", windows_message(e)),
+            Self::Ssml(e) => format!("Invalid SSML: {}", windows_message(e)),
+            Self::Playback(e) => format!("Playback error: {}", windows_message(e)),
+            Self::Voice(msg) => format!("Voice error: {}", msg),
+            Self::Io(msg, e) => format!("{}: {}", msg, e),
+            Self::Unsupported(msg) => format!("Not supported: {}", msg),
+            Self::Cancelled => "The operation was cancelled".to_string(),
+            Self::VoiceUnavailable { voice_id, reason } => {
+                format!("Voice {} is unavailable: {}", voice_id, reason)
+            }
+            Self::QueueFull(max) => {
+                format!("The queue is full, it holds at most {} utterances", max)
+            }
         };
         write!(f, "{}", err_message)
     }
@@ -40,15 +149,37 @@ impl fmt::Display for NeosynthError {
 
 impl From<windows::core::Error> for NeosynthError {
     fn from(error: windows::core::Error) -> Self {
-        RuntimeError(error.message().to_string(), error.code().0)
+        RuntimeError(error)
     }
 }
 
+create_exception!(
+    neosynth,
+    QueueFullError,
+    PyRuntimeError,
+    "Raised by `speak` when the queue of deferred utterances is full"
+);
+
+create_exception!(
+    neosynth,
+    VoiceUnavailableError,
+    PyRuntimeError,
+    "Raised when the voice can't be used in this context and no fallback voice could speak"
+);
+
 impl From<NeosynthError> for PyErr {
     fn from(error: NeosynthError) -> Self {
+        if let Some(e) = error.windows_error() {
+            return PyOSError::new_err((e.message().to_string(), e.code().0));
+        }
         match error {
-            RuntimeError(msg, code) => PyOSError::new_err((msg, code)),
             OperationError(msg) => PyRuntimeError::new_err(msg),
+            NeosynthError::Io(..) => PyOSError::new_err(error.to_string()),
+            NeosynthError::QueueFull(_) => QueueFullError::new_err(error.to_string()),
+            NeosynthError::VoiceUnavailable { .. } => {
+                VoiceUnavailableError::new_err(error.to_string())
+            }
+            _ => PyRuntimeError::new_err(error.to_string()),
         }
     }
 }
@@ -74,6 +205,37 @@ impl SynthState {
     }
 }
 
+/// Why the state of the synthesizer changed
+#[pyclass]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum StateChangeReason {
+    /// Speech was requested
+    Speak = 0,
+    /// Audio started playing after buffering
+    PlaybackStarted = 1,
+    /// Speech was paused on request
+    UserPause = 2,
+    /// Speech was resumed on request
+    UserResume = 3,
+    /// The reading limit was reached
+    ReadingLimit = 4,
+    /// Every element of the utterance was played
+    MediaEnded = 5,
+    /// Speech could not be generated
+    Error = 6,
+    /// Speech was stopped on request
+    Stop = 7,
+    /// Speech moved to another element on request, with `next` or `previous`
+    Skip = 8,
+}
+
+#[pymethods]
+impl StateChangeReason {
+    fn __hash__(&self) -> isize {
+        *self as isize
+    }
+}
+
 impl From<MediaPlaybackState> for SynthState {
     fn from(player_state: MediaPlaybackState) -> Self {
         match player_state {
@@ -86,35 +248,427 @@ impl From<MediaPlaybackState> for SynthState {
     }
 }
 
+/// What happens to an utterance requested while speech is paused
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub enum PausedSpeechMode {
+    /// Speak it right away, leaving the paused state
+    #[default]
+    Resume,
+    /// Keep it paused and speak it in place of the paused utterance on resume
+    Queue,
+    /// Like `Queue`, also calling `on_speech_while_paused`
+    Warn,
+    /// Refuse to speak it
+    Raise,
+}
+
+impl FromStr for PausedSpeechMode {
+    type Err = NeosynthError;
+
+    fn from_str(mode: &str) -> NeosynthResult<Self> {
+        match mode.to_ascii_lowercase().as_str() {
+            "resume" => Ok(PausedSpeechMode::Resume),
+            "queue" => Ok(PausedSpeechMode::Queue),
+            "warn" => Ok(PausedSpeechMode::Warn),
+            "raise" => Ok(PausedSpeechMode::Raise),
+            _ => Err(OperationError(format!(
+                "Invalid paused speech mode: {}",
+                mode
+            ))),
+        }
+    }
+}
+
+/// How an utterance is spoken along with the others, like SAPI's priorities
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub enum SpeechPriority {
+    /// Speak it in place of the current utterance
+    #[default]
+    Normal,
+    /// Interrupt normal speech, which resumes from the interrupted element once the
+    /// alerts are spoken. Alerts requested during an alert are spoken after it.
+    Alert,
+    /// Speak it on top of other speech, without interrupting it or reporting events
+    Over,
+}
+
+impl FromStr for SpeechPriority {
+    type Err = NeosynthError;
+
+    fn from_str(priority: &str) -> NeosynthResult<Self> {
+        match priority.to_ascii_lowercase().as_str() {
+            "normal" => Ok(SpeechPriority::Normal),
+            "alert" => Ok(SpeechPriority::Alert),
+            "over" => Ok(SpeechPriority::Over),
+            _ => Err(OperationError(format!(
+                "Invalid speech priority: {}",
+                priority
+            ))),
+        }
+    }
+}
+
+/// The role of a piece of text in a structured utterance
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TextRole {
+    Heading,
+    Paragraph,
+    /// Text following a bookmark marking a place of interest
+    Landmark,
+}
+
 #[derive(Clone)]
 pub enum SpeechElement {
     Text(String),
     Ssml(String),
     Bookmark(String),
     Audio(String),
+    /// Audio played over and over until skipped with `next` or stopped
+    LoopedAudio(String),
+    /// Switch the voice used for the elements that follow
+    Voice(VoiceInformation),
+    /// A voice switch made by the engine rather than asked for, e.g. by echo mode,
+    /// announced by the voice change cue
+    AutoVoice(VoiceInformation),
+    /// Speech synthesized ahead of time by `prefetch`
+    Synthesized(SpeechSynthesisStream),
+    /// Apply a speech profile to the elements that follow
+    Profile(SpeechProfile),
+    /// An element which may be dropped in skim mode, e.g. a decorative earcon
+    Skippable(Box<SpeechElement>),
+    /// Text with a structural role, used to preview an utterance
+    Structured(TextRole, String),
+    /// A bookmark and the text following it, spoken as SSML so that the bookmark
+    /// is reached when its audio plays
+    MarkedText(String, String),
+    /// Acronym expansions applying to the rest of the utterance
+    Acronyms(HashMap<String, String>),
+    /// A tone of voice applying to the following elements of the utterance
+    Tone(Tone),
+    /// Source code, read slowly with its symbols named
+    Code(CodeBlock),
+}
+
+impl SpeechElement {
+    /// The name of this kind of element reported to the event sink
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SpeechElement::Text(_) | SpeechElement::MarkedText(..) => "text",
+            SpeechElement::Ssml(_) => "ssml",
+            SpeechElement::Bookmark(_) => "bookmark",
+            SpeechElement::Audio(_) | SpeechElement::LoopedAudio(_) => "audio",
+            SpeechElement::Voice(_) | SpeechElement::AutoVoice(_) => "voice",
+            SpeechElement::Synthesized(_) => "synthesized",
+            SpeechElement::Profile(_) => "profile",
+            SpeechElement::Acronyms(_) => "acronyms",
+            SpeechElement::Tone(_) => "tone",
+            SpeechElement::Code(_) => "code",
+            SpeechElement::Skippable(element) => element.kind(),
+            SpeechElement::Structured(TextRole::Heading, _) => "heading",
+            SpeechElement::Structured(TextRole::Paragraph, _) => "paragraph",
+            SpeechElement::Structured(TextRole::Landmark, _) => "landmark",
+        }
+    }
+
+    /// Whether the element is heard, as opposed to marking a position or changing settings
+    pub fn is_audible(&self) -> bool {
+        match self {
+            SpeechElement::Text(_)
+            | SpeechElement::Ssml(_)
+            | SpeechElement::Audio(_)
+            | SpeechElement::LoopedAudio(_)
+            | SpeechElement::Synthesized(_)
+            | SpeechElement::Structured(..)
+            | SpeechElement::MarkedText(..)
+            | SpeechElement::Code(_) => true,
+            SpeechElement::Skippable(element) => element.is_audible(),
+            SpeechElement::Bookmark(_)
+            | SpeechElement::Voice(_)
+            | SpeechElement::AutoVoice(_)
+            | SpeechElement::Profile(_)
+            | SpeechElement::Acronyms(_)
+            | SpeechElement::Tone(_) => false,
+        }
+    }
+}
+
+/// Number of characters of text shown in the preview of a queued element
+const QUEUE_PREVIEW_LENGTH: usize = 60;
+
+/// An element waiting to be spoken, as listed by `Neosynth.get_queue`
+#[pyclass(frozen)]
+#[derive(Debug, Clone)]
+pub struct QueuedElement {
+    /// Kind of the element, as reported to `on_element_started`
+    #[pyo3(get)]
+    pub kind: String,
+    /// The beginning of the text, the path of an audio file or the name of a voice
+    #[pyo3(get)]
+    pub preview: Option<String>,
+    /// Name of the bookmark reached by the element
+    #[pyo3(get)]
+    pub bookmark: Option<String>,
+}
+
+/// What makes an element identical to another when deduplicating utterances, None for
+/// elements which can't be compared
+fn dedup_content(element: &SpeechElement) -> Option<String> {
+    let content = match element {
+        SpeechElement::Text(text)
+        | SpeechElement::Ssml(text)
+        | SpeechElement::Structured(_, text)
+        | SpeechElement::Bookmark(text)
+        | SpeechElement::Audio(text)
+        | SpeechElement::LoopedAudio(text) => text.clone(),
+        SpeechElement::MarkedText(mark, text) => format!("{}\0{}", mark, text),
+        SpeechElement::Code(block) => block.code.clone(),
+        SpeechElement::Tone(tone) => tone.name().to_string(),
+        SpeechElement::Skippable(element) => dedup_content(element)?,
+        SpeechElement::Voice(_)
+        | SpeechElement::AutoVoice(_)
+        | SpeechElement::Synthesized(_)
+        | SpeechElement::Profile(_)
+        | SpeechElement::Acronyms(_) => return None,
+    };
+    Some(content)
+}
+
+impl From<&SpeechElement> for QueuedElement {
+    fn from(element: &SpeechElement) -> Self {
+        let text_preview =
+            |text: &str| Some(text.trim().chars().take(QUEUE_PREVIEW_LENGTH).collect());
+        let (preview, bookmark) = match element {
+            SpeechElement::Text(text) | SpeechElement::Structured(_, text) => {
+                (text_preview(text), None)
+            }
+            SpeechElement::Ssml(ssml) => (text_preview(&text::strip_tags(ssml)), None),
+            SpeechElement::Code(block) => (text_preview(&block.code), None),
+            SpeechElement::MarkedText(mark, text) => (text_preview(text), Some(mark.clone())),
+            SpeechElement::Bookmark(mark) => (None, Some(mark.clone())),
+            SpeechElement::Audio(path) | SpeechElement::LoopedAudio(path) => {
+                (Some(path.clone()), None)
+            }
+            SpeechElement::Voice(voice) | SpeechElement::AutoVoice(voice) => {
+                (voice.DisplayName().ok().map(|name| name.to_string()), None)
+            }
+            SpeechElement::Skippable(element) => {
+                let inner = Self::from(element.as_ref());
+                (inner.preview, inner.bookmark)
+            }
+            SpeechElement::Tone(tone) => (Some(tone.name().to_string()), None),
+            SpeechElement::Synthesized(_)
+            | SpeechElement::Profile(_)
+            | SpeechElement::Acronyms(_) => (None, None),
+        };
+        Self {
+            kind: element.kind().to_string(),
+            preview,
+            bookmark,
+        }
+    }
 }
 
 #[pyclass(subclass)]
 #[derive(Default, Clone)]
 pub struct SpeechUtterance(Vec<SpeechElement>);
 
+impl SpeechUtterance {
+    /// Add an element, skippable elements are dropped while the mixer is in skim mode
+    fn push(&mut self, element: SpeechElement, skippable: bool) {
+        if skippable {
+            self.0.push(SpeechElement::Skippable(Box::new(element)));
+        } else {
+            self.0.push(element);
+        }
+    }
+
+    /// Add the rows of a table as sentences, each preceded by a bookmark named after
+    /// `bookmark_prefix` and the row number if given
+    fn push_table(
+        &mut self,
+        rows: &[Vec<String>],
+        headers: &[String],
+        cell_separator: &str,
+        row_separator: &str,
+        bookmark_prefix: Option<&str>,
+    ) {
+        for (idx, cells) in rows.iter().enumerate() {
+            if let Some(prefix) = bookmark_prefix {
+                self.0
+                    .push(SpeechElement::Bookmark(format!("{}{}", prefix, idx + 1)));
+            }
+            let mut row = text::linearize_table_row(idx + 1, cells, headers, cell_separator);
+            row.push_str(row_separator);
+            self.0.push(SpeechElement::Text(row));
+        }
+    }
+
+    /// Add the items of a list, each preceded by a bookmark named after `bookmark_prefix`
+    /// and the item number if given. The items of an ordered list are read with their
+    /// number. When announced, the list starts with its number of items and the items of
+    /// an unordered list with a bullet.
+    fn push_list(
+        &mut self,
+        items: &[String],
+        ordered: bool,
+        announce: bool,
+        bookmark_prefix: Option<&str>,
+    ) {
+        if announce {
+            let count = match items.len() {
+                1 => "1 item".to_string(),
+                count => format!("{} items", count),
+            };
+            self.0
+                .push(SpeechElement::Text(format!("List of {}.", count)));
+        }
+        for (idx, item) in items.iter().enumerate() {
+            if let Some(prefix) = bookmark_prefix {
+                self.0
+                    .push(SpeechElement::Bookmark(format!("{}{}", prefix, idx + 1)));
+            }
+            let marker = match (ordered, announce) {
+                (true, _) => ListMarker::Number(idx + 1),
+                (false, true) => ListMarker::Bullet,
+                (false, false) => ListMarker::None,
+            };
+            self.0
+                .push(SpeechElement::Text(text::list_item_text(marker, item)));
+        }
+    }
+}
+
 #[pymethods]
 impl SpeechUtterance {
     #[new]
     pub fn new() -> Self {
         Default::default()
     }
+    #[pyo3(text_signature = "($self, text: str, skippable: bool = False)")]
+    #[args(skippable = "false")]
+    fn add_text(&mut self, text: String, skippable: bool) {
+        self.push(SpeechElement::Text(text), skippable);
+    }
+    #[pyo3(text_signature = "($self, ssml: str, skippable: bool = False)")]
+    #[args(skippable = "false")]
+    fn add_ssml(&mut self, ssml: String, skippable: bool) {
+        self.push(SpeechElement::Ssml(ssml), skippable);
+    }
+    /// Add an audio file, played `repeat` more times or, if `forever` is true, over and
+    /// over until skipped with `next` or stopped
+    #[pyo3(
+        text_signature = "($self, audio_path: str, skippable: bool = False, repeat: int = 0, forever: bool = False)"
+    )]
+    #[args(skippable = "false", repeat = "0", forever = "false")]
+    fn add_audio(&mut self, audio_path: String, skippable: bool, repeat: usize, forever: bool) {
+        if forever {
+            self.push(SpeechElement::LoopedAudio(audio_path), skippable);
+            return;
+        }
+        for _ in 0..repeat {
+            self.push(SpeechElement::Audio(audio_path.clone()), skippable);
+        }
+        self.push(SpeechElement::Audio(audio_path), skippable);
+    }
+    #[pyo3(text_signature = "($self, bookmark: str)")]
+    fn add_bookmark(&mut self, bookmark: String) {
+        self.0.push(SpeechElement::Bookmark(bookmark));
+    }
+    /// Add a heading, always spoken in preview mode
+    #[pyo3(text_signature = "($self, text: str)")]
+    fn add_heading(&mut self, text: String) {
+        self.0
+            .push(SpeechElement::Structured(TextRole::Heading, text));
+    }
+    /// Add a paragraph, only its first sentence is spoken in preview mode
     #[pyo3(text_signature = "($self, text: str)")]
-    fn add_text(&mut self, text: String) {
-        self.0.push(SpeechElement::Text(text));
+    fn add_paragraph(&mut self, text: String) {
+        self.0
+            .push(SpeechElement::Structured(TextRole::Paragraph, text));
+    }
+    /// Add a bookmark followed by text which is always spoken in preview mode
+    #[pyo3(text_signature = "($self, bookmark: str, text: str)")]
+    fn add_landmark(&mut self, bookmark: String, text: String) {
+        self.0.push(SpeechElement::Bookmark(bookmark));
+        self.0
+            .push(SpeechElement::Structured(TextRole::Landmark, text));
+    }
+    /// Apply a speech profile to the elements added after it
+    #[pyo3(text_signature = "($self, profile: neosynth.SpeechProfile)")]
+    fn add_profile(&mut self, profile: SpeechProfile) {
+        self.0.push(SpeechElement::Profile(profile));
     }
-    #[pyo3(text_signature = "($self, ssml: str)")]
-    fn add_ssml(&mut self, ssml: String) {
-        self.0.push(SpeechElement::Ssml(ssml));
+    /// Read the acronyms in the following text as given, e.g. `{"SQL": "sequel"}`,
+    /// rather than as set with `Neosynth.set_acronym_filter`
+    #[pyo3(text_signature = "($self, expansions: dict[str, str])")]
+    fn add_acronyms(&mut self, expansions: HashMap<String, String>) {
+        self.0.push(SpeechElement::Acronyms(expansions));
     }
-    #[pyo3(text_signature = "($self, audio_path: str)")]
-    fn add_audio(&mut self, audio_path: String) {
-        self.0.push(SpeechElement::Audio(audio_path));
+    /// Speak the elements added after it with a tone of voice: `whisper`, `announcement`
+    /// or `normal`. Add it first to set the tone of the whole utterance, or around an
+    /// element to change the tone of that element only.
+    #[pyo3(text_signature = "($self, tone: str)")]
+    fn add_tone(&mut self, tone: &str) -> NeosynthResult<()> {
+        self.0.push(SpeechElement::Tone(tone.parse()?));
+        Ok(())
+    }
+    /// Add source code, read slower than text with its symbols named. Changes of
+    /// indentation are announced if `language` is one where it matters, e.g. `python`
+    #[pyo3(text_signature = "($self, text: str, language: str = None, line_numbers: bool = False)")]
+    #[args(language = "None", line_numbers = "false")]
+    fn add_code(&mut self, text: String, language: Option<String>, line_numbers: bool) {
+        self.0.push(SpeechElement::Code(CodeBlock {
+            code: text,
+            language,
+            line_numbers,
+        }));
+    }
+    /// Add a table, each row read as a sentence: `Row 2, Price: 10 dollars`.
+    /// `row_separator` ends each row, and if `bookmark_prefix` is given, a bookmark named
+    /// after it and the row number, e.g. `table1_row2`, precedes each row.
+    #[pyo3(
+        text_signature = "($self, rows: list[list[str]], headers: list[str] = None, cell_separator: str = \", \", row_separator: str = \".\", bookmark_prefix: str = None)"
+    )]
+    #[args(
+        headers = "None",
+        cell_separator = "\", \"",
+        row_separator = "\".\"",
+        bookmark_prefix = "None"
+    )]
+    fn add_table(
+        &mut self,
+        rows: Vec<Vec<String>>,
+        headers: Option<Vec<String>>,
+        cell_separator: &str,
+        row_separator: &str,
+        bookmark_prefix: Option<&str>,
+    ) {
+        let headers = headers.unwrap_or_default();
+        self.push_table(
+            &rows,
+            &headers,
+            cell_separator,
+            row_separator,
+            bookmark_prefix,
+        );
+    }
+    /// Add a list, each item read as a sentence, the items of an `ordered` list with their
+    /// number. When `announce` is true, the number of items is read first and the items
+    /// of an unordered list are introduced by a bullet.
+    /// If `bookmark_prefix` is given, a bookmark named after it and the item number,
+    /// e.g. `steps3`, precedes each item.
+    #[pyo3(
+        text_signature = "($self, items: list[str], ordered: bool = False, announce: bool = True, bookmark_prefix: str = None)"
+    )]
+    #[args(ordered = "false", announce = "true", bookmark_prefix = "None")]
+    fn add_list(
+        &mut self,
+        items: Vec<String>,
+        ordered: bool,
+        announce: bool,
+        bookmark_prefix: Option<&str>,
+    ) {
+        self.push_list(&items, ordered, announce, bookmark_prefix);
     }
     #[pyo3(text_signature = "($self, utterance: neosynth.SpeechUtterance)")]
     fn add_utterance(&mut self, utterance: &mut Self) {
@@ -131,7 +685,78 @@ pub struct VoiceInfo {
     pub language: String,
     #[pyo3(get)]
     pub name: String,
+    /// `male` or `female`
+    #[pyo3(get)]
+    pub gender: String,
     voice: VoiceInformation,
+    /// Audio format and content type of the speech synthesized with this voice
+    format: OnceLock<(WaveFormat, String)>,
+}
+
+impl VoiceInfo {
+    /// Get the native audio format of this voice, synthesizing a short silence to find it out
+    pub fn get_audio_format(&self) -> NeosynthResult<WaveFormat> {
+        Ok(self.probe_audio()?.0)
+    }
+
+    fn probe_audio(&self) -> NeosynthResult<&(WaveFormat, String)> {
+        if let Some(format) = self.format.get() {
+            return Ok(format);
+        }
+        let cache = voice_cache();
+        if let Some(cached) = cache.and_then(|cache| cache.get(&self.id)) {
+            return Ok(self.format.get_or_init(|| cached));
+        }
+        let synthesizer = SpeechSynthesizer::new()?;
+        synthesizer.SetVoice(&self.voice)?;
+        let ssml = format!(
+            r#"<speak version="1.0" xmlns="http://www.w3.org/2001/10/synthesis" xml:lang="{}"><break time="1ms"/></speak>"#,
+            self.language
+        );
+        // Probing isn't tied to an engine, so nothing cancels these operations
+        let ops = AsyncOps::default();
+        let stream = synthesize(&synthesizer, &ssml, true, &ops)?;
+        let input = stream.GetInputStreamAt(0)?;
+        let format = WaveFormat::from_stream(&input, &ops)?;
+        let content_type =
+            wave::stream_content_type(&stream.ContentType()?.to_string(), &input, &ops)?;
+        if let Some(cache) = cache {
+            cache.insert(&self.id, format, &content_type);
+        }
+        Ok(self.format.get_or_init(|| (format, content_type)))
+    }
+}
+
+/// The voice cache shared by the whole process, None if the installed voices can't be listed
+fn voice_cache() -> Option<&'static VoiceCache> {
+    static CACHE: OnceLock<VoiceCache> = OnceLock::new();
+    if let Some(cache) = CACHE.get() {
+        return Some(cache);
+    }
+    let ids = installed_voice_ids().ok()?;
+    Some(CACHE.get_or_init(|| VoiceCache::load(ids.iter().map(String::as_str))))
+}
+
+fn installed_voice_ids() -> windows::core::Result<Vec<String>> {
+    SpeechSynthesizer::AllVoices()?
+        .into_iter()
+        .map(|voice| voice.Id().map(|id| id.to_string()))
+        .collect()
+}
+
+#[pymethods]
+impl VoiceInfo {
+    /// The native sample rate of this voice in Hz. OneCore voices carry no other hint of
+    /// their audio quality, the token name of every voice ends in `M`.
+    #[getter]
+    pub fn sample_rate(&self) -> NeosynthResult<u32> {
+        Ok(self.get_audio_format()?.sample_rate)
+    }
+    /// The content type of the audio synthesized with this voice, e.g. `audio/wav`
+    #[getter]
+    pub fn content_type(&self) -> NeosynthResult<String> {
+        Ok(self.probe_audio()?.1.clone())
+    }
 }
 
 impl From<VoiceInformation> for VoiceInfo {
@@ -140,7 +765,13 @@ impl From<VoiceInformation> for VoiceInfo {
             id: vinfo.Id().unwrap().to_string(),
             language: vinfo.Language().unwrap().to_string(),
             name: vinfo.DisplayName().unwrap().to_string(),
+            gender: match vinfo.Gender().unwrap() {
+                VoiceGender::Female => "female",
+                _ => "male",
+            }
+            .to_string(),
             voice: vinfo,
+            format: OnceLock::new(),
         }
     }
 }
@@ -152,276 +783,4421 @@ impl From<&VoiceInfo> for VoiceInformation {
 }
 
 pub trait NsEventSink {
-    fn on_state_changed(&self, new_state: SynthState);
+    fn on_state_changed(
+        &self,
+        old_state: SynthState,
+        new_state: SynthState,
+        reason: StateChangeReason,
+    );
     fn on_bookmark_reached(&self, bookmark: String);
     fn log(&self, message: &str, level: &str);
+    /// The reading limit set with `set_reading_limit` was reached and speech was paused
+    fn on_reading_limit_reached(&self) {}
+    /// A set of settings was applied at once with `apply_settings`
+    fn on_settings_changed(&self, _settings: &EngineConfig) {}
+    /// The player started or finished buffering audio, or playback stalled or resumed
+    fn on_buffering(&self, _is_buffering: bool) {}
+    /// A word is about to be spoken, `offset` and `length` locate it in the element's text
+    /// in UTF-16 code units, `audio_time` is its start in seconds into the element's audio
+    fn on_word_reached(&self, _offset: usize, _length: usize, _audio_time: f64) {}
+    /// A sentence is about to be spoken, located like the words of `on_word_reached`
+    fn on_sentence_reached(&self, _offset: usize, _length: usize, _audio_time: f64) {}
+    /// An element of the utterance started being processed
+    fn on_element_started(&self, _index: usize, _kind: &str) {}
+    /// An element of the utterance finished or was interrupted after `duration` seconds
+    fn on_element_finished(&self, _index: usize, _kind: &str, _duration: f64) {}
+    /// The utterance with the id returned by `speak` started being spoken
+    fn on_utterance_started(&self, _id: u64) {}
+    /// The utterance with the given id was entirely spoken or was interrupted
+    fn on_utterance_finished(&self, _id: u64) {}
+    /// The player failed to play an element, which was skipped. `element_index` is None
+    /// if the failure happened outside of an utterance.
+    fn on_error(&self, _element_index: Option<usize>, _code: i32, _message: &str) {}
+    /// The audio file of an audio element started playing
+    fn on_audio_started(&self, _path: &str) {}
+    /// Another application holds the audio device exclusively. If `retrying` the element
+    /// is played again once the retry interval elapsed, otherwise it is skipped.
+    fn on_device_busy(&self, _retrying: bool) {}
+    /// The audio file of an audio element finished playing or was interrupted
+    fn on_audio_finished(&self, _path: &str) {}
+    /// Periodic report of the position in and the duration of the element being played,
+    /// in milliseconds
+    fn on_progress(&self, _position_ms: u64, _duration_ms: u64) {}
+    /// The player has been playing silence for `seconds`, which some voices produce when they get stuck
+    fn on_silence_detected(&self, _seconds: f64) {}
+    /// An error which couldn't be returned to any caller, e.g. one raised while handling
+    /// a player event or by another event handler, was ignored
+    fn on_internal_error(&self, _message: &str) {}
+    /// Voices were installed or uninstalled, given by their ids
+    fn on_voices_changed(&self, _added: Vec<String>, _removed: Vec<String>) {}
+    /// The utterance with the given id was kept for after quiet hours, or for when another
+    /// process releases the audio focus, instead of spoken
+    fn on_speech_deferred(&self, _id: u64) {}
+    /// Quiet hours started or ended
+    fn on_quiet_hours(&self, _active: bool) {}
+    /// The utterance with the given id was requested while paused, it is spoken on resume
+    fn on_speech_while_paused(&self, _id: u64) {}
+    /// The sleep timer stopped playback
+    fn on_sleep_timer_elapsed(&self) {}
+    /// The voice with id `voice_id` can't be used for `reason`, `fallback_id` speaks instead
+    fn on_voice_fallback(&self, _voice_id: &str, _fallback_id: &str, _reason: &str) {}
+    /// The audio synthesized for `text` differs from what the voice with id `voice_id`
+    /// produced before with the same settings, as recorded in the fingerprint file
+    fn on_synthesis_changed(&self, _voice_id: &str, _text: &str) {}
+    /// Stop delivering events, once this returns no further callbacks are invoked
+    fn close(&self) {}
+}
+
+thread_local! {
+    /// Set while a Python callback is running on the current thread
+    static IN_PY_CALLBACK: Cell<bool> = const { Cell::new(false) };
 }
 
-pub struct PyEventSinkWrapper(PyObject);
+/// Number of consecutive callback failures after which the event sink is suspended by default
+const DEFAULT_SINK_FAILURE_LIMIT: usize = 10;
+/// How often the watchdog checks for callbacks running past their budget
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(10);
 
-impl PyEventSinkWrapper {
+/// Python thread ident and deadline of each callback the watchdog may cancel
+type WatchedCallbacks = Mutex<Vec<(c_long, Instant)>>;
+
+/// Delivers events to the Python listeners, owned by the thread dispatching the events
+struct EventDispatcher {
+    /// The event sink followed by the added listeners.
+    /// Callbacks hold the read lock while running, closing takes the write lock
+    listeners: RwLock<Option<Mutex<Vec<Arc<Listener>>>>>,
+    closed: AtomicBool,
+    /// Consecutive failures after which a listener is suspended, 0 means never suspend
+    failure_limit: AtomicUsize,
+    /// Time a callback may run before it is reported as slow
+    callback_budget: RwLock<Option<Duration>>,
+    /// Raise TimeoutError in callbacks that exceed their budget
+    cancel_slow_callbacks: AtomicBool,
+    slow_callbacks: AtomicUsize,
+    watched_callbacks: Arc<WatchedCallbacks>,
+    watchdog: OnceLock<()>,
+}
+
+impl EventDispatcher {
     fn new(py_event_sink: PyObject) -> Self {
-        Self(py_event_sink)
+        Self {
+            listeners: RwLock::new(Some(Mutex::new(vec![Arc::new(Listener::new(
+                py_event_sink,
+                true,
+            ))]))),
+            closed: AtomicBool::new(false),
+            failure_limit: AtomicUsize::new(DEFAULT_SINK_FAILURE_LIMIT),
+            callback_budget: RwLock::new(None),
+            cancel_slow_callbacks: AtomicBool::new(false),
+            slow_callbacks: AtomicUsize::new(0),
+            watched_callbacks: Arc::new(Mutex::new(Vec::new())),
+            watchdog: OnceLock::new(),
+        }
     }
-}
 
-impl NsEventSink for PyEventSinkWrapper {
-    fn on_state_changed(&self, new_state: SynthState) {
-        Python::with_gil(|py| {
-            self.0
-                .call_method1(py, "on_state_changed", (new_state,))
-                .ok();
-        });
+    /// Report callbacks running longer than `budget`, and optionally interrupt them
+    pub fn set_callback_budget(&self, budget: Option<Duration>, cancel: bool) {
+        *self.callback_budget.write().unwrap() = budget;
+        self.cancel_slow_callbacks
+            .store(budget.is_some() && cancel, Ordering::SeqCst);
+        if budget.is_some() && cancel {
+            self.watchdog
+                .get_or_init(|| Self::spawn_watchdog(Arc::downgrade(&self.watched_callbacks)));
+        }
     }
-    fn on_bookmark_reached(&self, bookmark: String) {
-        Python::with_gil(|py| {
-            self.0
-                .call_method1(py, "on_bookmark_reached", (bookmark,))
-                .ok();
-        });
+
+    /// Number of callbacks which exceeded their time budget
+    pub fn slow_callback_count(&self) -> usize {
+        self.slow_callbacks.load(Ordering::SeqCst)
     }
-    fn log(&self, message: &str, level: &str) {
-        Python::with_gil(|py| {
-            self.0.call_method1(py, "log", (message, level)).ok();
+
+    /// Interrupt watched callbacks past their deadline by raising TimeoutError in their thread
+    fn spawn_watchdog(watched: Weak<WatchedCallbacks>) {
+        thread::spawn(move || loop {
+            thread::sleep(WATCHDOG_INTERVAL);
+            let Some(watched) = watched.upgrade() else {
+                break;
+            };
+            let now = Instant::now();
+            if !watched
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|(_, deadline)| *deadline <= now)
+            {
+                continue;
+            }
+            // Callbacks stop being watched while holding the GIL, so an exception
+            // is never raised in a thread which already left its callback
+            Python::with_gil(|_py| {
+                watched.lock().unwrap().retain(|(ident, deadline)| {
+                    if *deadline > now {
+                        return true;
+                    }
+                    unsafe {
+                        pyo3::ffi::PyThreadState_SetAsyncExc(*ident, pyo3::ffi::PyExc_TimeoutError);
+                    }
+                    false
+                });
+            });
         });
     }
-}
 
-pub fn register_event_sink<T>(item: &MediaPlaybackItem, event_sink: &Arc<T>) -> NeosynthResult<()>
-where
-    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
-{
-    let timed_metadata_tracks = item.TimedMetadataTracks()?;
-    for (idx, track) in timed_metadata_tracks.into_iter().enumerate() {
-        if track.Id()? == "SpeechBookmark" {
-            register_bookmark_track(item, idx.try_into().unwrap(), event_sink)?
-        };
+    fn current_thread_ident(py: Python<'_>) -> PyResult<c_long> {
+        let ident: u64 = py
+            .import("threading")?
+            .getattr("get_ident")?
+            .call0()?
+            .extract()?;
+        Ok(ident as c_long)
     }
-    Ok(())
-}
-
-pub fn register_bookmark_track<T>(
-    item: &MediaPlaybackItem,
-    idx: u32,
-    event_sink: &Arc<T>,
-) -> NeosynthResult<()>
-where
-    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
-{
-    item.TimedMetadataTracks()?.SetPresentationMode(
-        idx,
-        TimedMetadataTrackPresentationMode::ApplicationPresented,
-    )?;
-    let sink = Arc::clone(event_sink);
-    item.TimedMetadataTracks()?
-        .GetAt(idx)?
-        .CueEntered(
-            &TypedEventHandler::<TimedMetadataTrack, MediaCueEventArgs>::new(
-                move |_, event_args| {
-                    if let Some(event_args) = event_args {
-                        let speech_cue: Result<SpeechCue, _> = event_args.Cue()?.cast();
-                        sink.on_bookmark_reached(speech_cue?.Text()?.to_string_lossy());
-                    };
-                    Ok(())
-                },
-            ),
-        )?;
-    Ok(())
-}
-
-pub struct NeoMediaPlayer<T>(MediaPlayer, Arc<T>);
 
-impl<T> NeoMediaPlayer<T>
-where
-    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
-{
-    fn new(event_sink: T) -> NeosynthResult<Self> {
-        let win_player = MediaPlayer::new()?;
-        win_player.SetRealTimePlayback(true)?;
-        win_player.SetAudioCategory(MediaPlayerAudioCategory::Speech)?;
-        Ok(Self(win_player, Arc::new(event_sink)))
+    fn warn(py: Python<'_>, message: &str) {
+        if let Ok(category) = py
+            .import("builtins")
+            .and_then(|m| m.getattr("RuntimeWarning"))
+        {
+            PyErr::warn(py, category, message, 0).ok();
+        }
     }
-    pub fn get_playback_state(&self) -> NeosynthResult<MediaPlaybackState> {
-        Ok(self.0.PlaybackSession()?.PlaybackState()?)
+
+    pub fn set_failure_limit(&self, limit: usize) {
+        self.failure_limit.store(limit, Ordering::SeqCst);
     }
-    pub fn get_volume(&self) -> NeosynthResult<f64> {
-        Ok(self.0.Volume()? * 100f64)
+
+    /// Whether the event sink was suspended, the listeners added to it are suspended
+    /// on their own
+    pub fn is_suspended(&self) -> bool {
+        let listeners = self.listeners.read().unwrap();
+        listeners.as_ref().is_some_and(|listeners| {
+            listeners
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|listener| listener.is_sink && listener.suspended.load(Ordering::SeqCst))
+        })
     }
-    pub fn set_volume(&self, volume: f64) -> NeosynthResult<()> {
-        Ok(self.0.SetVolume(volume / 100f64)?)
+
+    /// Deliver events again to the sink and the listeners which were suspended
+    pub fn resume(&self) {
+        if let Some(listeners) = self.listeners.read().unwrap().as_ref() {
+            for listener in listeners.lock().unwrap().iter() {
+                listener.consecutive_failures.store(0, Ordering::SeqCst);
+                listener.suspended.store(false, Ordering::SeqCst);
+            }
+        }
     }
-    fn set_speech_stream_source(&self, stream: SpeechSynthesisStream) -> NeosynthResult<()> {
-        let _source = MediaSource::CreateFromStream(&stream, &stream.ContentType()?)?;
-        let item = MediaPlaybackItem::Create(&_source)?;
-        let evtsink = Arc::clone(&self.1);
-        // Register events in existing TimedMetadataTracks
-        register_event_sink(&item, &evtsink)?;
-        // Register events for future tracks
-        let evtsink = Arc::clone(&self.1);
-        item.TimedMetadataTracksChanged(&TypedEventHandler::<
-            MediaPlaybackItem,
-            IVectorChangedEventArgs,
-        >::new(move |item, args| {
-            if let Some(item) = item {
-                if let Some(args) = args {
-                    if args.CollectionChange()? == CollectionChange::ItemInserted {
-                        register_bookmark_track(item, args.Index()?, &evtsink).ok();
-                    } else if args.CollectionChange()? == CollectionChange::Reset {
-                        register_event_sink(item, &evtsink).ok();
-                    };
-                }
+
+    fn handle_callback_result(
+        &self,
+        py: Python<'_>,
+        listener: &Listener,
+        name: &str,
+        result: PyResult<PyObject>,
+    ) -> Option<PyErr> {
+        let error = match result {
+            Ok(_) => {
+                listener.consecutive_failures.store(0, Ordering::SeqCst);
+                return None;
             }
-            Ok(())
-        }))?;
-        self.0.SetSource(&item)?;
-        Ok(())
+            Err(error) => error,
+        };
+        let failures = listener.consecutive_failures.fetch_add(1, Ordering::SeqCst) + 1;
+        let limit = self.failure_limit.load(Ordering::SeqCst);
+        if limit > 0 && failures >= limit && !listener.suspended.swap(true, Ordering::SeqCst) {
+            let message = format!(
+                "neosynth: {} suspended after {} consecutive callback failures, last in {}: {}",
+                if listener.is_sink {
+                    "event sink"
+                } else {
+                    "event listener"
+                },
+                failures,
+                name,
+                error
+            );
+            Self::warn(py, &message);
+        }
+        Some(error)
     }
-    fn set_file_source(&self, file_path: String) -> NeosynthResult<()> {
-        let audiofile = StorageFile::GetFileFromPathAsync(&HSTRING::from(file_path))?.get()?;
-        self.0
-            .SetSource(&MediaSource::CreateFromStorageFile(&audiofile)?)?;
-        Ok(())
+
+    /// Register another object receiving every event
+    pub fn add_listener(&self, listener: PyObject) {
+        if let Some(listeners) = self.listeners.read().unwrap().as_ref() {
+            listeners
+                .lock()
+                .unwrap()
+                .push(Arc::new(Listener::new(listener, false)));
+        }
     }
-    fn play(&self) -> NeosynthResult<()> {
-        self.0.Play()?;
-        Ok(())
+
+    /// Stop sending events to the given object, returns false if it wasn't a listener
+    pub fn remove_listener(&self, py: Python<'_>, listener: &PyAny) -> bool {
+        let listeners = self.listeners.read().unwrap();
+        let Some(listeners) = listeners.as_ref() else {
+            return false;
+        };
+        let mut listeners = listeners.lock().unwrap();
+        let count = listeners.len();
+        listeners.retain(|l| !l.object.as_ref(py).is(listener));
+        listeners.len() != count
     }
-    fn pause(&self) -> NeosynthResult<()> {
-        self.0.Pause()?;
-        Ok(())
+
+    fn dispatch(&self, event: PendingEvent) {
+        if self.closed.load(Ordering::SeqCst) {
+            return;
+        }
+        let listeners = self.listeners.read().unwrap();
+        if let Some(listeners) = listeners.as_ref() {
+            Python::with_gil(|py| {
+                // The sink may have been closed while we were waiting for the GIL
+                if self.closed.load(Ordering::SeqCst) {
+                    return;
+                }
+                let args = (event.args)(py);
+                // Listeners may be added or removed by the callbacks themselves
+                let listeners = listeners.lock().unwrap().clone();
+                for listener in &listeners {
+                    if !listener.receives(py, event.name) {
+                        continue;
+                    }
+                    let error = self.invoke(py, listener, event.name, args.as_ref(py));
+                    // A failing error handler is not reported to itself over and over
+                    if let (Some(error), false) = (error, event.name == "on_internal_error") {
+                        let message = format!("Event handler {} failed: {}", event.name, error);
+                        let args = PyTuple::new(py, [message]);
+                        for listener in &listeners {
+                            if listener.receives(py, "on_internal_error") {
+                                self.invoke(py, listener, "on_internal_error", args);
+                            }
+                        }
+                    }
+                }
+            });
+        }
     }
-    fn resume(&self) -> NeosynthResult<()> {
-        if self.get_playback_state()? == MediaPlaybackState::Paused {
-            self.0.Play()?;
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        // Waiting for in-flight callbacks from within a callback would deadlock,
+        // the callback currently running is the caller itself
+        if !IN_PY_CALLBACK.with(|flag| flag.get()) {
+            self.listeners.write().unwrap().take();
         }
-        Ok(())
     }
-    fn stop(&self) -> NeosynthResult<()> {
-        self.pause()?;
-        let empty_stream = InMemoryRandomAccessStream::new()?;
-        self.0.SetSource(&MediaSource::CreateFromStream(
-            &empty_stream,
-            &HSTRING::from(""),
-        )?)?;
-        Ok(())
+
+    /// Call a handler of a listener, timing it against the callback budget.
+    /// Returns the exception raised by the handler, if any.
+    fn invoke(
+        &self,
+        py: Python<'_>,
+        listener: &Listener,
+        name: &str,
+        args: &PyTuple,
+    ) -> Option<PyErr> {
+        let budget = *self.callback_budget.read().unwrap();
+        let started = Instant::now();
+        let watched = match budget {
+            Some(budget) if self.cancel_slow_callbacks.load(Ordering::SeqCst) => {
+                Self::current_thread_ident(py).ok().inspect(|ident| {
+                    self.watched_callbacks
+                        .lock()
+                        .unwrap()
+                        .push((*ident, started + budget));
+                })
+            }
+            _ => None,
+        };
+        IN_PY_CALLBACK.with(|flag| flag.set(true));
+        let result = listener.object.call_method1(py, name, args);
+        IN_PY_CALLBACK.with(|flag| flag.set(false));
+        if let Some(ident) = watched {
+            self.watched_callbacks
+                .lock()
+                .unwrap()
+                .retain(|(i, _)| *i != ident);
+        }
+        let elapsed = started.elapsed();
+        if budget.is_some_and(|budget| elapsed > budget) {
+            let count = self.slow_callbacks.fetch_add(1, Ordering::SeqCst) + 1;
+            Self::warn(
+                py,
+                &format!(
+                    "neosynth: event sink callback {} took {:.1} ms ({} slow callbacks so far)",
+                    name,
+                    elapsed.as_secs_f64() * 1000.0,
+                    count
+                ),
+            );
+        }
+        self.handle_callback_result(py, listener, name, result)
     }
 }
 
-struct SpeechMixer<T>
-where
-    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
-{
-    synthesizer: SpeechSynthesizer,
-    player: NeoMediaPlayer<T>,
-    state: RwLock<SynthState>,
-    speech_queue: SegQueue<SpeechElement>,
+/// An object receiving events, it may implement any subset of the handlers
+struct Listener {
+    object: PyObject,
+    /// Whether this is the event sink the synthesizer was created with
+    is_sink: bool,
+    /// Whether the listener has a handler for each event, probed the first time the event is sent
+    handlers: Mutex<HashMap<&'static str, bool>>,
+    consecutive_failures: AtomicUsize,
+    /// Set once its callbacks failed too many times in a row, until resumed
+    suspended: AtomicBool,
 }
 
-impl<T> SpeechMixer<T>
-where
-    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
-{
-    pub fn new(event_sink: T) -> NeosynthResult<Self> {
-        Ok(Self {
+impl Listener {
+    fn new(object: PyObject, is_sink: bool) -> Self {
+        Self {
+            object,
+            is_sink,
+            handlers: Mutex::new(HashMap::new()),
+            consecutive_failures: AtomicUsize::new(0),
+            suspended: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether the event is to be sent to the listener
+    fn receives(&self, py: Python<'_>, name: &'static str) -> bool {
+        !self.suspended.load(Ordering::SeqCst) && self.has_handler(py, name)
+    }
+
+    fn has_handler(&self, py: Python<'_>, name: &'static str) -> bool {
+        *self
+            .handlers
+            .lock()
+            .unwrap()
+            .entry(name)
+            .or_insert_with(|| self.object.as_ref(py).hasattr(name).unwrap_or(false))
+    }
+}
+
+/// An event waiting to be delivered, its arguments are converted once the GIL is held
+struct PendingEvent {
+    name: &'static str,
+    args: Box<dyn FnOnce(Python<'_>) -> Py<PyTuple> + Send>,
+}
+
+/// Sends events to a Python event sink and listeners.
+///
+/// Events are queued and delivered in order by a dedicated thread, so Python callbacks
+/// never run on, nor block, the media player threads which raise the events.
+pub struct PyEventSinkWrapper {
+    dispatcher: Arc<EventDispatcher>,
+    events: mpsc::Sender<PendingEvent>,
+}
+
+impl PyEventSinkWrapper {
+    fn new(py_event_sink: PyObject) -> Self {
+        let dispatcher = Arc::new(EventDispatcher::new(py_event_sink));
+        let (events, receiver) = mpsc::channel::<PendingEvent>();
+        let thread_dispatcher = Arc::clone(&dispatcher);
+        // Exits once the wrapper, and with it the sending half, is dropped
+        thread::spawn(move || {
+            for event in receiver {
+                thread_dispatcher.dispatch(event);
+            }
+        });
+        Self { dispatcher, events }
+    }
+
+    fn call_method(&self, name: &'static str, args: impl IntoPy<Py<PyTuple>> + Send + 'static) {
+        if self.dispatcher.closed.load(Ordering::SeqCst) {
+            return;
+        }
+        let event = PendingEvent {
+            name,
+            args: Box::new(move |py| args.into_py(py)),
+        };
+        self.events.send(event).ok();
+    }
+
+    pub fn set_callback_budget(&self, budget: Option<Duration>, cancel: bool) {
+        self.dispatcher.set_callback_budget(budget, cancel);
+    }
+
+    pub fn slow_callback_count(&self) -> usize {
+        self.dispatcher.slow_callback_count()
+    }
+
+    pub fn set_failure_limit(&self, limit: usize) {
+        self.dispatcher.set_failure_limit(limit);
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.dispatcher.is_suspended()
+    }
+
+    pub fn resume(&self) {
+        self.dispatcher.resume();
+    }
+
+    pub fn add_listener(&self, listener: PyObject) {
+        self.dispatcher.add_listener(listener);
+    }
+
+    pub fn remove_listener(&self, py: Python<'_>, listener: &PyAny) -> bool {
+        self.dispatcher.remove_listener(py, listener)
+    }
+}
+
+impl NsEventSink for PyEventSinkWrapper {
+    fn on_state_changed(
+        &self,
+        old_state: SynthState,
+        new_state: SynthState,
+        reason: StateChangeReason,
+    ) {
+        // Sinks written for `on_state_changed(new_state)` keep working,
+        // the previous state and the reason go to `on_state_transition`
+        self.call_method("on_state_changed", (new_state,));
+        self.call_method("on_state_transition", (old_state, new_state, reason));
+    }
+    fn on_bookmark_reached(&self, bookmark: String) {
+        self.call_method("on_bookmark_reached", (bookmark,));
+    }
+    fn log(&self, message: &str, level: &str) {
+        self.call_method("log", (message.to_string(), level.to_string()));
+    }
+    fn on_reading_limit_reached(&self) {
+        self.call_method("on_reading_limit_reached", ());
+    }
+    fn on_settings_changed(&self, settings: &EngineConfig) {
+        self.call_method("on_settings_changed", (settings.clone(),));
+    }
+    fn on_buffering(&self, is_buffering: bool) {
+        self.call_method("on_buffering", (is_buffering,));
+    }
+    fn on_word_reached(&self, offset: usize, length: usize, audio_time: f64) {
+        self.call_method("on_word_reached", (offset, length, audio_time));
+    }
+    fn on_sentence_reached(&self, offset: usize, length: usize, audio_time: f64) {
+        self.call_method("on_sentence_reached", (offset, length, audio_time));
+    }
+    fn on_element_started(&self, index: usize, kind: &str) {
+        self.call_method("on_element_started", (index, kind.to_string()));
+    }
+    fn on_element_finished(&self, index: usize, kind: &str, duration: f64) {
+        self.call_method("on_element_finished", (index, kind.to_string(), duration));
+    }
+    fn on_utterance_started(&self, id: u64) {
+        self.call_method("on_utterance_started", (id,));
+    }
+    fn on_utterance_finished(&self, id: u64) {
+        self.call_method("on_utterance_finished", (id,));
+    }
+    fn on_error(&self, element_index: Option<usize>, code: i32, message: &str) {
+        self.call_method("on_error", (element_index, code, message.to_string()));
+    }
+    fn on_audio_started(&self, path: &str) {
+        self.call_method("on_audio_started", (path.to_string(),));
+    }
+    fn on_audio_finished(&self, path: &str) {
+        self.call_method("on_audio_finished", (path.to_string(),));
+    }
+    fn on_device_busy(&self, retrying: bool) {
+        self.call_method("on_device_busy", (retrying,));
+    }
+    fn on_internal_error(&self, message: &str) {
+        self.call_method("on_internal_error", (message.to_string(),));
+    }
+    fn on_progress(&self, position_ms: u64, duration_ms: u64) {
+        self.call_method("on_progress", (position_ms, duration_ms));
+    }
+    fn on_silence_detected(&self, seconds: f64) {
+        self.call_method("on_silence_detected", (seconds,));
+    }
+    fn on_voices_changed(&self, added: Vec<String>, removed: Vec<String>) {
+        self.call_method("on_voices_changed", (added, removed));
+    }
+    fn on_speech_deferred(&self, id: u64) {
+        self.call_method("on_speech_deferred", (id,));
+    }
+    fn on_quiet_hours(&self, active: bool) {
+        self.call_method("on_quiet_hours", (active,));
+    }
+    fn on_speech_while_paused(&self, id: u64) {
+        self.call_method("on_speech_while_paused", (id,));
+    }
+    fn on_sleep_timer_elapsed(&self) {
+        self.call_method("on_sleep_timer_elapsed", ());
+    }
+    fn on_voice_fallback(&self, voice_id: &str, fallback_id: &str, reason: &str) {
+        self.call_method(
+            "on_voice_fallback",
+            (
+                voice_id.to_string(),
+                fallback_id.to_string(),
+                reason.to_string(),
+            ),
+        );
+    }
+    fn on_synthesis_changed(&self, voice_id: &str, text: &str) {
+        self.call_method(
+            "on_synthesis_changed",
+            (voice_id.to_string(), text.to_string()),
+        );
+    }
+    fn close(&self) {
+        self.dispatcher.close();
+    }
+}
+
+pub fn register_event_sink<T>(
+    item: &MediaPlaybackItem,
+    event_sink: &Arc<T>,
+    profiler: &Arc<Profiler>,
+    offsets: &Option<Arc<OffsetMap>>,
+) -> NeosynthResult<()>
+where
+    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
+{
+    let timed_metadata_tracks = item.TimedMetadataTracks()?;
+    for idx in 0..timed_metadata_tracks.Size()? {
+        register_cue_track(item, idx, event_sink, profiler, offsets)?;
+    }
+    Ok(())
+}
+
+/// Id of the timed metadata track holding the bookmarks of a speech stream
+const BOOKMARK_TRACK_ID: &str = "SpeechBookmark";
+/// Id of the timed metadata track holding the word boundaries of a speech stream
+const WORD_TRACK_ID: &str = "SpeechWord";
+/// Id of the timed metadata track holding the sentence boundaries of a speech stream
+const SENTENCE_TRACK_ID: &str = "SpeechSentence";
+
+/// Deliver the cues of a bookmark, word or sentence boundary track to the event sink,
+/// other tracks are ignored. Word and sentence offsets are mapped back through `offsets`
+/// when the synthesized text was rewritten from the element's text.
+pub fn register_cue_track<T>(
+    item: &MediaPlaybackItem,
+    idx: u32,
+    event_sink: &Arc<T>,
+    profiler: &Arc<Profiler>,
+    offsets: &Option<Arc<OffsetMap>>,
+) -> NeosynthResult<()>
+where
+    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
+{
+    let track = item.TimedMetadataTracks()?.GetAt(idx)?;
+    let track_id = track.Id()?;
+    let is_word_track = track_id == WORD_TRACK_ID;
+    let is_sentence_track = track_id == SENTENCE_TRACK_ID;
+    if !is_word_track && !is_sentence_track && track_id != BOOKMARK_TRACK_ID {
+        return Ok(());
+    }
+    item.TimedMetadataTracks()?.SetPresentationMode(
+        idx,
+        TimedMetadataTrackPresentationMode::ApplicationPresented,
+    )?;
+    let sink = Arc::clone(event_sink);
+    let profiler = Arc::clone(profiler);
+    let offsets = offsets.clone();
+    track.CueEntered(
+        &TypedEventHandler::<TimedMetadataTrack, MediaCueEventArgs>::new(move |_, event_args| {
+            if let Some(event_args) = event_args {
+                let speech_cue: SpeechCue = event_args.Cue()?.cast()?;
+                if is_word_track || is_sentence_track {
+                    let (offset, length, audio_time) = cue_position(&speech_cue, &offsets)?;
+                    profiler.time(Stage::Callback, || {
+                        if is_word_track {
+                            sink.on_word_reached(offset, length, audio_time)
+                        } else {
+                            sink.on_sentence_reached(offset, length, audio_time)
+                        }
+                    });
+                } else {
+                    let bookmark = speech_cue.Text()?.to_string_lossy();
+                    profiler.time(Stage::Callback, || sink.on_bookmark_reached(bookmark));
+                }
+            };
+            Ok(())
+        }),
+    )?;
+    Ok(())
+}
+
+/// Convert a WinRT TimeSpan, expressed in 100 nanosecond units, negative spans become zero
+fn timespan_to_duration(span: TimeSpan) -> Duration {
+    Duration::from_nanos(span.Duration.max(0) as u64 * 100)
+}
+
+/// Convert a duration to a WinRT TimeSpan, saturating at the largest representable span
+fn duration_to_timespan(duration: Duration) -> TimeSpan {
+    TimeSpan {
+        Duration: i64::try_from(duration.as_nanos() / 100).unwrap_or(i64::MAX),
+    }
+}
+
+/// Offset and length in the input, mapped back through `offsets` if it was rewritten, and
+/// start time in seconds of a word or sentence cue
+fn cue_position(
+    cue: &SpeechCue,
+    offsets: &Option<Arc<OffsetMap>>,
+) -> windows::core::Result<(usize, usize, f64)> {
+    let start = cue.StartPositionInInput()?.Value()?;
+    let end = cue.EndPositionInInput()?.Value()?;
+    let audio_time = timespan_to_duration(cue.StartTime()?).as_secs_f64();
+    let (offset, length) = (start.max(0) as usize, (end - start + 1).max(0) as usize);
+    let (offset, length) = match offsets {
+        Some(offsets) => offsets.map_span(offset, length),
+        None => (offset, length),
+    };
+    Ok((offset, length, audio_time))
+}
+
+/// The offset, length and start time of each word of a synthesized stream,
+/// empty unless word boundary metadata is enabled
+fn word_boundaries(
+    stream: &SpeechSynthesisStream,
+    offsets: &Option<Arc<OffsetMap>>,
+) -> NeosynthResult<Vec<(usize, usize, f64)>> {
+    let mut boundaries = Vec::new();
+    for track in stream.TimedMetadataTracks()? {
+        if track.Id()? == WORD_TRACK_ID {
+            for cue in track.Cues()? {
+                boundaries.push(cue_position(&cue.cast()?, offsets)?);
+            }
+        }
+    }
+    Ok(boundaries)
+}
+
+/// Start times in seconds of the sentences of a synthesized stream
+fn sentence_starts(stream: &SpeechSynthesisStream) -> NeosynthResult<Vec<f64>> {
+    let mut starts = Vec::new();
+    for track in stream.TimedMetadataTracks()? {
+        if track.Id()? == SENTENCE_TRACK_ID {
+            for cue in track.Cues()? {
+                starts.push(cue_position(&cue.cast()?, &None)?.2);
+            }
+        }
+    }
+    Ok(starts)
+}
+
+/// Copy a track of speech cues, a cue can only belong to one track
+fn copy_speech_track(track: &TimedMetadataTrack) -> windows::core::Result<TimedMetadataTrack> {
+    let copy =
+        TimedMetadataTrack::Create(&track.Id()?, &track.Language()?, track.TimedMetadataKind()?)?;
+    for cue in track.Cues()? {
+        let cue: SpeechCue = cue.cast()?;
+        let copied_cue = SpeechCue::new()?;
+        copied_cue.SetId(&cue.Id()?)?;
+        copied_cue.SetText(&cue.Text()?)?;
+        copied_cue.SetStartTime(cue.StartTime()?)?;
+        copied_cue.SetDuration(cue.Duration()?)?;
+        // Bookmarks have no position in the input
+        if let (Ok(start), Ok(end)) = (cue.StartPositionInInput(), cue.EndPositionInInput()) {
+            copied_cue.SetStartPositionInInput(&start)?;
+            copied_cue.SetEndPositionInInput(&end)?;
+        }
+        copy.AddCue(&copied_cue)?;
+    }
+    Ok(copy)
+}
+
+/// The media player, the event sink, the profiler, the async operations, and the user's
+/// volume with the gain applied on top of it.
+///
+/// Each element is set as the source once it is reached rather than queued ahead in a
+/// `MediaPlaybackList`. A list has one volume and looping flag and raises `MediaEnded`
+/// once it runs out, while tones and profiles change the gain, looped audio repeats and
+/// the queue advances for each element.
+pub struct NeoMediaPlayer<T>(
+    MediaPlayer,
+    Arc<T>,
+    Arc<Profiler>,
+    Arc<AsyncOps>,
+    Mutex<(f64, f64)>,
+);
+
+impl<T> NeoMediaPlayer<T>
+where
+    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
+{
+    fn new(event_sink: T, profiler: Arc<Profiler>, ops: Arc<AsyncOps>) -> NeosynthResult<Self> {
+        let win_player = MediaPlayer::new()?;
+        win_player.SetRealTimePlayback(true)?;
+        win_player.SetAudioCategory(MediaPlayerAudioCategory::Speech)?;
+        let first_audio_profiler = Arc::clone(&profiler);
+        win_player
+            .PlaybackSession()?
+            .PlaybackStateChanged(&TypedEventHandler::<MediaPlaybackSession, _>::new(
+                move |session, _| {
+                    if let Some(session) = session {
+                        if session.PlaybackState()? == MediaPlaybackState::Playing {
+                            first_audio_profiler.finish(Stage::FirstAudio);
+                        }
+                    }
+                    Ok(())
+                },
+            ))?;
+        Ok(Self(
+            win_player,
+            Arc::new(event_sink),
+            profiler,
+            ops,
+            Mutex::new((100.0, 1.0)),
+        ))
+    }
+    /// Invoke the event sink, timing the callback when profiling
+    pub fn notify(&self, callback: impl FnOnce(&T)) {
+        self.2.time(Stage::Callback, || callback(&self.1));
+    }
+    pub fn get_playback_state(&self) -> NeosynthResult<MediaPlaybackState> {
+        Ok(self.0.PlaybackSession()?.PlaybackState()?)
+    }
+    pub fn get_volume(&self) -> NeosynthResult<f64> {
+        Ok(self.4.lock().unwrap().0)
+    }
+    pub fn set_volume(&self, volume: f64) -> NeosynthResult<()> {
+        let mut levels = self.4.lock().unwrap();
+        self.0.SetVolume(volume / 100f64 * levels.1)?;
+        levels.0 = volume;
+        Ok(())
+    }
+    /// Scale the volume by `gain` without changing the volume reported to the user
+    fn set_gain(&self, gain: f64) -> NeosynthResult<()> {
+        let mut levels = self.4.lock().unwrap();
+        let gain = gain.max(MIN_GAIN);
+        self.0.SetVolume(levels.0 / 100f64 * gain)?;
+        levels.1 = gain;
+        Ok(())
+    }
+    fn set_muted(&self, muted: bool) -> NeosynthResult<()> {
+        Ok(self.0.SetIsMuted(muted)?)
+    }
+    fn set_speech_stream_source(
+        &self,
+        stream: SpeechSynthesisStream,
+        offsets: Option<Arc<OffsetMap>>,
+    ) -> NeosynthResult<()> {
+        let content_type = wave::stream_content_type(
+            &stream.ContentType()?.to_string(),
+            &stream.GetInputStreamAt(0)?,
+            &self.3,
+        )?;
+        let source = MediaSource::CreateFromStream(&stream, &HSTRING::from(content_type))
+            .map_err(NeosynthError::Playback)?;
+        self.set_media_source(source, offsets)
+    }
+    /// Play processed wave audio in place of a synthesized stream, keeping the stream's bookmarks
+    fn set_processed_source(
+        &self,
+        stream: &SpeechSynthesisStream,
+        audio: &[u8],
+        offsets: Option<Arc<OffsetMap>>,
+    ) -> NeosynthResult<()> {
+        let memory_stream = wave::memory_stream(audio, &self.3)?;
+        let source =
+            MediaSource::CreateFromStream(&memory_stream, &HSTRING::from(wave::WAVE_CONTENT_TYPE))
+                .map_err(NeosynthError::Playback)?;
+        let tracks = source.ExternalTimedMetadataTracks()?;
+        for track in stream.TimedMetadataTracks()? {
+            tracks.Append(&copy_speech_track(&track)?)?;
+        }
+        self.set_media_source(source, offsets)
+    }
+    fn set_media_source(
+        &self,
+        source: MediaSource,
+        offsets: Option<Arc<OffsetMap>>,
+    ) -> NeosynthResult<()> {
+        let item = MediaPlaybackItem::Create(&source).map_err(NeosynthError::Playback)?;
+        let evtsink = Arc::clone(&self.1);
+        // Register events in existing TimedMetadataTracks
+        register_event_sink(&item, &evtsink, &self.2, &offsets)?;
+        // Register events for future tracks
+        let evtsink = Arc::clone(&self.1);
+        let profiler = Arc::clone(&self.2);
+        item.TimedMetadataTracksChanged(&TypedEventHandler::<
+            MediaPlaybackItem,
+            IVectorChangedEventArgs,
+        >::new(move |item, args| {
+            if let Some(item) = item {
+                if let Some(args) = args {
+                    let change = args.CollectionChange()?;
+                    let registered = if change == CollectionChange::ItemInserted {
+                        register_cue_track(item, args.Index()?, &evtsink, &profiler, &offsets)
+                    } else if change == CollectionChange::Reset {
+                        register_event_sink(item, &evtsink, &profiler, &offsets)
+                    } else {
+                        Ok(())
+                    };
+                    if let Err(e) = registered {
+                        let message = format!("Registering speech cue events failed: {}", e);
+                        evtsink.on_internal_error(&message);
+                    }
+                }
+            }
+            Ok(())
+        }))?;
+        self.0.SetSource(&item).map_err(NeosynthError::Playback)?;
+        Ok(())
+    }
+    fn set_file_source(&self, file_path: String) -> NeosynthResult<()> {
+        let operation = StorageFile::GetFileFromPathAsync(&HSTRING::from(file_path))?;
+        let audiofile: StorageFile = self.3.wait(&operation)?;
+        // Audio files start playing as soon as they are set as the source
+        self.2.start(Stage::FirstAudio);
+        MediaSource::CreateFromStorageFile(&audiofile)
+            .and_then(|source| self.0.SetSource(&source))
+            .map_err(NeosynthError::Playback)
+    }
+    /// Play silence lasting `duration`, MediaEnded is raised at its end like for any source
+    fn set_silence_source(&self, duration: Duration) -> NeosynthResult<()> {
+        let silence = wave::silence(SILENCE_SAMPLE_RATE, duration)
+            .ok_or_else(|| OperationError(format!("Silence of {:?} is too long", duration)))?;
+        let stream = wave::memory_stream(&silence, &self.3)?;
+        MediaSource::CreateFromStream(&stream, &HSTRING::from(wave::WAVE_CONTENT_TYPE))
+            .and_then(|source| self.0.SetSource(&source))
+            .map_err(NeosynthError::Playback)?;
+        self.play()
+    }
+    fn play(&self) -> NeosynthResult<()> {
+        self.2.start(Stage::FirstAudio);
+        self.0.Play().map_err(NeosynthError::Playback)
+    }
+    /// Open the current source again and play it, after it failed to play
+    fn reload_source(&self) -> NeosynthResult<()> {
+        let source = self.0.Source().map_err(NeosynthError::Playback)?;
+        self.0.SetSource(&source).map_err(NeosynthError::Playback)?;
+        self.play()
+    }
+    fn pause(&self) -> NeosynthResult<()> {
+        self.0.Pause().map_err(NeosynthError::Playback)
+    }
+    /// Play the current source over and over, MediaEnded isn't raised meanwhile
+    fn set_looping(&self, looping: bool) -> NeosynthResult<()> {
+        self.0
+            .SetIsLoopingEnabled(looping)
+            .map_err(NeosynthError::Playback)
+    }
+    /// Position and duration of the current source, zero when there is none
+    fn position_and_duration(&self) -> NeosynthResult<(Duration, Duration)> {
+        let session = self.0.PlaybackSession().map_err(NeosynthError::Playback)?;
+        Ok((
+            timespan_to_duration(session.Position()?),
+            timespan_to_duration(session.NaturalDuration()?),
+        ))
+    }
+    /// Move the playback position within the current source, clamped to its duration.
+    /// Returns false if the source can't seek.
+    fn seek(&self, position: Duration) -> NeosynthResult<bool> {
+        let session = self.0.PlaybackSession().map_err(NeosynthError::Playback)?;
+        if !session.CanSeek()? {
+            return Ok(false);
+        }
+        let duration = timespan_to_duration(session.NaturalDuration()?);
+        session
+            .SetPosition(duration_to_timespan(position.min(duration)))
+            .map_err(NeosynthError::Playback)?;
+        Ok(true)
+    }
+    fn resume(&self) -> NeosynthResult<()> {
+        if self.get_playback_state()? == MediaPlaybackState::Paused {
+            self.0.Play().map_err(NeosynthError::Playback)?;
+        }
+        Ok(())
+    }
+    fn stop(&self) -> NeosynthResult<()> {
+        self.pause()?;
+        let empty_stream = InMemoryRandomAccessStream::new()?;
+        self.0.SetSource(&MediaSource::CreateFromStream(
+            &empty_stream,
+            &HSTRING::from(wave::WAVE_CONTENT_TYPE),
+        )?)?;
+        Ok(())
+    }
+}
+
+/// How often a `RateRamp` increases the speaking rate
+#[derive(Debug, Clone, Copy)]
+pub enum RampInterval {
+    /// After every given number of spoken text or SSML elements
+    Elements(usize),
+    /// After the given amount of time has passed
+    Time(Duration),
+}
+
+/// A schedule which gradually increases the speaking rate during a reading session
+#[derive(Debug, Clone, Copy)]
+pub struct RateRamp {
+    pub step: f64,
+    pub max_rate: f64,
+    pub interval: RampInterval,
+}
+
+struct RateRampState {
+    ramp: RateRamp,
+    spoken_elements: usize,
+    last_step: Instant,
+}
+
+impl RateRampState {
+    fn new(ramp: RateRamp) -> Self {
+        Self {
+            ramp,
+            spoken_elements: 0,
+            last_step: Instant::now(),
+        }
+    }
+
+    /// Count a spoken element, returns true if the rate should be increased
+    fn advance(&mut self) -> bool {
+        self.spoken_elements += 1;
+        match self.ramp.interval {
+            RampInterval::Elements(count) => self.spoken_elements.is_multiple_of(count.max(1)),
+            RampInterval::Time(interval) => {
+                if self.last_step.elapsed() >= interval {
+                    self.last_step = Instant::now();
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// How many more times an utterance is spoken
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Repeat {
+    Times(u32),
+    /// Until stopped
+    Forever,
+}
+
+/// An utterance being spoken again as set with `set_repeat`
+struct Replay {
+    /// The elements pulled from the source so far, the utterance is only known once
+    /// spoken as it may be produced lazily
+    elements: Vec<SpeechElement>,
+    remaining: Repeat,
+    /// Whether an audible element was started since the utterance was last started,
+    /// preview and skim modes may leave nothing to play
+    played: bool,
+    /// Whether pulled elements are recorded, only the first time the utterance is spoken
+    recording: bool,
+}
+
+/// A normal utterance interrupted by an alert, with what is needed to go on speaking it
+struct Interrupted {
+    id: u64,
+    /// False for an utterance requested during the alerts, which didn't start yet
+    started: bool,
+    /// The interrupted element, spoken again from its start, and the elements after it
+    queue: VecDeque<(usize, SpeechElement)>,
+    source: Option<SpeechSource>,
+    element_index: usize,
+    replay: Option<Replay>,
+    voice: VoiceInformation,
+    /// The settings applied by the utterance's profiles, and those restored once it ends
+    profile: Option<(SpeechProfile, SpeechProfile)>,
+    tone: Tone,
+    acronyms: HashMap<String, String>,
+}
+
+/// Event sink of the mixer speaking `Over` utterances, whose events aren't reported
+struct OverlaySink;
+
+impl NsEventSink for OverlaySink {
+    fn on_state_changed(&self, _: SynthState, _: SynthState, _: StateChangeReason) {}
+    fn on_bookmark_reached(&self, _bookmark: String) {}
+    fn log(&self, _message: &str, _level: &str) {}
+}
+
+/// The amount of speech after which a `SpeechMixer` pauses by itself
+#[derive(Debug, Clone, Copy)]
+pub enum ReadingLimit {
+    /// Time spent speaking
+    Time(Duration),
+    /// Number of characters spoken
+    Characters(usize),
+}
+
+struct ReadingLimitState {
+    limit: ReadingLimit,
+    spoken_time: Duration,
+    spoken_chars: usize,
+}
+
+/// Emits a synthetic bookmark after every `interval` of played audio
+struct AutoBookmarkState {
+    interval: Duration,
+    played: Duration,
+}
+
+/// Reports the playback progress every `interval` of played audio
+struct ProgressState {
+    interval: Duration,
+    played: Duration,
+}
+
+/// Tracks the playback position to notice playback which stalls without the player buffering
+#[derive(Default)]
+struct StallState {
+    /// Last position seen
+    position: Duration,
+    stalled_for: Duration,
+}
+
+/// Playback whose position doesn't advance for this long is reported as buffering
+const STALL_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// Retries playing an element which failed because the audio device was in use
+#[derive(Default)]
+struct DeviceRetryState {
+    /// None to skip the element instead
+    interval: Option<Duration>,
+    /// Time waited so far, None if no retry is pending
+    waited: Option<Duration>,
+}
+
+/// `AUDCLNT_E_DEVICE_IN_USE`, another application uses the audio device in exclusive mode
+const AUDCLNT_E_DEVICE_IN_USE: i32 = 0x8889000A_u32 as i32;
+
+/// How often the position is checked while waiting for the end of a sentence to stop
+const SENTENCE_STOP_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How often the mixer checks its time based limits and schedules
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+/// How often the installed voices are compared with those seen before
+const VOICE_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The installed voices last seen by the voice watcher
+struct VoiceWatchState {
+    since_check: Duration,
+    /// None until the voices could be listed once
+    voice_ids: Option<Vec<String>>,
+}
+
+/// The volume fades out over this long before the sleep timer stops playback
+const SLEEP_FADE_DURATION: Duration = Duration::from_secs(30);
+
+/// Stops playback once `deadline` is reached
+struct SleepTimer {
+    deadline: Instant,
+    /// Lower the volume over the last `SLEEP_FADE_DURATION` before stopping
+    fade: bool,
+}
+
+impl SleepTimer {
+    /// Factor applied to the volume at this point of the fade
+    fn fade_gain(&self) -> f64 {
+        if !self.fade {
+            return 1.0;
+        }
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        (remaining.as_secs_f64() / SLEEP_FADE_DURATION.as_secs_f64()).min(1.0)
+    }
+}
+
+fn synthesize(
+    synthesizer: &SpeechSynthesizer,
+    text: &str,
+    is_ssml: bool,
+    ops: &AsyncOps,
+) -> NeosynthResult<SpeechSynthesisStream> {
+    let operation = if is_ssml {
+        synthesizer.SynthesizeSsmlToStreamAsync(&HSTRING::from(text))
+    } else {
+        synthesizer.SynthesizeTextToStreamAsync(&HSTRING::from(text))
+    };
+    match operation
+        .map_err(RuntimeError)
+        .and_then(|operation| ops.wait(&operation))
+    {
+        Err(RuntimeError(e)) if is_ssml => Err(NeosynthError::Ssml(e)),
+        Err(RuntimeError(e)) => Err(NeosynthError::Synthesis(e)),
+        result => result,
+    }
+}
+
+/// Playing time in seconds of a synthesized wave stream
+fn stream_duration(stream: &SpeechSynthesisStream, ops: &AsyncOps) -> NeosynthResult<f64> {
+    let format = WaveFormat::from_stream(&stream.GetInputStreamAt(0)?, ops)?;
+    if format.byte_rate == 0 {
+        return Err(OperationError("Invalid wave stream byte rate".to_string()));
+    }
+    Ok(stream.Size()?.saturating_sub(WAVE_HEADER_SIZE) as f64 / format.byte_rate as f64)
+}
+
+/// Size of the canonical RIFF/WAVE header preceding the audio data
+const WAVE_HEADER_SIZE: u64 = 44;
+
+/// Get the playing time of an audio file in seconds
+fn audio_file_duration(file_path: &str, ops: &AsyncOps) -> NeosynthResult<f64> {
+    let audiofile: StorageFile = ops.wait(&StorageFile::GetFileFromPathAsync(&HSTRING::from(
+        file_path,
+    ))?)?;
+    let properties: MusicProperties =
+        ops.wait(&audiofile.Properties()?.GetMusicPropertiesAsync()?)?;
+    Ok(timespan_to_duration(properties.Duration()?).as_secs_f64())
+}
+
+/// Number of elements pulled ahead of playback from a lazy speech source by default
+const DEFAULT_LOOKAHEAD: usize = 4;
+/// Sample rate of the silence played between elements
+const SILENCE_SAMPLE_RATE: u32 = 16000;
+/// Longest silence `Neosynth.set_inter_element_silence` accepts
+const MAX_INTER_ELEMENT_SILENCE: Duration = Duration::from_secs(60);
+/// Lowest factor applied to the player's volume, a sleep timer fading out ends there
+const MIN_GAIN: f64 = 0.001;
+/// Number of played elements `previous_element` can go back through
+const ELEMENT_HISTORY_SIZE: usize = 32;
+/// Number of elements `skip_to_bookmark` pulls from a lazy speech source looking for the bookmark
+const BOOKMARK_SEARCH_LIMIT: usize = 1000;
+
+/// The elements of an utterance which have not been pulled into the speech queue yet
+enum SpeechSource {
+    Iter(Peekable<Box<dyn Iterator<Item = SpeechElement> + Send>>),
+    Feed(Arc<FeedChannel>),
+}
+
+enum PulledElement {
+    /// An element and the number of elements of the utterance it stands for
+    Element(SpeechElement, usize),
+    /// The source has no element right now, but may produce more later
+    Pending,
+    Exhausted,
+}
+
+impl SpeechSource {
+    /// The number of elements left if the source holds them already, as a list does.
+    /// None for a lazy source, whose elements are only known once pulled.
+    fn known_len(&self) -> Option<usize> {
+        match self {
+            SpeechSource::Iter(iter) => match iter.size_hint() {
+                (lower, Some(upper)) if lower == upper => Some(lower),
+                _ => None,
+            },
+            SpeechSource::Feed(_) => None,
+        }
+    }
+
+    fn pull(&mut self) -> PulledElement {
+        match self {
+            SpeechSource::Iter(iter) => match iter.next() {
+                // A bookmark is merged with the following text so that it is reached
+                // when the audio gets there, rather than before synthesizing the text
+                Some(SpeechElement::Bookmark(mark)) => {
+                    match iter.next_if(|elem| matches!(elem, SpeechElement::Text(_))) {
+                        Some(SpeechElement::Text(text)) => {
+                            PulledElement::Element(SpeechElement::MarkedText(mark, text), 2)
+                        }
+                        _ => PulledElement::Element(SpeechElement::Bookmark(mark), 1),
+                    }
+                }
+                Some(elem) => PulledElement::Element(elem, 1),
+                None => PulledElement::Exhausted,
+            },
+            SpeechSource::Feed(channel) => match channel.queue.pop() {
+                Some(elem) => PulledElement::Element(elem, 1),
+                None if channel.closed.load(Ordering::SeqCst) => PulledElement::Exhausted,
+                None => PulledElement::Pending,
+            },
+        }
+    }
+}
+
+struct FeedChannel {
+    queue: ArrayQueue<SpeechElement>,
+    closed: AtomicBool,
+}
+
+/// A bounded, non-blocking producer of speech elements for a `SpeechMixer`.
+///
+/// `try_push` never blocks, it hands the element back when the feed is full,
+/// which makes it suitable for driving from async code.
+/// The feed is closed when dropped.
+pub struct SpeechFeed<T>
+where
+    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
+{
+    channel: Arc<FeedChannel>,
+    mixer: Weak<SpeechMixer<T>>,
+    utterance_id: u64,
+}
+
+impl<T> SpeechFeed<T>
+where
+    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
+{
+    /// The id of the utterance made of the elements pushed to this feed
+    pub fn utterance_id(&self) -> u64 {
+        self.utterance_id
+    }
+    pub fn try_push(&self, element: SpeechElement) -> Result<(), SpeechElement> {
+        self.channel.queue.push(element)?;
+        self.wake();
+        Ok(())
+    }
+    pub fn is_full(&self) -> bool {
+        self.channel.queue.is_full()
+    }
+    /// Signal that no more elements will be pushed
+    pub fn close(&self) {
+        self.channel.closed.store(true, Ordering::SeqCst);
+        self.wake();
+    }
+    fn wake(&self) {
+        if let Some(mixer) = self.mixer.upgrade() {
+            if mixer.starved.swap(false, Ordering::SeqCst) {
+                mixer.advance_queue();
+            }
+        }
+    }
+}
+
+impl<T> Drop for SpeechFeed<T>
+where
+    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
+{
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+pub struct SpeechMixer<T>
+where
+    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
+{
+    synthesizer: SpeechSynthesizer,
+    player: NeoMediaPlayer<T>,
+    state: RwLock<SynthState>,
+    /// Elements pulled from the speech source, with their index within the utterance
+    speech_queue: Mutex<VecDeque<(usize, SpeechElement)>>,
+    speech_source: Mutex<Option<SpeechSource>>,
+    profiler: Arc<Profiler>,
+    /// WinRT operations in flight, cancelled when the mixer is closed
+    async_ops: Arc<AsyncOps>,
+    lookahead: AtomicUsize,
+    /// Number of upcoming elements synthesized while the current one plays
+    prefetch_count: AtomicUsize,
+    /// Streams synthesized ahead of time, keyed by their content and synthesis settings
+    prefetched: Mutex<VecDeque<(String, SpeechSynthesisStream)>>,
+    /// Wakes up the thread synthesizing upcoming elements
+    prefetch_trigger: OnceLock<mpsc::Sender<()>>,
+    max_text_length: AtomicUsize,
+    rate_ramp: Mutex<Option<RateRampState>>,
+    reading_limit: Mutex<Option<ReadingLimitState>>,
+    auto_bookmark: Mutex<Option<AutoBookmarkState>>,
+    progress: Mutex<Option<ProgressState>>,
+    device_retry: Mutex<DeviceRetryState>,
+    stall: Mutex<StallState>,
+    /// Whether buffering was last reported as started
+    buffering: AtomicBool,
+    voice_watch: Mutex<VoiceWatchState>,
+    voice_health: VoiceHealthTracker,
+    /// Voices tried in order when the current one is unavailable
+    voice_fallbacks: RwLock<Vec<VoiceInformation>>,
+    duration_estimator: DurationEstimator,
+    /// Hashes of the audio synthesized so far, None to not compare it with previous runs
+    fingerprints: RwLock<Option<FingerprintStore>>,
+    log_level: RwLock<LogLevel>,
+    /// Audio file played whenever the voice is switched automatically
+    voice_change_cue: RwLock<Option<String>>,
+    audio_transform: RwLock<Option<Arc<dyn AudioTransform>>>,
+    /// Drop skippable elements
+    skim_mode: AtomicBool,
+    /// Drop audio elements
+    skip_audio: AtomicBool,
+    /// Drop text, SSML and other spoken elements
+    skip_speech: AtomicBool,
+    /// Speaking rate from which skippable elements are dropped
+    skim_rate: Mutex<Option<f64>>,
+    preview_mode: AtomicBool,
+    /// Rejoin words and sentences broken across lines before synthesizing text
+    line_joining: AtomicBool,
+    /// Clean up bidirectional text before synthesizing it
+    bidi_cleanup: AtomicBool,
+    /// How emoji and symbols are spoken, None to leave them to the voice
+    symbol_mode: RwLock<Option<SymbolMode>>,
+    reading_rules: RwLock<ReadingRules>,
+    punctuation_runs: RwLock<Option<PunctuationRuns>>,
+    acronym_filter: RwLock<Option<AcronymFilter>>,
+    /// Acronym expansions added by the utterance being spoken
+    document_acronyms: Mutex<HashMap<String, String>>,
+    quiet_hours: Mutex<Option<QuietHours>>,
+    /// Whether quiet hours were in effect at the last check
+    quiet_active: AtomicBool,
+    /// Id of the last utterance requested as a priority, not softened during quiet hours
+    priority_utterance: Mutex<Option<u64>>,
+    /// Utterances kept until quiet hours end, with the ids they are spoken with
+    deferred: Mutex<VecDeque<(u64, Vec<SpeechElement>)>>,
+    /// Signalled when deferred utterances are spoken or dropped
+    deferred_freed: Condvar,
+    /// The maximum number of deferred utterances and what happens beyond it
+    max_deferred: Mutex<Option<(usize, QueueFullPolicy)>>,
+    /// Held while speaking, None to ignore other processes
+    audio_focus: Mutex<Option<AudioFocus>>,
+    paused_speech_mode: RwLock<PausedSpeechMode>,
+    /// Identical utterances requested within this long of each other are only spoken once
+    dedup_window: Mutex<Option<Duration>>,
+    /// Utterances spoken within the deduplication window, by key, with when they were
+    /// requested and their ids
+    recent_announcements: Mutex<HashMap<String, (Instant, u64)>>,
+    duplicate_count: AtomicUsize,
+    repeat: RwLock<Repeat>,
+    /// The utterance being spoken, kept while it has to be spoken again
+    replay: Mutex<Option<Replay>>,
+    /// Utterance requested while paused, spoken on resume, with the id it is spoken with
+    held: Mutex<Option<(u64, Vec<SpeechElement>)>>,
+    /// Alerts waiting for the one being spoken to end, with the ids they are spoken with
+    alerts: Mutex<VecDeque<(u64, Vec<SpeechElement>)>>,
+    /// Whether the utterance being spoken is an alert
+    alert_active: AtomicBool,
+    /// Normal utterance resumed once the alerts are spoken
+    interrupted: Mutex<Option<Interrupted>>,
+    /// Speaks `Over` utterances, created when the first one is requested
+    overlay: Mutex<Option<Arc<SpeechMixer<OverlaySink>>>>,
+    /// Tone of voice of the utterance being spoken
+    tone: Mutex<Tone>,
+    /// Settings from before the utterance being spoken applied a profile or switched the
+    /// voice, restored once it ends
+    saved_profile: Mutex<Option<SpeechProfile>>,
+    sleep_timer: Mutex<Option<SleepTimer>>,
+    muted: AtomicBool,
+    soloed: AtomicBool,
+    /// Set when the queue ran dry while waiting for a feed to produce more elements
+    starved: AtomicBool,
+    wait_for_buffering: AtomicBool,
+    /// Set while the Busy state is deferred until the player starts playing
+    awaiting_playback: AtomicBool,
+    /// Index within the utterance of the next element to be pulled from the speech source
+    element_index: AtomicUsize,
+    /// Index, kind and start time of the element being processed
+    current_element: Mutex<Option<(usize, &'static str, Instant)>>,
+    /// The audible elements played most recently with their index, the last one being the
+    /// current element
+    history: Mutex<VecDeque<(usize, SpeechElement)>>,
+    /// Offset, length and start time of the words of the element being played
+    word_boundaries: Mutex<Vec<(usize, usize, f64)>>,
+    /// Start times of the sentences of the element being played
+    sentence_starts: Mutex<Vec<f64>>,
+    /// Index of the element at the end of whose current sentence speech stops
+    pending_stop: Mutex<Option<usize>>,
+    /// Path of the audio element being played, and whether it started playing
+    current_audio: Mutex<Option<(String, bool)>>,
+    /// Silence lasting this long is reported, None to disable silence detection
+    silence_threshold: Mutex<Option<Duration>>,
+    /// Silence played after each audible element followed by another one
+    inter_element_silence: Mutex<Option<Duration>>,
+    /// Set while an audible element plays, so that silence follows it
+    silence_due: AtomicBool,
+    /// Start and end of the silent stretches of the element being played not reported yet
+    silent_stretches: Mutex<Vec<(f64, f64)>>,
+    next_utterance_id: AtomicU64,
+    /// Id of the utterance being spoken
+    current_utterance: Mutex<Option<u64>>,
+    closed: AtomicBool,
+}
+
+impl<T> SpeechMixer<T>
+where
+    T: NsEventSink + std::marker::Send + std::marker::Sync + 'static,
+{
+    pub fn new(event_sink: T) -> NeosynthResult<Self> {
+        let profiler = Arc::new(Profiler::default());
+        let async_ops = Arc::new(AsyncOps::default());
+        Ok(Self {
             synthesizer: SpeechSynthesizer::new()?,
-            player: NeoMediaPlayer::new(event_sink)?,
+            player: NeoMediaPlayer::new(event_sink, Arc::clone(&profiler), Arc::clone(&async_ops))?,
             state: RwLock::new(Default::default()),
-            speech_queue: SegQueue::new(),
+            speech_queue: Mutex::new(VecDeque::new()),
+            speech_source: Mutex::new(None),
+            profiler,
+            async_ops,
+            lookahead: AtomicUsize::new(DEFAULT_LOOKAHEAD),
+            prefetch_count: AtomicUsize::new(0),
+            prefetched: Mutex::new(VecDeque::new()),
+            prefetch_trigger: OnceLock::new(),
+            max_text_length: AtomicUsize::new(0),
+            rate_ramp: Mutex::new(None),
+            reading_limit: Mutex::new(None),
+            auto_bookmark: Mutex::new(None),
+            progress: Mutex::new(None),
+            device_retry: Mutex::new(DeviceRetryState::default()),
+            stall: Mutex::new(StallState::default()),
+            buffering: AtomicBool::new(false),
+            voice_watch: Mutex::new(VoiceWatchState {
+                since_check: Duration::ZERO,
+                voice_ids: installed_voice_ids().ok(),
+            }),
+            voice_health: VoiceHealthTracker::default(),
+            voice_fallbacks: RwLock::new(Vec::new()),
+            duration_estimator: DurationEstimator::default(),
+            fingerprints: RwLock::new(None),
+            log_level: RwLock::new(Default::default()),
+            voice_change_cue: RwLock::new(None),
+            audio_transform: RwLock::new(None),
+            skim_mode: AtomicBool::new(false),
+            skip_audio: AtomicBool::new(false),
+            skip_speech: AtomicBool::new(false),
+            skim_rate: Mutex::new(None),
+            preview_mode: AtomicBool::new(false),
+            line_joining: AtomicBool::new(false),
+            bidi_cleanup: AtomicBool::new(false),
+            symbol_mode: RwLock::new(None),
+            reading_rules: RwLock::new(ReadingRules::default()),
+            punctuation_runs: RwLock::new(None),
+            acronym_filter: RwLock::new(None),
+            document_acronyms: Mutex::new(HashMap::new()),
+            quiet_hours: Mutex::new(None),
+            quiet_active: AtomicBool::new(false),
+            priority_utterance: Mutex::new(None),
+            deferred: Mutex::new(VecDeque::new()),
+            deferred_freed: Condvar::new(),
+            max_deferred: Mutex::new(None),
+            audio_focus: Mutex::new(None),
+            paused_speech_mode: RwLock::new(PausedSpeechMode::default()),
+            dedup_window: Mutex::new(None),
+            recent_announcements: Mutex::new(HashMap::new()),
+            duplicate_count: AtomicUsize::new(0),
+            repeat: RwLock::new(Repeat::Times(0)),
+            replay: Mutex::new(None),
+            held: Mutex::new(None),
+            alerts: Mutex::new(VecDeque::new()),
+            alert_active: AtomicBool::new(false),
+            interrupted: Mutex::new(None),
+            overlay: Mutex::new(None),
+            tone: Mutex::new(Tone::Normal),
+            saved_profile: Mutex::new(None),
+            sleep_timer: Mutex::new(None),
+            muted: AtomicBool::new(false),
+            soloed: AtomicBool::new(false),
+            starved: AtomicBool::new(false),
+            wait_for_buffering: AtomicBool::new(false),
+            awaiting_playback: AtomicBool::new(false),
+            element_index: AtomicUsize::new(0),
+            current_element: Mutex::new(None),
+            history: Mutex::new(VecDeque::new()),
+            word_boundaries: Mutex::new(Vec::new()),
+            sentence_starts: Mutex::new(Vec::new()),
+            pending_stop: Mutex::new(None),
+            current_audio: Mutex::new(None),
+            silence_threshold: Mutex::new(None),
+            inter_element_silence: Mutex::new(None),
+            silence_due: AtomicBool::new(false),
+            silent_stretches: Mutex::new(Vec::new()),
+            next_utterance_id: AtomicU64::new(1),
+            current_utterance: Mutex::new(None),
+            closed: AtomicBool::new(false),
+        })
+    }
+
+    /// Register the player events which advance the speech queue
+    pub fn register_events(mixer: &Arc<Self>) -> NeosynthResult<()> {
+        let weak_mixer = Arc::downgrade(mixer);
+        mixer
+            .player
+            .0
+            .MediaEnded(&TypedEventHandler::<MediaPlayer, _>::new(move |_, _| {
+                if let Some(mixer) = weak_mixer.upgrade() {
+                    mixer.advance_queue();
+                }
+                Ok(())
+            }))?;
+        let weak_mixer = Arc::downgrade(mixer);
+        mixer.player.0.MediaFailed(
+            &TypedEventHandler::<MediaPlayer, MediaPlayerFailedEventArgs>::new(move |_, args| {
+                if let Some(mixer) = weak_mixer.upgrade() {
+                    let retrying = args
+                        .as_ref()
+                        .and_then(|args| mixer.report_playback_error(args).ok())
+                        .unwrap_or(false);
+                    if !retrying {
+                        mixer.advance_queue();
+                    }
+                }
+                Ok(())
+            }),
+        )?;
+        let session = mixer.player.0.PlaybackSession()?;
+        let weak_mixer = Arc::downgrade(mixer);
+        session.BufferingStarted(&TypedEventHandler::<MediaPlaybackSession, _>::new(
+            move |_, _| {
+                if let Some(mixer) = weak_mixer.upgrade() {
+                    mixer.set_buffering(true);
+                }
+                Ok(())
+            },
+        ))?;
+        let weak_mixer = Arc::downgrade(mixer);
+        session.BufferingEnded(&TypedEventHandler::<MediaPlaybackSession, _>::new(
+            move |_, _| {
+                if let Some(mixer) = weak_mixer.upgrade() {
+                    mixer.set_buffering(false);
+                }
+                Ok(())
+            },
+        ))?;
+        let weak_mixer = Arc::downgrade(mixer);
+        session.PlaybackStateChanged(&TypedEventHandler::<MediaPlaybackSession, _>::new(
+            move |session, _| {
+                if let (Some(mixer), Some(session)) = (weak_mixer.upgrade(), session) {
+                    match session.PlaybackState()? {
+                        MediaPlaybackState::Buffering => mixer.set_buffering(true),
+                        MediaPlaybackState::Playing => mixer.start_audio(),
+                        _ => {}
+                    }
+                    if session.PlaybackState()? == MediaPlaybackState::Playing
+                        && mixer.awaiting_playback.swap(false, Ordering::SeqCst)
+                    {
+                        if let Err(e) =
+                            mixer.set_state(SynthState::Busy, StateChangeReason::PlaybackStarted)
+                        {
+                            mixer.report_internal_error("Changing the state failed", &e);
+                        }
+                    }
+                }
+                Ok(())
+            },
+        ))?;
+        Self::spawn_ticker(mixer);
+        Self::spawn_prefetcher(mixer);
+        Ok(())
+    }
+
+    /// Start a background thread which synthesizes upcoming elements when woken up,
+    /// it stops with the mixer
+    fn spawn_prefetcher(mixer: &Arc<Self>) {
+        let (trigger, wakeups) = mpsc::channel();
+        if mixer.prefetch_trigger.set(trigger).is_err() {
+            return;
+        }
+        let weak_mixer = Arc::downgrade(mixer);
+        thread::spawn(move || {
+            while wakeups.recv().is_ok() {
+                // Requests made while prefetching are served by a single pass
+                while wakeups.try_recv().is_ok() {}
+                match weak_mixer.upgrade() {
+                    Some(mixer) if !mixer.is_closed() => mixer.prefetch_upcoming(),
+                    _ => break,
+                }
+            }
+        });
+    }
+
+    /// Report buffering as started or finished, unless it already was
+    fn set_buffering(&self, is_buffering: bool) {
+        if self.buffering.swap(is_buffering, Ordering::SeqCst) != is_buffering {
+            self.player.notify(|sink| sink.on_buffering(is_buffering));
+        }
+    }
+
+    /// Report playback whose position stopped advancing as buffering, until it advances again
+    fn tick_stall(&self, elapsed: Duration) {
+        let mut stall = self.stall.lock().unwrap();
+        if self.player.get_playback_state().ok() != Some(MediaPlaybackState::Playing) {
+            stall.stalled_for = Duration::ZERO;
+            return;
+        }
+        let Ok(position) = self
+            .player
+            .0
+            .PlaybackSession()
+            .and_then(|session| session.Position())
+        else {
+            return;
+        };
+        let position = timespan_to_duration(position);
+        if position != stall.position {
+            stall.position = position;
+            stall.stalled_for = Duration::ZERO;
+            drop(stall);
+            self.set_buffering(false);
+            return;
+        }
+        stall.stalled_for += elapsed;
+        let stalled = stall.stalled_for >= STALL_THRESHOLD;
+        drop(stall);
+        if stalled {
+            self.set_buffering(true);
+        }
+    }
+
+    /// Only report the Busy state once audio is actually playing, rather than when speech is requested
+    pub fn set_wait_for_buffering(&self, wait: bool) {
+        self.wait_for_buffering.store(wait, Ordering::SeqCst);
+    }
+
+    /// Report the Busy state for newly requested speech, or defer it until audio plays
+    pub fn set_busy(&self) -> NeosynthResult<()> {
+        if self.wait_for_buffering.load(Ordering::SeqCst) {
+            self.awaiting_playback.store(true, Ordering::SeqCst);
+            Ok(())
+        } else {
+            self.set_state(SynthState::Busy, StateChangeReason::Speak)
+        }
+    }
+
+    /// Start a background thread which periodically runs `tick` for as long as the mixer is alive
+    fn spawn_ticker(mixer: &Arc<Self>) {
+        let weak_mixer = Arc::downgrade(mixer);
+        thread::spawn(move || loop {
+            thread::sleep(TICK_INTERVAL);
+            match weak_mixer.upgrade() {
+                Some(mixer) if !mixer.is_closed() => mixer.tick(TICK_INTERVAL),
+                _ => break,
+            }
+        });
+    }
+
+    fn tick(&self, elapsed: Duration) {
+        if self.get_state().ok() == Some(SynthState::Busy) {
+            let limit_reached = match self.reading_limit.lock().unwrap().as_mut() {
+                Some(state) => {
+                    state.spoken_time += elapsed;
+                    matches!(state.limit, ReadingLimit::Time(limit) if state.spoken_time >= limit)
+                }
+                None => false,
+            };
+            if limit_reached {
+                if let Err(e) = self.reading_limit_reached() {
+                    self.report_internal_error("Pausing at the reading limit failed", &e);
+                }
+            }
+            self.tick_auto_bookmark(elapsed);
+            self.tick_progress(elapsed);
+            self.tick_silence_detection();
+            self.tick_stall(elapsed);
+        }
+        // A failed element may leave the busy state, the retry is pending until it is cleared
+        self.tick_device_retry(elapsed);
+        self.tick_voice_watch(elapsed);
+        self.tick_quiet_hours();
+        self.tick_audio_focus();
+        self.tick_sleep_timer();
+        self.tick_fingerprints();
+    }
+
+    /// Write the fingerprints recorded since the last tick, at most once per tick
+    fn tick_fingerprints(&self) {
+        if let Some(store) = self.fingerprints.read().unwrap().as_ref() {
+            if let Err(e) = store.flush() {
+                self.log(&format!("Fingerprinting failed: {}", e), LogLevel::Warning);
+            }
+        }
+    }
+
+    /// Fade the volume out as the sleep timer is about to elapse, then stop playback
+    fn tick_sleep_timer(&self) {
+        let elapsed = match self.sleep_timer.lock().unwrap().as_ref() {
+            Some(timer) if Instant::now() >= timer.deadline => true,
+            Some(timer) if timer.fade_gain() < 1.0 => false,
+            _ => return,
+        };
+        if elapsed {
+            // Stopped while faded out, the volume is only restored once nothing plays.
+            // Like a stop requested by the user, nothing is left to be spoken later.
+            if let Err(e) = self.stop_speech() {
+                self.report_internal_error("Stopping at the end of the sleep timer failed", &e);
+            }
+            self.sleep_timer.lock().unwrap().take();
+        }
+        if let Err(e) = self.update_gain() {
+            self.report_internal_error("Fading out for the sleep timer failed", &e);
+        }
+        if elapsed {
+            self.player.notify(|sink| sink.on_sleep_timer_elapsed());
+        }
+    }
+
+    /// Stop playback once `duration` has passed, fading the volume out over the last
+    /// 30 seconds if `fade` is true. None cancels the sleep timer.
+    pub fn set_sleep_timer(&self, duration: Option<Duration>, fade: bool) -> NeosynthResult<()> {
+        *self.sleep_timer.lock().unwrap() = duration.map(|duration| SleepTimer {
+            deadline: Instant::now() + duration,
+            fade,
+        });
+        self.update_gain()
+    }
+
+    /// Report quiet hours starting or ending, adjusting the volume and speaking the
+    /// deferred utterances once they end
+    fn tick_quiet_hours(&self) {
+        let quiet_hours = *self.quiet_hours.lock().unwrap();
+        let active = quiet_hours.is_some_and(|hours| hours.is_active().unwrap_or(false));
+        if self.quiet_active.swap(active, Ordering::SeqCst) == active {
+            return;
+        }
+        self.player.notify(|sink| sink.on_quiet_hours(active));
+        if let Err(e) = self.update_gain() {
+            self.report_internal_error("Adjusting the volume for quiet hours failed", &e);
+        }
+        if !active && self.get_state().ok() == Some(SynthState::Ready) {
+            if let Err(e) = self.speak_deferred() {
+                self.report_internal_error("Speaking deferred utterances failed", &e);
+            }
+        }
+    }
+
+    /// Play only priority speech normally during the given quiet hours, None to disable them.
+    /// Utterances deferred so far are spoken once quiet hours are disabled.
+    pub fn set_quiet_hours(&self, quiet_hours: Option<QuietHours>) {
+        *self.quiet_hours.lock().unwrap() = quiet_hours;
+        self.tick_quiet_hours();
+    }
+
+    /// Whether an utterance which isn't a priority should be deferred right now
+    pub fn should_defer(&self) -> NeosynthResult<bool> {
+        let quiet_hours = *self.quiet_hours.lock().unwrap();
+        let quiet = match quiet_hours {
+            Some(hours) if hours.mode == QuietMode::Defer => hours.is_active()?,
+            _ => false,
+        };
+        Ok(quiet || !self.acquire_focus())
+    }
+
+    /// Share the audio focus with the other processes using the given focus name, None to
+    /// speak regardless of them
+    pub fn set_audio_focus(&self, name: Option<&str>) -> NeosynthResult<()> {
+        let focus = name.map(AudioFocus::new).transpose()?;
+        // Dropping the previous focus releases it
+        *self.audio_focus.lock().unwrap() = focus;
+        if self.get_state()? != SynthState::Ready {
+            self.acquire_focus();
+        }
+        Ok(())
+    }
+
+    /// Take the audio focus for the speech about to start, returns false if another
+    /// process holds it
+    fn acquire_focus(&self) -> bool {
+        match self.audio_focus.lock().unwrap().as_ref() {
+            Some(focus) => focus.try_acquire(),
+            None => true,
+        }
+    }
+
+    fn release_focus(&self) {
+        if let Some(focus) = self.audio_focus.lock().unwrap().as_ref() {
+            focus.release();
+        }
+    }
+
+    /// Speak the utterances deferred while another process held the audio focus, once
+    /// it is released
+    fn tick_audio_focus(&self) {
+        if self.audio_focus.lock().unwrap().is_none()
+            || self.deferred.lock().unwrap().is_empty()
+            || self.get_state().ok() != Some(SynthState::Ready)
+        {
+            return;
+        }
+        if let Err(e) = self.speak_deferred() {
+            self.report_internal_error("Speaking deferred utterances failed", &e);
+        }
+    }
+
+    /// Speak the utterance with the given id at the full volume during quiet hours in
+    /// `Reduce` mode
+    pub fn set_priority(&self, id: u64) -> NeosynthResult<()> {
+        *self.priority_utterance.lock().unwrap() = Some(id);
+        self.update_gain()
+    }
+
+    /// Keep an utterance until quiet hours end, returns the id it will be spoken with.
+    /// Once the maximum number of deferred utterances is reached, this fails or waits for
+    /// one to be spoken or dropped depending on the policy.
+    pub fn defer(&self, utterance: Vec<SpeechElement>) -> NeosynthResult<u64> {
+        let mut deferred = self.deferred.lock().unwrap();
+        loop {
+            let max_deferred = *self.max_deferred.lock().unwrap();
+            let Some((max, policy)) = max_deferred else {
+                break;
+            };
+            if deferred.len() < max {
+                break;
+            }
+            if policy == QueueFullPolicy::Raise {
+                return Err(NeosynthError::QueueFull(max));
+            }
+            if self.is_closed() {
+                return Err(NeosynthError::Cancelled);
+            }
+            // Also woken up regularly, to notice the synthesizer being closed
+            deferred = self
+                .deferred_freed
+                .wait_timeout(deferred, TICK_INTERVAL)
+                .unwrap()
+                .0;
+        }
+        let id = self.new_utterance_id();
+        deferred.push_back((id, utterance));
+        drop(deferred);
+        self.player.notify(|sink| sink.on_speech_deferred(id));
+        // Quiet hours may have ended while waiting
+        if self.get_state()? == SynthState::Ready {
+            self.speak_deferred()?;
+        }
+        Ok(id)
+    }
+
+    /// Limit the number of deferred utterances, None to keep any number of them
+    pub fn set_max_deferred(&self, max_deferred: Option<(usize, QueueFullPolicy)>) {
+        *self.max_deferred.lock().unwrap() = max_deferred;
+        self.deferred_freed.notify_all();
+    }
+
+    /// Speak the oldest deferred utterance, if any, quiet hours are over and no other
+    /// process holds the audio focus. Returns whether one was started.
+    fn speak_deferred(&self) -> NeosynthResult<bool> {
+        if self.quiet_active.load(Ordering::SeqCst)
+            || self.deferred.lock().unwrap().is_empty()
+            || !self.acquire_focus()
+        {
+            return Ok(false);
+        }
+        let next = self.deferred.lock().unwrap().pop_front();
+        let Some((id, utterance)) = next else {
+            return Ok(false);
+        };
+        self.deferred_freed.notify_all();
+        self.speak_with_id(id, utterance)?;
+        self.set_busy()?;
+        self.player.play()?;
+        Ok(true)
+    }
+    /// Report the voices installed or uninstalled since the last check
+    fn tick_voice_watch(&self, elapsed: Duration) {
+        let (added, removed) = {
+            let mut state = self.voice_watch.lock().unwrap();
+            state.since_check += elapsed;
+            if state.since_check < VOICE_WATCH_INTERVAL {
+                return;
+            }
+            state.since_check = Duration::ZERO;
+            let Ok(voice_ids) = installed_voice_ids() else {
+                return;
+            };
+            let Some(known_ids) = state.voice_ids.replace(voice_ids.clone()) else {
+                return;
+            };
+            let added: Vec<_> = voice_ids
+                .iter()
+                .filter(|id| !known_ids.contains(id))
+                .cloned()
+                .collect();
+            let removed: Vec<_> = known_ids
+                .into_iter()
+                .filter(|id| !voice_ids.contains(id))
+                .collect();
+            (added, removed)
+        };
+        if !added.is_empty() || !removed.is_empty() {
+            self.player
+                .notify(|sink| sink.on_voices_changed(added, removed));
+        }
+    }
+
+    /// Emit bookmarks every given interval of played audio, None to disable.
+    /// The bookmarks are named `auto:<element index>:<seconds into the element>`.
+    pub fn set_auto_bookmark_interval(&self, interval: Option<Duration>) {
+        *self.auto_bookmark.lock().unwrap() =
+            interval
+                .filter(|interval| !interval.is_zero())
+                .map(|interval| AutoBookmarkState {
+                    interval,
+                    played: Duration::ZERO,
+                });
+    }
+
+    /// Report the playback progress every given interval of played audio, None to disable.
+    /// The interval is rounded up to the mixer's tick interval.
+    pub fn set_progress_interval(&self, interval: Option<Duration>) {
+        *self.progress.lock().unwrap() =
+            interval
+                .filter(|interval| !interval.is_zero())
+                .map(|interval| ProgressState {
+                    interval,
+                    played: Duration::ZERO,
+                });
+    }
+
+    fn tick_progress(&self, elapsed: Duration) {
+        if self.player.get_playback_state().ok() != Some(MediaPlaybackState::Playing) {
+            return;
+        }
+        let due = match self.progress.lock().unwrap().as_mut() {
+            Some(state) => {
+                state.played += elapsed;
+                if state.played >= state.interval {
+                    state.played = Duration::ZERO;
+                    true
+                } else {
+                    false
+                }
+            }
+            None => false,
+        };
+        if !due {
+            return;
+        }
+        if let Ok((position, duration)) = self.player.position_and_duration() {
+            let (position_ms, duration_ms) =
+                (position.as_millis() as u64, duration.as_millis() as u64);
+            self.player
+                .notify(|sink| sink.on_progress(position_ms, duration_ms));
+        }
+    }
+
+    /// Play silence of the given duration between consecutive audible elements, None to
+    /// play them back to back
+    pub fn set_inter_element_silence(&self, silence: Option<Duration>) {
+        *self.inter_element_silence.lock().unwrap() = silence.filter(|s| !s.is_zero());
+    }
+
+    /// Report silence lasting at least `threshold` while playing speech, None to disable
+    pub fn set_silence_threshold(&self, threshold: Option<Duration>) {
+        *self.silence_threshold.lock().unwrap() = threshold.filter(|t| !t.is_zero());
+    }
+
+    /// Find the silent stretches of a synthesized stream about to be played
+    fn detect_silence(&self, stream: &SpeechSynthesisStream) -> NeosynthResult<()> {
+        let threshold = *self.silence_threshold.lock().unwrap();
+        let stretches = match threshold {
+            Some(threshold) => {
+                let audio = self.read_stream(stream)?;
+                match (WaveFormat::parse(&audio), wave::samples(&audio)) {
+                    (Some(format), Some(pcm)) => {
+                        wave::silent_stretches(&format, pcm, threshold.as_secs_f64())
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            None => Vec::new(),
+        };
+        *self.silent_stretches.lock().unwrap() = stretches;
+        Ok(())
+    }
+
+    fn tick_silence_detection(&self) {
+        let Some(threshold) = *self.silence_threshold.lock().unwrap() else {
+            return;
+        };
+        if self.player.get_playback_state().ok() != Some(MediaPlaybackState::Playing) {
+            return;
+        }
+        let Ok(position) = self
+            .player
+            .0
+            .PlaybackSession()
+            .and_then(|session| session.Position())
+        else {
+            return;
+        };
+        let position = timespan_to_duration(position).as_secs_f64();
+        let detected = {
+            let mut stretches = self.silent_stretches.lock().unwrap();
+            stretches
+                .iter()
+                .position(|(start, end)| {
+                    position >= start + threshold.as_secs_f64() && position < *end
+                })
+                .map(|idx| position - stretches.remove(idx).0)
+        };
+        if let Some(seconds) = detected {
+            self.log(
+                &format!("Playing silence for {:.1} seconds", seconds),
+                LogLevel::Warning,
+            );
+            self.player.notify(|sink| sink.on_silence_detected(seconds));
+        }
+    }
+
+    fn tick_auto_bookmark(&self, elapsed: Duration) {
+        if self.player.get_playback_state().ok() != Some(MediaPlaybackState::Playing) {
+            return;
+        }
+        let due = match self.auto_bookmark.lock().unwrap().as_mut() {
+            Some(state) => {
+                state.played += elapsed;
+                if state.played >= state.interval {
+                    state.played -= state.interval;
+                    true
+                } else {
+                    false
+                }
+            }
+            None => false,
+        };
+        if !due {
+            return;
+        }
+        let current = *self.current_element.lock().unwrap();
+        if let Some((index, _, _)) = current {
+            let position = self
+                .player
+                .0
+                .PlaybackSession()
+                .and_then(|session| session.Position())
+                .map(|position| timespan_to_duration(position).as_secs_f64())
+                .unwrap_or_default();
+            let bookmark = format!("auto:{}:{:.1}", index, position);
+            self.player
+                .notify(|sink| sink.on_bookmark_reached(bookmark));
+        }
+    }
+
+    /// Pause the speech after `limit` worth of speech, the limit applies once
+    pub fn set_reading_limit(&self, limit: Option<ReadingLimit>) {
+        *self.reading_limit.lock().unwrap() = limit.map(|limit| ReadingLimitState {
+            limit,
+            spoken_time: Duration::ZERO,
+            spoken_chars: 0,
+        });
+    }
+
+    /// Count characters about to be spoken, returns true if the reading limit is now reached
+    fn count_spoken_chars(&self, text: &str, is_ssml: bool) -> bool {
+        match self.reading_limit.lock().unwrap().as_mut() {
+            Some(state) => {
+                let reached_before = matches!(state.limit, ReadingLimit::Characters(limit) if state.spoken_chars >= limit);
+                state.spoken_chars += text::spoken_len(text, is_ssml);
+                reached_before
+            }
+            None => false,
+        }
+    }
+
+    fn reading_limit_reached(&self) -> NeosynthResult<()> {
+        self.reading_limit.lock().unwrap().take();
+        self.pause(StateChangeReason::ReadingLimit)?;
+        self.player.notify(|sink| sink.on_reading_limit_reached());
+        Ok(())
+    }
+
+    /// Move to the given position within the element being played or paused.
+    /// Returns false if nothing is playing or the element can't seek.
+    pub fn seek(&self, position: Duration) -> NeosynthResult<bool> {
+        if self.get_state()? == SynthState::Ready {
+            return Ok(false);
+        }
+        let moved = self.player.seek(position)?;
+        if moved {
+            *self.stall.lock().unwrap() = StallState::default();
+        }
+        Ok(moved)
+    }
+
+    /// Move forward or back by `delta` within the element being played or paused, without
+    /// leaving it. Returns false if nothing is playing or the element can't seek.
+    pub fn seek_by(&self, delta: Duration, forward: bool) -> NeosynthResult<bool> {
+        if self.get_state()? == SynthState::Ready {
+            return Ok(false);
+        }
+        let (position, _) = self.player.position_and_duration()?;
+        let target = if forward {
+            position.saturating_add(delta)
+        } else {
+            position.saturating_sub(delta)
+        };
+        // The player clamps positions past the end of the element
+        self.seek(target)
+    }
+
+    pub fn pause(&self, reason: StateChangeReason) -> NeosynthResult<()> {
+        self.set_state(SynthState::Paused, reason)?;
+        self.player.pause()?;
+        Ok(())
+    }
+
+    /// Report an error which can't be returned to any caller, it is also logged as an error
+    fn report_internal_error(&self, context: &str, error: &NeosynthError) {
+        let message = format!("{}: {}", context, error);
+        self.log(&message, LogLevel::Error);
+        self.player.notify(|sink| sink.on_internal_error(&message));
+    }
+
+    /// Send a message to the event sink, unless it is below the configured log level
+    pub fn log(&self, message: &str, level: LogLevel) {
+        if level >= *self.log_level.read().unwrap() {
+            self.player.notify(|sink| sink.log(message, level.name()));
+        }
+    }
+
+    pub fn set_log_level(&self, level: LogLevel) {
+        *self.log_level.write().unwrap() = level;
+    }
+
+    pub fn get_log_level(&self) -> LogLevel {
+        *self.log_level.read().unwrap()
+    }
+
+    pub fn get_state(&self) -> NeosynthResult<SynthState> {
+        Ok(*self.state.read().unwrap())
+    }
+
+    pub fn set_state(&self, state: SynthState, reason: StateChangeReason) -> NeosynthResult<()> {
+        let old_state = std::mem::replace(&mut *self.state.write().unwrap(), state);
+        if old_state != state {
+            self.player
+                .notify(|sink| sink.on_state_changed(old_state, state, reason));
+        }
+        Ok(())
+    }
+
+    pub fn speak_content(&self, text: &str, is_ssml: bool) -> NeosynthResult<()> {
+        let acronyms = self.document_acronyms.lock().unwrap().clone();
+        let prepared = self.prepare_text(&self.synthesizer, text, is_ssml, &acronyms)?;
+        let offsets = (prepared != text).then(|| OffsetMap::diff(text, &prepared));
+        self.speak_prepared(prepared, is_ssml, offsets)
+    }
+
+    /// Speak content the text filters were applied to already, `offsets` maps the offsets
+    /// in it back to the element's text if it was rewritten
+    fn speak_prepared(
+        &self,
+        text: String,
+        is_ssml: bool,
+        offsets: Option<OffsetMap>,
+    ) -> NeosynthResult<()> {
+        let tone = *self.tone.lock().unwrap();
+        let (text, is_ssml, offsets) = match tone {
+            Tone::Normal => (text, is_ssml, offsets),
+            tone => {
+                let language = self.synthesizer.Voice()?.Language()?.to_string();
+                let (text, is_ssml, tone_offsets) = tone.apply(&text, is_ssml, &language);
+                let offsets = match (tone_offsets, offsets) {
+                    (Some(tone_offsets), Some(offsets)) => Some(tone_offsets.compose(&offsets)),
+                    (tone_offsets, offsets) => tone_offsets.or(offsets),
+                };
+                (text, is_ssml, offsets)
+            }
+        };
+        let text = &text;
+        self.apply_rate_ramp()?;
+        let stream = match self.take_prefetched(text, is_ssml)? {
+            Some(stream) => stream,
+            None => self.profiler.time(Stage::Synthesis, || {
+                self.generate_speech_stream(text, is_ssml)
+            })?,
+        };
+        if let Some(trigger) = self.prefetch_trigger.get() {
+            trigger.send(()).ok();
+        }
+        // Calibration is best effort, it must not get in the way of speaking
+        self.calibrate_duration(&stream, text, is_ssml).ok();
+        if let Err(e) = self.check_fingerprint(&stream, text, is_ssml) {
+            self.log(&format!("Fingerprinting failed: {}", e), LogLevel::Warning);
+        }
+        let offsets = offsets.map(Arc::new);
+        self.profiler
+            .time(Stage::SourceSet, || self.set_stream_source(stream, offsets))?;
+        self.player.play()?;
+        // The element is kept loaded, so resuming starts speaking it
+        if self.count_spoken_chars(text, is_ssml) {
+            self.reading_limit_reached()?;
+        }
+        Ok(())
+    }
+
+    /// Speak the following elements with the given tone, adjusting the player's gain
+    fn set_tone(&self, tone: Tone) -> NeosynthResult<()> {
+        let changed = {
+            let mut current = self.tone.lock().unwrap();
+            std::mem::replace(&mut *current, tone) != tone
+        };
+        if changed {
+            self.update_gain()?;
+        }
+        Ok(())
+    }
+
+    /// Apply the gain of the current tone, reduced for speech which isn't a priority during
+    /// quiet hours in `Reduce` mode and as the sleep timer fades out
+    fn update_gain(&self) -> NeosynthResult<()> {
+        let mut gain = self.tone.lock().unwrap().gain();
+        if let Some(timer) = self.sleep_timer.lock().unwrap().as_ref() {
+            gain *= timer.fade_gain();
+        }
+        let reduce = matches!(
+            *self.quiet_hours.lock().unwrap(),
+            Some(QuietHours {
+                mode: QuietMode::Reduce,
+                ..
+            })
+        );
+        let priority = {
+            let current = *self.current_utterance.lock().unwrap();
+            current.is_some() && current == *self.priority_utterance.lock().unwrap()
+        };
+        if reduce && !priority && self.quiet_active.load(Ordering::SeqCst) {
+            gain *= quiet::QUIET_GAIN;
+        }
+        self.player.set_gain(gain)
+    }
+
+    /// Apply the enabled text filters to the content of an element before synthesizing it,
+    /// with the acronym expansions of its utterance up to it. The offsets of the words
+    /// spoken are mapped back by diffing the result with the content.
+    fn prepare_text(
+        &self,
+        synthesizer: &SpeechSynthesizer,
+        text: &str,
+        is_ssml: bool,
+        acronyms: &HashMap<String, String>,
+    ) -> NeosynthResult<String> {
+        let mut cleaned = None;
+        if !is_ssml && self.line_joining.load(Ordering::SeqCst) {
+            cleaned = Some(text::join_broken_lines(text));
+        }
+        if self.bidi_cleanup.load(Ordering::SeqCst) {
+            let content = cleaned.as_deref().unwrap_or(text);
+            cleaned = Some(text::clean_bidi_text(content));
+        }
+        if let (false, Some(runs)) = (is_ssml, *self.punctuation_runs.read().unwrap()) {
+            let content = cleaned.as_deref().unwrap_or(text);
+            cleaned = Some(text::collapse_punctuation_runs(content, runs));
+        }
+        let rules = *self.reading_rules.read().unwrap();
+        // Markup could be mistaken for text, SSML has say-as for this anyway
+        if !is_ssml && !rules.is_empty() {
+            let language = synthesizer.Voice()?.Language()?.to_string();
+            let content = cleaned.as_deref().unwrap_or(text);
+            cleaned = Some(reading::apply_reading_rules(content, rules, &language));
+        }
+        if !is_ssml {
+            let filter = self.acronym_filter.read().unwrap();
+            let expansions_only = AcronymFilter::default();
+            let filter = match filter.as_ref() {
+                Some(filter) => Some(filter),
+                // The utterance's expansions apply even without a filter
+                None if !acronyms.is_empty() => Some(&expansions_only),
+                None => None,
+            };
+            if let Some(filter) = filter {
+                let content = cleaned.as_deref().unwrap_or(text);
+                cleaned = Some(filter.apply(content, acronyms));
+            }
+        }
+        if let Some(mode) = *self.symbol_mode.read().unwrap() {
+            let content = cleaned.as_deref().unwrap_or(text);
+            let language = synthesizer.Voice()?.Language()?.to_string();
+            cleaned = Some(symbols::verbalize_symbols(content, mode, &language));
+        }
+        Ok(cleaned.unwrap_or_else(|| text.to_string()))
+    }
+
+    /// Hand a synthesized stream to the player, passing it through the audio transform if there is one
+    fn set_stream_source(
+        &self,
+        stream: SpeechSynthesisStream,
+        offsets: Option<Arc<OffsetMap>>,
+    ) -> NeosynthResult<()> {
+        *self.word_boundaries.lock().unwrap() =
+            word_boundaries(&stream, &offsets).unwrap_or_default();
+        *self.sentence_starts.lock().unwrap() = sentence_starts(&stream).unwrap_or_default();
+        // Silence detection is best effort, it must not get in the way of speaking
+        self.detect_silence(&stream).ok();
+        let transform = self.audio_transform.read().unwrap().clone();
+        let Some(transform) = transform else {
+            return self.player.set_speech_stream_source(stream, offsets);
+        };
+        match self.transform_stream(&stream, transform.as_ref()) {
+            Ok(audio) => self.player.set_processed_source(&stream, &audio, offsets),
+            Err(e) => {
+                // Rather play the unprocessed speech than nothing
+                self.log(&e.to_string(), LogLevel::Warning);
+                self.player.set_speech_stream_source(stream, offsets)
+            }
+        }
+    }
+
+    fn transform_stream(
+        &self,
+        stream: &SpeechSynthesisStream,
+        transform: &dyn AudioTransform,
+    ) -> NeosynthResult<Vec<u8>> {
+        let audio = self.read_stream(stream)?;
+        let invalid = || OperationError("The speech stream is not a valid wave stream".to_string());
+        let format = WaveFormat::parse(&audio).ok_or_else(invalid)?;
+        let pcm = wave::samples(&audio).ok_or_else(invalid)?.to_vec();
+        let processed = transform.process(&format, pcm)?;
+        wave::replace_samples(&audio, &processed).ok_or_else(invalid)
+    }
+
+    /// Read the whole wave file of a synthesized stream
+    fn read_stream(&self, stream: &SpeechSynthesisStream) -> NeosynthResult<Vec<u8>> {
+        let size = u32::try_from(stream.Size()?)
+            .map_err(|_| OperationError("The speech stream is too large".to_string()))?;
+        wave::read_bytes(&stream.GetInputStreamAt(0)?, size, &self.async_ops)
+    }
+
+    /// Set the transform applied to synthesized speech before it is played, None to remove it
+    pub fn set_audio_transform(&self, transform: Option<Arc<dyn AudioTransform>>) {
+        *self.audio_transform.write().unwrap() = transform;
+    }
+
+    /// Measure the duration of a synthesized stream to refine the duration estimates of the current voice
+    fn calibrate_duration(
+        &self,
+        stream: &SpeechSynthesisStream,
+        text: &str,
+        is_ssml: bool,
+    ) -> NeosynthResult<()> {
+        self.duration_estimator.record(
+            &self.synthesizer.Voice()?.Id()?.to_string(),
+            text::spoken_len(text, is_ssml),
+            stream_duration(stream, &self.async_ops)?,
+            self.rate_multiplier()?,
+        );
+        Ok(())
+    }
+
+    /// Compare a synthesized stream with what the same content and settings produced
+    /// before, if fingerprinting is enabled
+    fn check_fingerprint(
+        &self,
+        stream: &SpeechSynthesisStream,
+        text: &str,
+        is_ssml: bool,
+    ) -> NeosynthResult<()> {
+        let fingerprints = self.fingerprints.read().unwrap();
+        let Some(store) = fingerprints.as_ref() else {
+            return Ok(());
+        };
+        let key = Self::synthesis_key(&self.synthesizer, text, is_ssml)?;
+        if store.record(&key, &self.read_stream(stream)?) == FingerprintMatch::Changed {
+            let voice_id = self.synthesizer.Voice()?.Id()?.to_string();
+            self.player
+                .notify(|sink| sink.on_synthesis_changed(&voice_id, text));
+        }
+        Ok(())
+    }
+
+    /// Record the hash of the audio synthesized for each element in the given file and
+    /// compare it with the hash recorded by previous runs, None to stop fingerprinting
+    pub fn set_fingerprint_file(&self, path: Option<&Path>) -> NeosynthResult<()> {
+        *self.fingerprints.write().unwrap() = path.map(FingerprintStore::open).transpose()?;
+        Ok(())
+    }
+
+    /// The current speaking rate as a multiple of the normal rate
+    fn rate_multiplier(&self) -> NeosynthResult<f64> {
+        if Self::is_prosody_supported()? {
+            Ok(self.synthesizer.Options()?.SpeakingRate()?)
+        } else {
+            Ok(1.0)
+        }
+    }
+
+    /// Estimate the time in seconds needed to speak the given elements with the current settings
+    pub fn estimate_duration<'a, I>(&self, elements: I) -> NeosynthResult<f64>
+    where
+        I: IntoIterator<Item = &'a SpeechElement>,
+    {
+        let mut rate_multiplier = self.rate_multiplier()?;
+        let mut voice_id = self.synthesizer.Voice()?.Id()?.to_string();
+        let mut seconds = 0.0;
+        for element in elements {
+            let element = match element {
+                SpeechElement::Skippable(_) if self.should_skip() => continue,
+                SpeechElement::Skippable(element) => element.as_ref(),
+                element => element,
+            };
+            seconds += match element {
+                SpeechElement::Text(text)
+                | SpeechElement::Structured(_, text)
+                | SpeechElement::MarkedText(_, text) => self.duration_estimator.estimate(
+                    &voice_id,
+                    text::spoken_len(text, false),
+                    rate_multiplier,
+                ),
+                SpeechElement::Ssml(ssml) => self.duration_estimator.estimate(
+                    &voice_id,
+                    text::spoken_len(ssml, true),
+                    rate_multiplier,
+                ),
+                SpeechElement::Code(block) => self.duration_estimator.estimate(
+                    &voice_id,
+                    text::spoken_len(&code::code_ssml("", block), true),
+                    rate_multiplier * code::CODE_RATE,
+                ),
+                // Looped audio is counted once
+                SpeechElement::Audio(filename) | SpeechElement::LoopedAudio(filename) => {
+                    audio_file_duration(filename, &self.async_ops)?
+                }
+                SpeechElement::Voice(voice) | SpeechElement::AutoVoice(voice) => {
+                    voice_id = voice.Id()?.to_string();
+                    0.0
+                }
+                SpeechElement::Synthesized(stream) => stream_duration(stream, &self.async_ops)?,
+                SpeechElement::Profile(profile) => {
+                    if let Some(voice) = &profile.voice {
+                        voice_id = voice.Id()?.to_string();
+                    }
+                    if let Some(rate) = profile.rate {
+                        if Self::is_prosody_supported()? {
+                            rate_multiplier = rate * 0.06;
+                        }
+                    }
+                    0.0
+                }
+                SpeechElement::Bookmark(_)
+                | SpeechElement::Skippable(_)
+                | SpeechElement::Acronyms(_)
+                | SpeechElement::Tone(_) => 0.0,
+            };
+        }
+        Ok(seconds)
+    }
+
+    /// Create a synthesizer with the same voice and options as the one used for speaking
+    fn clone_synthesizer(&self) -> NeosynthResult<SpeechSynthesizer> {
+        let synthesizer = SpeechSynthesizer::new()?;
+        synthesizer.SetVoice(&self.synthesizer.Voice()?)?;
+        let options = self.synthesizer.Options()?;
+        let cloned_options = synthesizer.Options()?;
+        cloned_options.SetAudioPitch(options.AudioPitch()?)?;
+        if Self::is_prosody_supported()? {
+            cloned_options.SetSpeakingRate(options.SpeakingRate()?)?;
+            cloned_options.SetAudioVolume(options.AudioVolume()?)?;
+            cloned_options
+                .SetIncludeWordBoundaryMetadata(options.IncludeWordBoundaryMetadata()?)?;
+            cloned_options
+                .SetIncludeSentenceBoundaryMetadata(options.IncludeSentenceBoundaryMetadata()?)?;
+        }
+        if ApiInformation::IsApiContractPresentByMajorAndMinor(
+            &HSTRING::from("Windows.Foundation.UniversalApiContract"),
+            6,
+            0,
+        )? {
+            cloned_options.SetAppendedSilence(options.AppendedSilence()?)?;
+            cloned_options.SetPunctuationSilence(options.PunctuationSilence()?)?;
+        }
+        Ok(synthesizer)
+    }
+
+    /// Synthesize the text and SSML elements ahead of time with the current settings,
+    /// without disturbing the speech in progress
+    pub fn prefetch<I>(&self, elements: I) -> NeosynthResult<Vec<SpeechElement>>
+    where
+        I: IntoIterator<Item = SpeechElement>,
+    {
+        let synthesizer = self.clone_synthesizer()?;
+        // Only the utterance's own expansions apply, not those of the one playing
+        let mut acronyms = HashMap::new();
+        elements
+            .into_iter()
+            .flat_map(|element| match element {
+                // A synthesized stream keeps no text to map its word offsets back to, the
+                // mark is at its start anyway
+                SpeechElement::MarkedText(mark, text) => {
+                    vec![SpeechElement::Bookmark(mark), SpeechElement::Text(text)]
+                }
+                element => vec![element],
+            })
+            .map(|element| self.prefetch_element(&synthesizer, element, &mut acronyms))
+            .collect()
+    }
+
+    /// Identify synthesized speech by its content and the settings it was synthesized with
+    fn synthesis_key(
+        synthesizer: &SpeechSynthesizer,
+        text: &str,
+        is_ssml: bool,
+    ) -> NeosynthResult<String> {
+        let options = synthesizer.Options()?;
+        let (rate, volume) = if Self::is_prosody_supported()? {
+            (options.SpeakingRate()?, options.AudioVolume()?)
+        } else {
+            (1.0, 1.0)
+        };
+        // Both are chosen when the engine is created and change the pauses in the audio
+        let (appended_silence, punctuation_silence) =
+            if ApiInformation::IsApiContractPresentByMajorAndMinor(
+                &HSTRING::from("Windows.Foundation.UniversalApiContract"),
+                6,
+                0,
+            )? {
+                (
+                    options.AppendedSilence()?.0,
+                    options.PunctuationSilence()?.0,
+                )
+            } else {
+                (0, 0)
+            };
+        Ok(format!(
+            "{}|{}|{}|{}|{}|{}|{}|{}",
+            synthesizer.Voice()?.Id()?,
+            options.AudioPitch()?,
+            rate,
+            volume,
+            appended_silence,
+            punctuation_silence,
+            is_ssml,
+            text
+        ))
+    }
+
+    /// Take the stream synthesized ahead of time for filtered content, if it was
+    /// synthesized with the current settings
+    fn take_prefetched(
+        &self,
+        text: &str,
+        is_ssml: bool,
+    ) -> NeosynthResult<Option<SpeechSynthesisStream>> {
+        let mut prefetched = self.prefetched.lock().unwrap();
+        if prefetched.is_empty() {
+            return Ok(None);
+        }
+        let key = Self::synthesis_key(&self.synthesizer, text, is_ssml)?;
+        let stream = prefetched
+            .iter()
+            .position(|(prefetched_key, _)| *prefetched_key == key)
+            .and_then(|idx| prefetched.remove(idx))
+            .map(|(_, stream)| stream);
+        Ok(stream)
+    }
+
+    /// Set how many upcoming elements are synthesized while the current one plays, 0 to
+    /// only synthesize elements once they are reached
+    pub fn set_prefetch_count(&self, count: usize) {
+        self.prefetch_count.store(count, Ordering::SeqCst);
+        if count == 0 {
+            self.prefetched.lock().unwrap().clear();
+        }
+    }
+
+    /// Synthesize the spoken elements at the front of the queue which weren't yet.
+    /// Failures are left to be reported when the elements are reached.
+    fn prefetch_upcoming(&self) {
+        let count = self.prefetch_count.load(Ordering::SeqCst);
+        if count == 0 {
+            return;
+        }
+        // Only plain speech is prefetched, toned speech is rewritten as it is spoken
+        if *self.tone.lock().unwrap() != Tone::Normal {
+            return;
+        }
+        // The settings of the current element were applied already
+        let Ok(synthesizer) = self.clone_synthesizer() else {
+            return;
+        };
+        let upcoming: Vec<_> = {
+            let queue = self.speech_queue.lock().unwrap();
+            queue.iter().map(|(_, element)| element.clone()).collect()
+        };
+        let preview = self.preview_mode.load(Ordering::SeqCst);
+        let mut keys = Vec::with_capacity(count);
+        for element in upcoming {
+            if keys.len() >= count || self.is_closed() {
+                break;
+            }
+            let element = match element {
+                SpeechElement::Skippable(_) if self.should_skip() => continue,
+                SpeechElement::Skippable(element) => *element,
+                // These change how the following text is spoken once they are reached
+                SpeechElement::Acronyms(_) | SpeechElement::Tone(_) => break,
+                element => element,
+            };
+            let element = match element {
+                element if !preview => element,
+                element => match Self::preview_element(element) {
+                    Some(element) => element,
+                    None => continue,
+                },
+            };
+            let Some(element) = self.filter_kind(element) else {
+                continue;
+            };
+            match self.prefetch_content(&synthesizer, element) {
+                Ok(Some(key)) => keys.push(key),
+                Ok(None) => {}
+                // The following elements depend on what this one changes
+                Err(_) => break,
+            }
+        }
+        let mut prefetched = self.prefetched.lock().unwrap();
+        prefetched.retain(|(key, _)| keys.contains(key));
+    }
+
+    /// Synthesize a spoken element as `process_admitted` would, returning the key of
+    /// its stream. Other elements only change the settings of the synthesizer.
+    fn prefetch_content(
+        &self,
+        synthesizer: &SpeechSynthesizer,
+        element: SpeechElement,
+    ) -> NeosynthResult<Option<String>> {
+        // Acronyms elements end the look-ahead, those reached so far are the ones to apply
+        let mut acronyms = self.document_acronyms.lock().unwrap().clone();
+        let (text, is_ssml) = match element {
+            SpeechElement::Text(text) | SpeechElement::Structured(_, text) => (
+                self.prepare_text(synthesizer, &text, false, &acronyms)?,
+                false,
+            ),
+            SpeechElement::Ssml(ssml) => (
+                self.prepare_text(synthesizer, &ssml, true, &acronyms)?,
+                true,
+            ),
+            SpeechElement::MarkedText(mark, text) => {
+                let language = synthesizer.Voice()?.Language()?.to_string();
+                let text = self.prepare_text(synthesizer, &text, false, &acronyms)?;
+                (text::marked_ssml(&language, &mark, &text).0, true)
+            }
+            SpeechElement::Code(block) => {
+                let language = synthesizer.Voice()?.Language()?.to_string();
+                let ssml = code::code_ssml(&language, &block);
+                (
+                    self.prepare_text(synthesizer, &ssml, true, &acronyms)?,
+                    true,
+                )
+            }
+            element => {
+                self.prefetch_element(synthesizer, element, &mut acronyms)?;
+                return Ok(None);
+            }
+        };
+        let key = Self::synthesis_key(synthesizer, &text, is_ssml)?;
+        let cached = self
+            .prefetched
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(prefetched_key, _)| *prefetched_key == key);
+        if !cached {
+            let stream = synthesize(synthesizer, &text, is_ssml, &self.async_ops)?;
+            self.prefetched
+                .lock()
+                .unwrap()
+                .push_back((key.clone(), stream));
+        }
+        Ok(Some(key))
+    }
+
+    fn prefetch_element(
+        &self,
+        synthesizer: &SpeechSynthesizer,
+        element: SpeechElement,
+        acronyms: &mut HashMap<String, String>,
+    ) -> NeosynthResult<SpeechElement> {
+        match element {
+            _ if self.is_closed() => Err(NeosynthError::Cancelled),
+            SpeechElement::Text(text) => {
+                let text = self.prepare_text(synthesizer, &text, false, acronyms)?;
+                synthesize(synthesizer, &text, false, &self.async_ops)
+                    .map(SpeechElement::Synthesized)
+            }
+            SpeechElement::Ssml(ssml) => {
+                let ssml = self.prepare_text(synthesizer, &ssml, true, acronyms)?;
+                synthesize(synthesizer, &ssml, true, &self.async_ops)
+                    .map(SpeechElement::Synthesized)
+            }
+            SpeechElement::MarkedText(mark, text) => {
+                let language = synthesizer.Voice()?.Language()?.to_string();
+                let text = self.prepare_text(synthesizer, &text, false, acronyms)?;
+                synthesize(
+                    synthesizer,
+                    &text::marked_ssml(&language, &mark, &text).0,
+                    true,
+                    &self.async_ops,
+                )
+                .map(SpeechElement::Synthesized)
+            }
+            SpeechElement::Code(block) => {
+                let language = synthesizer.Voice()?.Language()?.to_string();
+                let ssml = self.prepare_text(
+                    synthesizer,
+                    &code::code_ssml(&language, &block),
+                    true,
+                    acronyms,
+                )?;
+                synthesize(synthesizer, &ssml, true, &self.async_ops)
+                    .map(SpeechElement::Synthesized)
+            }
+            SpeechElement::Voice(ref voice) | SpeechElement::AutoVoice(ref voice) => {
+                synthesizer.SetVoice(voice)?;
+                Ok(element)
+            }
+            // Synthesize the following elements with the profile's voice and prosody,
+            // the volume is applied by the player when the profile element is reached
+            SpeechElement::Profile(profile) => {
+                if let Some(voice) = &profile.voice {
+                    synthesizer.SetVoice(voice)?;
+                }
+                let options = synthesizer.Options()?;
+                if let Some(pitch) = profile.pitch {
+                    options.SetAudioPitch(pitch / 50.0)?;
+                }
+                if let Some(rate) = profile.rate {
+                    if Self::is_prosody_supported()? {
+                        options.SetSpeakingRate(rate * 0.06)?;
+                    }
+                }
+                Ok(SpeechElement::Profile(profile))
+            }
+            SpeechElement::Skippable(inner) => Ok(SpeechElement::Skippable(Box::new(
+                self.prefetch_element(synthesizer, *inner, acronyms)?,
+            ))),
+            // Expand the acronyms of the following elements as they will be when spoken
+            SpeechElement::Acronyms(expansions) => {
+                acronyms.extend(expansions.clone());
+                Ok(SpeechElement::Acronyms(expansions))
+            }
+            element => Ok(element),
+        }
+    }
+
+    pub fn get_pitch(&self) -> NeosynthResult<f64> {
+        Ok(self.synthesizer.Options()?.AudioPitch()? * 50.0)
+    }
+
+    pub fn set_pitch(&self, value: f64) -> NeosynthResult<()> {
+        Ok(self.synthesizer.Options()?.SetAudioPitch(value / 50.0)?)
+    }
+
+    /// Apply the settings present in a speech profile
+    pub fn apply_profile(&self, profile: &SpeechProfile) -> NeosynthResult<()> {
+        if let Some(voice) = &profile.voice {
+            self.synthesizer.SetVoice(voice)?;
+        }
+        if let Some(rate) = profile.rate {
+            self.set_rate(rate)?;
+        }
+        if let Some(pitch) = profile.pitch {
+            self.set_pitch(pitch)?;
+        }
+        if let Some(volume) = profile.volume {
+            self.player.set_volume(volume)?;
+        }
+        Ok(())
+    }
+
+    /// Get the current voice and prosody settings as a speech profile
+    pub fn current_profile(&self) -> NeosynthResult<SpeechProfile> {
+        Ok(SpeechProfile {
+            voice: Some(self.synthesizer.Voice()?),
+            rate: if Self::is_prosody_supported()? {
+                Some(self.get_rate()?)
+            } else {
+                None
+            },
+            pitch: Some(self.get_pitch()?),
+            volume: Some(self.player.get_volume()?),
+        })
+    }
+
+    /// Indicates if the prosody option is supported
+    pub fn is_prosody_supported() -> NeosynthResult<bool> {
+        Ok(ApiInformation::IsApiContractPresentByMajorAndMinor(
+            &HSTRING::from("Windows.Foundation.UniversalApiContract"),
+            5,
+            0,
+        )?)
+    }
+
+    pub fn get_rate(&self) -> NeosynthResult<f64> {
+        if !Self::is_prosody_supported()? {
+            Ok(-1.0)
+        } else {
+            Ok(self.synthesizer.Options()?.SpeakingRate()? / 0.06)
+        }
+    }
+
+    pub fn set_rate(&self, value: f64) -> NeosynthResult<()> {
+        if Self::is_prosody_supported()? {
+            Ok(self.synthesizer.Options()?.SetSpeakingRate(value * 0.06)?)
+        } else {
+            Err(NeosynthError::Unsupported(
+                "The current version of OneCore synthesizer does not support the prosody option"
+                    .to_string(),
+            ))
+        }
+    }
+
+    pub fn set_rate_ramp(&self, ramp: Option<RateRamp>) {
+        *self.rate_ramp.lock().unwrap() = ramp.map(RateRampState::new);
+    }
+
+    /// Increase the speaking rate if the rate ramp schedule says so
+    fn apply_rate_ramp(&self) -> NeosynthResult<()> {
+        let mut rate_ramp = self.rate_ramp.lock().unwrap();
+        if let Some(state) = rate_ramp.as_mut() {
+            if state.advance() && Self::is_prosody_supported()? {
+                let rate = self.get_rate()?;
+                if rate < state.ramp.max_rate {
+                    self.set_rate((rate + state.ramp.step).min(state.ramp.max_rate))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn generate_speech_stream(
+        &self,
+        text: &str,
+        is_ssml: bool,
+    ) -> NeosynthResult<SpeechSynthesisStream> {
+        let output = synthesize(&self.synthesizer, text, is_ssml, &self.async_ops);
+        let voice_id = self.synthesizer.Voice()?.Id()?.to_string();
+        match output {
+            Ok(output) => {
+                self.voice_health.record_success(&voice_id);
+                Ok(output)
+            }
+            Err(error) => {
+                // Cancelled synthesis says nothing about the health of the voice
+                if let Some(e) = error.windows_error() {
+                    self.voice_health.record_failure(
+                        &voice_id,
+                        is_ssml,
+                        e.code().0,
+                        &e.message().to_string(),
+                    );
+                }
+                let unavailable = error
+                    .windows_error()
+                    .and_then(|e| voice_unavailable_reason(e.code().0));
+                let error = match unavailable {
+                    Some(reason) => {
+                        match self.synthesize_with_fallback(&voice_id, reason, text, is_ssml) {
+                            Ok(output) => return Ok(output),
+                            Err(error) => error,
+                        }
+                    }
+                    None => error,
+                };
+                if let Some(e) = error.windows_error() {
+                    self.set_state(SynthState::Ready, StateChangeReason::Error)
+                        .ok();
+                    self.log(
+                        &format!("Error generating speech stream: {}", e.code().0),
+                        LogLevel::Error,
+                    );
+                } else if matches!(error, NeosynthError::VoiceUnavailable { .. }) {
+                    self.set_state(SynthState::Ready, StateChangeReason::Error)
+                        .ok();
+                    self.log(&error.to_string(), LogLevel::Error);
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Speak with the first voice of the fallback chain which can, after the voice with the
+    /// given id turned out to be unavailable. The fallback voice stays the current voice.
+    fn synthesize_with_fallback(
+        &self,
+        voice_id: &str,
+        reason: &str,
+        text: &str,
+        is_ssml: bool,
+    ) -> NeosynthResult<SpeechSynthesisStream> {
+        self.log(
+            &format!("Voice {} is unavailable: {}", voice_id, reason),
+            LogLevel::Warning,
+        );
+        let original_voice = self.synthesizer.Voice()?;
+        let fallbacks = self.voice_fallbacks.read().unwrap().clone();
+        for voice in fallbacks {
+            let fallback_id = voice.Id()?.to_string();
+            if fallback_id == voice_id {
+                continue;
+            }
+            self.synthesizer.SetVoice(&voice)?;
+            match synthesize(&self.synthesizer, text, is_ssml, &self.async_ops) {
+                Ok(output) => {
+                    self.voice_health.record_success(&fallback_id);
+                    self.player
+                        .notify(|sink| sink.on_voice_fallback(voice_id, &fallback_id, reason));
+                    return Ok(output);
+                }
+                Err(error) => {
+                    let Some(e) = error.windows_error() else {
+                        return Err(error);
+                    };
+                    self.voice_health.record_failure(
+                        &fallback_id,
+                        is_ssml,
+                        e.code().0,
+                        &e.message().to_string(),
+                    );
+                    // Other failures have to do with the content rather than the voice
+                    if voice_unavailable_reason(e.code().0).is_none() {
+                        self.synthesizer.SetVoice(&original_voice)?;
+                        return Err(error);
+                    }
+                }
+            }
+        }
+        self.synthesizer.SetVoice(&original_voice)?;
+        Err(NeosynthError::VoiceUnavailable {
+            voice_id: voice_id.to_string(),
+            reason: reason.to_string(),
         })
     }
 
-    pub fn get_state(&self) -> NeosynthResult<SynthState> {
-        Ok(*self.state.read().unwrap())
+    /// Try the given voices in order whenever the current voice turns out to be unavailable
+    pub fn set_voice_fallbacks(&self, voices: Vec<VoiceInformation>) {
+        *self.voice_fallbacks.write().unwrap() = voices;
+    }
+
+    /// Report that the element at `index` in the utterance started being processed
+    fn start_element(&self, index: usize, element: &SpeechElement) {
+        let kind = element.kind();
+        *self.current_element.lock().unwrap() = Some((index, kind, Instant::now()));
+        if element.is_audible() {
+            if let Some(replay) = self.replay.lock().unwrap().as_mut() {
+                replay.played = true;
+            }
+            let silence = self.inter_element_silence.lock().unwrap().is_some();
+            self.silence_due.store(silence, Ordering::SeqCst);
+            let mut history = self.history.lock().unwrap();
+            if history.len() == ELEMENT_HISTORY_SIZE {
+                history.pop_front();
+            }
+            history.push_back((index, element.clone()));
+        }
+        self.word_boundaries.lock().unwrap().clear();
+        self.sentence_starts.lock().unwrap().clear();
+        self.silent_stretches.lock().unwrap().clear();
+        self.player
+            .notify(|sink| sink.on_element_started(index, kind));
+    }
+
+    /// Start time in seconds within the current element's audio of the word at the given
+    /// offset into its text, the offset being in UTF-16 code units like word boundary events
+    pub fn audio_time_for_offset(&self, offset: usize) -> Option<f64> {
+        self.word_boundaries
+            .lock()
+            .unwrap()
+            .iter()
+            .take_while(|(start, _, _)| *start <= offset)
+            .last()
+            .map(|(_, _, audio_time)| *audio_time)
+    }
+
+    /// Offset and length in the current element's text of the word being spoken
+    /// at the given time in seconds into the element's audio
+    pub fn offset_for_audio_time(&self, seconds: f64) -> Option<(usize, usize)> {
+        self.word_boundaries
+            .lock()
+            .unwrap()
+            .iter()
+            .take_while(|(_, _, audio_time)| *audio_time <= seconds)
+            .last()
+            .map(|(offset, length, _)| (*offset, *length))
+    }
+
+    /// Report the failure of the player to play the element being processed.
+    /// Returns true if the element will be played again rather than skipped.
+    fn report_playback_error(&self, args: &MediaPlayerFailedEventArgs) -> NeosynthResult<bool> {
+        let code = args.ExtendedErrorCode()?.0;
+        let message = args.ErrorMessage()?.to_string();
+        if code == AUDCLNT_E_DEVICE_IN_USE {
+            let retrying = {
+                let mut retry = self.device_retry.lock().unwrap();
+                retry.waited = retry.interval.map(|_| Duration::ZERO);
+                retry.waited.is_some()
+            };
+            self.log(
+                "Playback failed: the audio device is used exclusively by another application.",
+                LogLevel::Warning,
+            );
+            self.player.notify(|sink| sink.on_device_busy(retrying));
+            if retrying {
+                return Ok(true);
+            }
+        }
+        let element_index = self
+            .current_element
+            .lock()
+            .unwrap()
+            .map(|(index, _, _)| index);
+        self.log(
+            &format!("Playback failed: {} Code: {}.", message, code),
+            LogLevel::Error,
+        );
+        self.player
+            .notify(|sink| sink.on_error(element_index, code, &message));
+        Ok(false)
+    }
+
+    /// Play the element which failed because the audio device was in use again every
+    /// given interval until it plays, None to skip it instead
+    pub fn set_device_retry_interval(&self, interval: Option<Duration>) {
+        let mut retry = self.device_retry.lock().unwrap();
+        retry.interval = interval.filter(|interval| !interval.is_zero());
+        if retry.interval.is_none() {
+            retry.waited = None;
+        }
+    }
+
+    fn tick_device_retry(&self, elapsed: Duration) {
+        // Resuming plays the element again anyway
+        if self.get_state().ok() == Some(SynthState::Paused) {
+            return;
+        }
+        let due = {
+            let mut retry = self.device_retry.lock().unwrap();
+            let DeviceRetryState {
+                interval: Some(interval),
+                waited: Some(waited),
+            } = &mut *retry
+            else {
+                return;
+            };
+            *waited += elapsed;
+            let due = *waited >= *interval;
+            if due {
+                retry.waited = None;
+            }
+            due
+        };
+        if due && self.player.reload_source().is_err() {
+            self.advance_queue();
+        }
+    }
+
+    /// Report that the element being processed, if any, is done
+    fn finish_element(&self) {
+        self.finish_audio();
+        *self.stall.lock().unwrap() = StallState::default();
+        self.set_buffering(false);
+        let current = self.current_element.lock().unwrap().take();
+        if let Some((index, kind, started)) = current {
+            let duration = started.elapsed().as_secs_f64();
+            self.player
+                .notify(|sink| sink.on_element_finished(index, kind, duration));
+        }
+    }
+
+    /// Report that the audio element being played, if any, started playing
+    fn start_audio(&self) {
+        let path = match self.current_audio.lock().unwrap().as_mut() {
+            Some((path, started)) if !*started => {
+                *started = true;
+                path.clone()
+            }
+            _ => return,
+        };
+        self.player.notify(|sink| sink.on_audio_started(&path));
+    }
+
+    /// Report that the audio element being played, if any, is done
+    fn finish_audio(&self) {
+        let current = self.current_audio.lock().unwrap().take();
+        // Audio which failed to play never started
+        if let Some((path, true)) = current {
+            self.player.notify(|sink| sink.on_audio_finished(&path));
+        }
+    }
+
+    /// The part of a queued element which is to be processed, None if it is skipped
+    fn admit_element(&self, mut element: SpeechElement) -> Option<SpeechElement> {
+        while let SpeechElement::Skippable(inner) = element {
+            if self.should_skip() {
+                self.log("Skipped a skippable element", LogLevel::Debug);
+                return None;
+            }
+            element = *inner;
+        }
+        if self.preview_mode.load(Ordering::SeqCst) {
+            element = Self::preview_element(element)?;
+        }
+        let element = self.filter_kind(element);
+        if element.is_none() {
+            self.log("Skipped an element of a skipped kind", LogLevel::Debug);
+        }
+        element
+    }
+
+    /// Process the element at `index` in the utterance, unless it is skipped
+    pub fn process_speech_element(
+        &self,
+        index: usize,
+        element: SpeechElement,
+    ) -> NeosynthResult<()> {
+        match self.admit_element(element) {
+            Some(element) => self.process_admitted(index, element),
+            None => Ok(()),
+        }
+    }
+
+    /// Process an element admitted by `admit_element`
+    fn process_admitted(&self, index: usize, element: SpeechElement) -> NeosynthResult<()> {
+        self.profiler.finish(Stage::QueueWait);
+        self.start_element(index, &element);
+        match element {
+            SpeechElement::Text(text) | SpeechElement::Structured(_, text) => {
+                self.speak_content(&text, false)?
+            }
+            SpeechElement::Ssml(ssml) => self.speak_content(&ssml, true)?,
+            SpeechElement::MarkedText(mark, text) => {
+                let language = self.synthesizer.Voice()?.Language()?.to_string();
+                // Filtered once as text, the offsets of the words are mapped back to it
+                let acronyms = self.document_acronyms.lock().unwrap().clone();
+                let prepared = self.prepare_text(&self.synthesizer, &text, false, &acronyms)?;
+                let (ssml, offsets) = text::marked_ssml(&language, &mark, &prepared);
+                let offsets = if prepared != text {
+                    offsets.compose(&OffsetMap::diff(&text, &prepared))
+                } else {
+                    offsets
+                };
+                self.speak_prepared(ssml, true, Some(offsets))?
+            }
+            SpeechElement::Code(block) => {
+                let language = self.synthesizer.Voice()?.Language()?.to_string();
+                self.speak_content(&code::code_ssml(&language, &block), true)?
+            }
+            SpeechElement::Audio(filename) => {
+                *self.current_audio.lock().unwrap() = Some((filename.clone(), false));
+                self.profiler
+                    .time(Stage::SourceSet, || self.player.set_file_source(filename))?
+            }
+            SpeechElement::LoopedAudio(filename) => {
+                *self.current_audio.lock().unwrap() = Some((filename.clone(), false));
+                self.profiler
+                    .time(Stage::SourceSet, || self.player.set_file_source(filename))?;
+                self.player.set_looping(true)?;
+            }
+            SpeechElement::Voice(ref voice) | SpeechElement::AutoVoice(ref voice) => {
+                // Like profiles, voice switches only apply to the utterance they are part of.
+                // Restoring the voice once the utterance ends isn't announced.
+                self.save_profile()?;
+                let changed = self.synthesizer.Voice()?.Id()? != voice.Id()?;
+                self.synthesizer.SetVoice(voice)?;
+                let cue = match element {
+                    SpeechElement::AutoVoice(_) => self.voice_change_cue.read().unwrap().clone(),
+                    _ => None,
+                };
+                match cue {
+                    Some(cue) if changed && !self.skip_audio.load(Ordering::SeqCst) => self
+                        .profiler
+                        .time(Stage::SourceSet, || self.player.set_file_source(cue))?,
+                    _ => self.process_queue()?,
+                }
+            }
+            SpeechElement::Synthesized(stream) => {
+                stream.Seek(0)?;
+                self.profiler
+                    .time(Stage::SourceSet, || self.set_stream_source(stream, None))?;
+                self.player.play()?;
+            }
+            SpeechElement::Bookmark(bookmark) => {
+                self.player
+                    .notify(|sink| sink.on_bookmark_reached(bookmark));
+                self.process_queue()?;
+            }
+            SpeechElement::Profile(profile) => {
+                if let Err(e) = self
+                    .save_profile()
+                    .and_then(|_| self.apply_profile(&profile))
+                {
+                    self.log(
+                        &format!("Failed to apply speech profile: {}", e),
+                        LogLevel::Warning,
+                    );
+                }
+                self.process_queue()?;
+            }
+            SpeechElement::Acronyms(expansions) => {
+                self.document_acronyms.lock().unwrap().extend(expansions);
+                self.process_queue()?;
+            }
+            SpeechElement::Tone(tone) => {
+                self.set_tone(tone)?;
+                self.process_queue()?;
+            }
+            SpeechElement::Skippable(_) => {
+                unreachable!("skippable elements are unwrapped by admit_element")
+            }
+        };
+        Ok(())
+    }
+
+    fn process_queue(&self) -> NeosynthResult<()> {
+        if self.is_closed() {
+            return Ok(());
+        }
+        self.finish_element();
+        self.player.set_looping(false)?;
+        // The element ended with its last sentence
+        if self.pending_stop.lock().unwrap().take().is_some() {
+            return self.stop_speech();
+        }
+        let mut pending = self.fill_speech_queue();
+        if self.silence_due.swap(false, Ordering::SeqCst) {
+            let silence = *self.inter_element_silence.lock().unwrap();
+            let has_next = !self.speech_queue.lock().unwrap().is_empty();
+            if let (Some(silence), true) = (silence, has_next) {
+                // Processing goes on when the silence ends
+                return self.player.set_silence_source(silence);
+            }
+        }
+        // Skipped elements are dropped in a loop, a long run of them mustn't grow the stack
+        let next = loop {
+            // Not popped in the match, the lock would be held while processing the element
+            let next = self.speech_queue.lock().unwrap().pop_front();
+            match next {
+                Some((index, elem)) => match self.admit_element(elem) {
+                    Some(elem) => break Some((index, elem)),
+                    None => pending = self.fill_speech_queue(),
+                },
+                None => break None,
+            }
+        };
+        match next {
+            Some((index, elem)) => self.process_admitted(index, elem),
+            None if pending => {
+                self.starved.store(true, Ordering::SeqCst);
+                // Don't miss an element pushed to the feed before we were marked as starved
+                if self.fill_speech_queue() && self.speech_queue.lock().unwrap().is_empty() {
+                    return Ok(());
+                }
+                if self.starved.swap(false, Ordering::SeqCst) {
+                    self.process_queue()?;
+                }
+                Ok(())
+            }
+            None => {
+                if self.replay_utterance()? {
+                    return Ok(());
+                }
+                self.finish_utterance();
+                if self.speak_next_alert()? || self.speak_deferred()? {
+                    return Ok(());
+                }
+                self.release_focus();
+                self.awaiting_playback.store(false, Ordering::SeqCst);
+                self.set_state(SynthState::Ready, StateChangeReason::MediaEnded)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Abort the element being played or paused and go on with the next one in the queue
+    pub fn skip_element(&self) -> NeosynthResult<()> {
+        let state = self.get_state()?;
+        if state == SynthState::Ready {
+            return Ok(());
+        }
+        self.device_retry.lock().unwrap().waited = None;
+        self.silence_due.store(false, Ordering::SeqCst);
+        self.player.pause()?;
+        self.process_queue()?;
+        if self.get_state()? == SynthState::Ready {
+            // Nothing left to play, don't leave the skipped element paused in the player
+            self.player.stop()?;
+        } else if state == SynthState::Paused {
+            self.set_state(SynthState::Busy, StateChangeReason::Skip)?;
+        }
+        Ok(())
+    }
+
+    /// Drop the elements before the given bookmark and go on speaking from it, the bookmark
+    /// being reported when speech gets there. Voices, profiles, tones and acronyms among
+    /// the dropped elements still apply. Returns false if no such bookmark is ahead, a lazy
+    /// source is only searched up to `BOOKMARK_SEARCH_LIMIT` elements and a feed as far as
+    /// it was pushed.
+    pub fn skip_to_bookmark(&self, name: &str) -> NeosynthResult<bool> {
+        let state = self.get_state()?;
+        if state == SynthState::Ready {
+            return Ok(false);
+        }
+        let is_target = |(_, element): &(usize, SpeechElement)| match element {
+            SpeechElement::Bookmark(mark) | SpeechElement::MarkedText(mark, _) => mark == name,
+            _ => false,
+        };
+        let is_setting = |(_, element): &(usize, SpeechElement)| {
+            matches!(
+                element,
+                SpeechElement::Voice(_)
+                    | SpeechElement::AutoVoice(_)
+                    | SpeechElement::Profile(_)
+                    | SpeechElement::Acronyms(_)
+                    | SpeechElement::Tone(_)
+            )
+        };
+        let mut warnings = Vec::new();
+        let found = {
+            let mut source = self.speech_source.lock().unwrap();
+            let mut idx = self.speech_queue.lock().unwrap().iter().position(is_target);
+            // The elements pulled while looking stay queued, they come next anyway
+            let mut pulled = 0;
+            while let (None, Some(src)) = (idx, source.as_mut()) {
+                if pulled >= BOOKMARK_SEARCH_LIMIT {
+                    break;
+                }
+                match src.pull() {
+                    PulledElement::Element(elem, span) => {
+                        pulled += 1;
+                        let start = self.speech_queue.lock().unwrap().len();
+                        self.queue_pulled(elem, span, &mut warnings);
+                        let queue = self.speech_queue.lock().unwrap();
+                        idx = queue
+                            .range(start..)
+                            .position(is_target)
+                            .map(|idx| start + idx);
+                    }
+                    PulledElement::Pending => break,
+                    PulledElement::Exhausted => *source = None,
+                }
+            }
+            if let Some(idx) = idx {
+                let mut queue = self.speech_queue.lock().unwrap();
+                let settings: Vec<_> = queue.drain(..idx).filter(is_setting).collect();
+                for element in settings.into_iter().rev() {
+                    queue.push_front(element);
+                }
+            }
+            idx.is_some()
+        };
+        for warning in warnings {
+            self.log(&warning, LogLevel::Warning);
+        }
+        if !found {
+            return Ok(false);
+        }
+        self.device_retry.lock().unwrap().waited = None;
+        self.silence_due.store(false, Ordering::SeqCst);
+        self.prefetched.lock().unwrap().clear();
+        self.player.pause()?;
+        self.process_queue()?;
+        if state == SynthState::Paused {
+            self.set_state(SynthState::Busy, StateChangeReason::Skip)?;
+        }
+        Ok(true)
+    }
+
+    /// Play the element before the one being played or paused, then go on from there.
+    /// Once speech is done, the last element played is spoken again.
+    pub fn previous_element(&self) -> NeosynthResult<()> {
+        let state = self.get_state()?;
+        let mut history = self.history.lock().unwrap();
+        if state == SynthState::Ready {
+            let Some(last) = history.pop_back() else {
+                return Ok(());
+            };
+            drop(history);
+            self.speak(vec![last.1])?;
+            self.set_busy()?;
+            return self.player.play();
+        }
+        let Some(current) = history.pop_back() else {
+            return Ok(());
+        };
+        let replayed: Vec<_> = history.pop_back().into_iter().chain([current]).collect();
+        drop(history);
+        {
+            let mut queue = self.speech_queue.lock().unwrap();
+            for element in replayed.into_iter().rev() {
+                queue.push_front(element);
+            }
+        }
+        self.device_retry.lock().unwrap().waited = None;
+        self.silence_due.store(false, Ordering::SeqCst);
+        self.player.pause()?;
+        self.process_queue()?;
+        if state == SynthState::Paused {
+            self.set_state(SynthState::Busy, StateChangeReason::Skip)?;
+        }
+        Ok(())
+    }
+
+    /// Process the queue from an event handler, where errors can only be reported
+    fn advance_queue(&self) {
+        if let Err(e) = self.process_queue() {
+            self.report_internal_error("Processing the speech queue failed", &e);
+        }
+    }
+
+    /// Pull elements from the speech source until the look-ahead is filled.
+    /// Returns true if the source may produce more elements later.
+    fn fill_speech_queue(&self) -> bool {
+        let mut warnings = Vec::new();
+        let pending = {
+            let mut source = self.speech_source.lock().unwrap();
+            // Elements can only be prefetched once they are in the queue
+            let lookahead = self
+                .lookahead
+                .load(Ordering::SeqCst)
+                .max(self.prefetch_count.load(Ordering::SeqCst))
+                .max(1);
+            loop {
+                let Some(src) = source.as_mut() else {
+                    break false;
+                };
+                if self.speech_queue.lock().unwrap().len() >= lookahead {
+                    break false;
+                }
+                match src.pull() {
+                    PulledElement::Element(elem, span) => {
+                        self.queue_pulled(elem, span, &mut warnings)
+                    }
+                    PulledElement::Pending => break true,
+                    PulledElement::Exhausted => *source = None,
+                }
+            }
+        };
+        // Notify after releasing the source lock, the sink may call back into the mixer
+        for warning in warnings {
+            self.log(&warning, LogLevel::Warning);
+        }
+        pending
+    }
+
+    /// Add an element pulled from the speech source to the queue, recording it for replay.
+    /// `span` is the number of elements of the utterance it was made of.
+    fn queue_pulled(&self, elem: SpeechElement, span: usize, warnings: &mut Vec<String>) {
+        if let Some(replay) = self.replay.lock().unwrap().as_mut() {
+            if replay.recording {
+                // Recorded as the user gave it, so that a replay reports the same indices
+                match (&elem, span) {
+                    (SpeechElement::MarkedText(mark, text), 2) => {
+                        replay.elements.push(SpeechElement::Bookmark(mark.clone()));
+                        replay.elements.push(SpeechElement::Text(text.clone()));
+                    }
+                    _ => replay.elements.push(elem.clone()),
+                }
+            }
+        }
+        let index = self.element_index.fetch_add(span, Ordering::SeqCst);
+        // The parts of a split element are reported as the element they come from
+        let elements = self.enforce_text_length(elem, warnings);
+        let elements = elements.into_iter().map(|elem| (index, elem));
+        self.speech_queue.lock().unwrap().extend(elements);
+    }
+
+    /// Split text elements longer than the maximum text length into several elements
+    fn enforce_text_length(
+        &self,
+        element: SpeechElement,
+        warnings: &mut Vec<String>,
+    ) -> Vec<SpeechElement> {
+        let max_length = self.max_text_length.load(Ordering::SeqCst);
+        match element {
+            SpeechElement::Text(text) if max_length > 0 && text.chars().count() > max_length => {
+                let chunks = text::split_text(&text, max_length);
+                warnings.push(format!(
+                    "Text of {} characters exceeds the limit of {} characters, split into {} parts",
+                    text.chars().count(),
+                    max_length,
+                    chunks.len()
+                ));
+                chunks.into_iter().map(SpeechElement::Text).collect()
+            }
+            SpeechElement::Ssml(ssml) if max_length > 0 && ssml.chars().count() > max_length => {
+                match text::split_ssml(&ssml, max_length) {
+                    Some(parts) => {
+                        warnings.push(format!(
+                            "SSML of {} characters exceeds the limit of {} characters, split into {} parts",
+                            ssml.chars().count(),
+                            max_length,
+                            parts.len()
+                        ));
+                        parts.into_iter().map(SpeechElement::Ssml).collect()
+                    }
+                    None => {
+                        warnings.push(format!(
+                            "SSML of {} characters exceeds the limit of {} characters and cannot be split",
+                            ssml.chars().count(),
+                            max_length
+                        ));
+                        vec![SpeechElement::Ssml(ssml)]
+                    }
+                }
+            }
+            SpeechElement::MarkedText(mark, text)
+                if max_length > 0 && text.chars().count() > max_length =>
+            {
+                let mut elements = self.enforce_text_length(SpeechElement::Text(text), warnings);
+                if let Some(SpeechElement::Text(first)) = elements.first_mut() {
+                    let first = std::mem::take(first);
+                    elements[0] = SpeechElement::MarkedText(mark, first);
+                }
+                elements
+            }
+            // Only the first chunk of a paragraph keeps its role, so that it has a single first sentence
+            SpeechElement::Structured(role, text)
+                if max_length > 0 && text.chars().count() > max_length =>
+            {
+                let mut elements = self.enforce_text_length(SpeechElement::Text(text), warnings);
+                for (idx, element) in elements.iter_mut().enumerate() {
+                    if idx > 0 && role == TextRole::Paragraph {
+                        break;
+                    }
+                    if let SpeechElement::Text(chunk) = element {
+                        let chunk = std::mem::take(chunk);
+                        *element = SpeechElement::Structured(role, chunk);
+                    }
+                }
+                elements
+            }
+            SpeechElement::Skippable(element) => self
+                .enforce_text_length(*element, warnings)
+                .into_iter()
+                .map(|element| SpeechElement::Skippable(Box::new(element)))
+                .collect(),
+            element => vec![element],
+        }
+    }
+
+    /// Set the maximum number of characters sent to the voice in one text element, 0 means no limit
+    pub fn set_max_text_length(&self, max_length: usize) {
+        self.max_text_length.store(max_length, Ordering::SeqCst);
+    }
+
+    pub fn get_max_text_length(&self) -> usize {
+        self.max_text_length.load(Ordering::SeqCst)
+    }
+
+    /// Drop skippable elements instead of speaking them
+    pub fn set_skim_mode(&self, enabled: bool) {
+        self.skim_mode.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Enter skim mode automatically whenever the speaking rate is at least `rate`, None to disable
+    pub fn set_skim_rate(&self, rate: Option<f64>) {
+        *self.skim_rate.lock().unwrap() = rate;
+    }
+
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::SeqCst);
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted.load(Ordering::SeqCst)
+    }
+
+    pub fn set_soloed(&self, soloed: bool) {
+        self.soloed.store(soloed, Ordering::SeqCst);
+    }
+
+    pub fn is_soloed(&self) -> bool {
+        self.soloed.load(Ordering::SeqCst)
+    }
+
+    /// Silence the player if this channel is muted, or if other channels are soloed
+    pub fn update_muting(&self, any_soloed: bool) -> NeosynthResult<()> {
+        self.player
+            .set_muted(self.is_muted() || (any_soloed && !self.is_soloed()))
+    }
+
+    /// Only speak headings, landmarks and the first sentence of paragraphs
+    pub fn set_preview_mode(&self, enabled: bool) {
+        self.preview_mode.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Drop audio elements and voice change cues, or speech elements, as they are reached
+    pub fn set_skipped_elements(&self, skip_audio: bool, skip_speech: bool) {
+        self.skip_audio.store(skip_audio, Ordering::SeqCst);
+        self.skip_speech.store(skip_speech, Ordering::SeqCst);
+    }
+
+    /// Rejoin hyphenated words and lines broken within paragraphs before synthesizing text
+    pub fn set_line_joining(&self, enabled: bool) {
+        self.line_joining.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Remove directional formatting characters and separate adjacent right-to-left and
+    /// left-to-right words before synthesizing text
+    pub fn set_bidi_cleanup(&self, enabled: bool) {
+        self.bidi_cleanup.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Choose the reading rules applied to text elements
+    pub fn set_reading_rules(&self, rules: ReadingRules) {
+        *self.reading_rules.write().unwrap() = rules;
+    }
+
+    /// Collapse runs of punctuation in text elements, None to leave them to the voice
+    pub fn set_punctuation_runs(&self, runs: Option<PunctuationRuns>) {
+        *self.punctuation_runs.write().unwrap() = runs;
     }
 
-    pub fn set_state(&self, state: SynthState) -> NeosynthResult<()> {
-        if self.get_state()? != state {
-            let mut wst = self.state.write().unwrap();
-            *wst = state;
-            self.player.1.on_state_changed(state);
+    /// Rewrite the acronyms of text elements, None to leave them to the voice
+    pub fn set_acronym_filter(&self, filter: Option<AcronymFilter>) {
+        *self.acronym_filter.write().unwrap() = filter;
+    }
+
+    /// Remove emoji and symbols or replace them with their names, None to leave them as is
+    pub fn set_symbol_mode(&self, mode: Option<SymbolMode>) {
+        *self.symbol_mode.write().unwrap() = mode;
+    }
+
+    /// The part of an element spoken in preview mode, if any
+    fn preview_element(element: SpeechElement) -> Option<SpeechElement> {
+        match element {
+            SpeechElement::Structured(TextRole::Paragraph, text) => {
+                Some(SpeechElement::Structured(
+                    TextRole::Paragraph,
+                    text::first_sentence(&text).to_string(),
+                ))
+            }
+            SpeechElement::MarkedText(mark, _) => Some(SpeechElement::Bookmark(mark)),
+            SpeechElement::Text(_)
+            | SpeechElement::Ssml(_)
+            | SpeechElement::Audio(_)
+            | SpeechElement::LoopedAudio(_)
+            | SpeechElement::Synthesized(_) => None,
+            element => Some(element),
         }
-        Ok(())
     }
 
-    pub fn speak_content(&self, text: &str, is_ssml: bool) -> NeosynthResult<()> {
-        let stream = self.generate_speech_stream(text, is_ssml)?;
-        self.player.set_speech_stream_source(stream)?;
+    /// What is left of an element once the kinds of elements being skipped are dropped
+    fn filter_kind(&self, element: SpeechElement) -> Option<SpeechElement> {
+        let skip_audio = self.skip_audio.load(Ordering::SeqCst);
+        let skip_speech = self.skip_speech.load(Ordering::SeqCst);
+        match element {
+            SpeechElement::Audio(_) | SpeechElement::LoopedAudio(_) if skip_audio => None,
+            // The bookmark is still reported
+            SpeechElement::MarkedText(mark, _) if skip_speech => {
+                Some(SpeechElement::Bookmark(mark))
+            }
+            SpeechElement::Text(_)
+            | SpeechElement::Ssml(_)
+            | SpeechElement::Structured(..)
+            | SpeechElement::Code(_)
+            | SpeechElement::Synthesized(_)
+                if skip_speech =>
+            {
+                None
+            }
+            element => Some(element),
+        }
+    }
+
+    fn should_skip(&self) -> bool {
+        if self.skim_mode.load(Ordering::SeqCst) {
+            return true;
+        }
+        let skim_rate = *self.skim_rate.lock().unwrap();
+        skim_rate.is_some_and(|skim_rate| self.get_rate().is_ok_and(|rate| rate >= skim_rate))
+    }
+
+    /// Set the audio file played whenever the voice is switched automatically, or None to disable it
+    pub fn set_voice_change_cue(&self, cue: Option<String>) {
+        *self.voice_change_cue.write().unwrap() = cue;
+    }
+
+    /// Set how many elements are pulled ahead of playback from a lazy speech source
+    pub fn set_lookahead(&self, lookahead: usize) {
+        self.lookahead.store(lookahead, Ordering::SeqCst);
+    }
+
+    /// Start speaking the utterance in place of the current one, returns the id of the utterance.
+    /// During alerts, it takes the place of the utterance they interrupted instead.
+    pub fn speak<I>(&self, utterance: I) -> NeosynthResult<u64>
+    where
+        I: IntoIterator<Item = SpeechElement>,
+        I::IntoIter: Send + 'static,
+    {
+        let id = self.new_utterance_id();
+        if self.alert_active.load(Ordering::SeqCst) {
+            self.replace_interrupted(id, utterance)?;
+        } else {
+            self.speak_with_id(id, utterance)?;
+        }
+        Ok(id)
+    }
+
+    /// Start speaking the utterance in place of the current one under the given id
+    fn speak_with_id<I>(&self, id: u64, utterance: I) -> NeosynthResult<()>
+    where
+        I: IntoIterator<Item = SpeechElement>,
+        I::IntoIter: Send + 'static,
+    {
+        self.held.lock().unwrap().take();
+        self.clear_speech_queue()?;
+        self.start_utterance(id);
+        *self.replay.lock().unwrap() = self.new_replay();
+        self.profiler.start(Stage::QueueWait);
+        *self.speech_source.lock().unwrap() = Some(SpeechSource::Iter(
+            (Box::new(utterance.into_iter()) as Box<dyn Iterator<Item = _> + Send>).peekable(),
+        ));
+        self.process_queue()
+    }
+
+    /// Speak an alert, interrupting normal speech until the alerts are spoken. During an
+    /// alert, it is spoken after the alerts requested so far. Returns the id of the alert.
+    pub fn speak_alert(&self, utterance: Vec<SpeechElement>) -> NeosynthResult<u64> {
+        let id = self.new_utterance_id();
+        let speaking = self.get_state()? != SynthState::Ready;
+        if speaking && self.alert_active.load(Ordering::SeqCst) {
+            self.alerts.lock().unwrap().push_back((id, utterance));
+            return Ok(id);
+        }
+        if speaking {
+            self.interrupt()?;
+        }
+        self.alert_active.store(true, Ordering::SeqCst);
+        self.speak_with_id(id, utterance)?;
+        self.set_busy()?;
         self.player.play()?;
+        Ok(id)
+    }
+
+    /// Keep the utterance being spoken to go on with it once the alerts are spoken
+    fn interrupt(&self) -> NeosynthResult<()> {
+        let Some(id) = self.current_utterance.lock().unwrap().take() else {
+            return Ok(());
+        };
+        let mut queue: VecDeque<_> = self.speech_queue.lock().unwrap().drain(..).collect();
+        let current = *self.current_element.lock().unwrap();
+        if let Some((index, kind, _)) = current {
+            // Only audible elements are kept in the history
+            let history = self.history.lock().unwrap();
+            if let Some(entry) = history
+                .back()
+                .filter(|(played, element)| *played == index && element.kind() == kind)
+            {
+                queue.push_front(entry.clone());
+            }
+        }
+        let voice = self.synthesizer.Voice()?;
+        // Alerts are spoken with the settings from before the utterance's profiles
+        let saved_profile = self.saved_profile.lock().unwrap().take();
+        let profile = match saved_profile {
+            Some(saved) => {
+                let applied = self.current_profile()?;
+                self.apply_profile(&saved)?;
+                Some((applied, saved))
+            }
+            None => None,
+        };
+        let interrupted = Interrupted {
+            id,
+            started: true,
+            queue,
+            source: self.speech_source.lock().unwrap().take(),
+            element_index: self.element_index.load(Ordering::SeqCst),
+            replay: self.replay.lock().unwrap().take(),
+            voice,
+            profile,
+            tone: *self.tone.lock().unwrap(),
+            acronyms: std::mem::take(&mut *self.document_acronyms.lock().unwrap()),
+        };
+        *self.interrupted.lock().unwrap() = Some(interrupted);
         Ok(())
     }
 
-    fn generate_speech_stream(
-        &self,
-        text: &str,
-        is_ssml: bool,
-    ) -> NeosynthResult<SpeechSynthesisStream> {
-        let output = if is_ssml {
-            self.synthesizer
-                .SynthesizeSsmlToStreamAsync(&HSTRING::from(text))?
-                .get()
-        } else {
-            self.synthesizer
-                .SynthesizeTextToStreamAsync(&HSTRING::from(text))?
-                .get()
+    /// Speak the utterance once the alerts are spoken, in place of the one they interrupted
+    fn replace_interrupted<I>(&self, id: u64, utterance: I) -> NeosynthResult<()>
+    where
+        I: IntoIterator<Item = SpeechElement>,
+        I::IntoIter: Send + 'static,
+    {
+        let voice = match self.interrupted.lock().unwrap().as_ref() {
+            // The voice from before the interrupted utterance's profiles
+            Some(interrupted) => interrupted
+                .profile
+                .as_ref()
+                .and_then(|(_, saved)| saved.voice.clone())
+                .unwrap_or_else(|| interrupted.voice.clone()),
+            None => self.synthesizer.Voice()?,
         };
-        match output {
-            Ok(output) => Ok(output),
-            Err(e) => {
-                self.player.1.on_state_changed(SynthState::Ready);
-                self.player.1.log(
-                    format!("Error generating speech stream: {}", e.code().0).as_str(),
-                    "error",
-                );
-                Err(e.into())
+        self.drop_interrupted();
+        *self.interrupted.lock().unwrap() = Some(Interrupted {
+            id,
+            started: false,
+            queue: VecDeque::new(),
+            replay: self.new_replay(),
+            source: Some(SpeechSource::Iter(
+                (Box::new(utterance.into_iter()) as Box<dyn Iterator<Item = _> + Send>).peekable(),
+            )),
+            element_index: 0,
+            voice,
+            profile: None,
+            tone: Tone::Normal,
+            acronyms: HashMap::new(),
+        });
+        Ok(())
+    }
+
+    /// Forget the alerts waiting to be spoken, and the utterance they interrupted
+    fn drop_alerts(&self) {
+        self.alerts.lock().unwrap().clear();
+        self.alert_active.store(false, Ordering::SeqCst);
+        self.drop_interrupted();
+    }
+
+    /// Forget the utterance interrupted by the alerts, if any
+    fn drop_interrupted(&self) {
+        let interrupted = self.interrupted.lock().unwrap().take();
+        if let Some(Interrupted {
+            id, started: true, ..
+        }) = interrupted
+        {
+            self.player.notify(|sink| sink.on_utterance_finished(id));
+        }
+    }
+
+    /// Speak the next alert, or go on with the utterance the alerts interrupted.
+    /// Returns whether either was started.
+    fn speak_next_alert(&self) -> NeosynthResult<bool> {
+        let next = self.alerts.lock().unwrap().pop_front();
+        if let Some((id, utterance)) = next {
+            self.speak_with_id(id, utterance)?;
+            self.set_busy()?;
+            self.player.play()?;
+            return Ok(true);
+        }
+        self.alert_active.store(false, Ordering::SeqCst);
+        let Some(interrupted) = self.interrupted.lock().unwrap().take() else {
+            return Ok(false);
+        };
+        if let Some((applied, saved)) = interrupted.profile {
+            self.apply_profile(&applied)?;
+            *self.saved_profile.lock().unwrap() = Some(saved);
+        }
+        self.synthesizer.SetVoice(&interrupted.voice)?;
+        if interrupted.started {
+            *self.current_utterance.lock().unwrap() = Some(interrupted.id);
+            self.set_tone(interrupted.tone)?;
+            self.update_gain()?;
+            *self.document_acronyms.lock().unwrap() = interrupted.acronyms;
+        } else {
+            self.start_utterance(interrupted.id);
+        }
+        *self.replay.lock().unwrap() = interrupted.replay;
+        self.element_index
+            .store(interrupted.element_index, Ordering::SeqCst);
+        *self.speech_queue.lock().unwrap() = interrupted.queue;
+        *self.speech_source.lock().unwrap() = interrupted.source;
+        self.process_queue()?;
+        self.set_busy()?;
+        self.player.play()?;
+        Ok(true)
+    }
+
+    /// Speak an utterance on top of the current speech, with the current voice and prosody.
+    /// Its events aren't reported. Returns the id of the utterance.
+    pub fn speak_over(&self, utterance: Vec<SpeechElement>) -> NeosynthResult<u64> {
+        let overlay = {
+            let mut overlay = self.overlay.lock().unwrap();
+            match overlay.as_ref() {
+                Some(overlay) => Arc::clone(overlay),
+                None => {
+                    let mixer = Arc::new(SpeechMixer::new(OverlaySink)?);
+                    SpeechMixer::register_events(&mixer)?;
+                    overlay.insert(mixer).clone()
+                }
             }
+        };
+        overlay.apply_profile(&self.current_profile()?)?;
+        overlay.player.set_muted(self.player.0.IsMuted()?)?;
+        let id = self.new_utterance_id();
+        overlay.speak_with_id(id, utterance)?;
+        overlay.set_busy()?;
+        overlay.player.play()?;
+        Ok(id)
+    }
+
+    fn overlay(&self) -> Option<Arc<SpeechMixer<OverlaySink>>> {
+        self.overlay.lock().unwrap().clone()
+    }
+
+    /// Speak each utterance `repeat` more times, the change applies from the next utterance
+    pub fn set_repeat(&self, repeat: Repeat) {
+        *self.repeat.write().unwrap() = repeat;
+    }
+
+    /// The state of an utterance about to be spoken which has to be repeated, if any
+    fn new_replay(&self) -> Option<Replay> {
+        let remaining = *self.repeat.read().unwrap();
+        if remaining == Repeat::Times(0) {
+            return None;
         }
+        Some(Replay {
+            elements: Vec::new(),
+            remaining,
+            played: false,
+            recording: true,
+        })
     }
 
-    pub fn process_speech_element(&self, element: SpeechElement) -> NeosynthResult<()> {
-        match element {
-            SpeechElement::Text(text) => self.speak_content(&text, false)?,
-            SpeechElement::Ssml(ssml) => self.speak_content(&ssml, true)?,
-            SpeechElement::Audio(filename) => self.player.set_file_source(filename)?,
-            SpeechElement::Bookmark(bookmark) => {
-                self.player.1.on_bookmark_reached(bookmark);
-                self.process_queue()?;
+    /// Speak the utterance which just ended once more, if it has to be.
+    /// Returns whether it was started again.
+    fn replay_utterance(&self) -> NeosynthResult<bool> {
+        let elements = {
+            let mut replay = self.replay.lock().unwrap();
+            let Some(state) = replay.as_mut() else {
+                return Ok(false);
+            };
+            if !state.played || state.remaining == Repeat::Times(0) {
+                replay.take();
+                return Ok(false);
+            }
+            if let Repeat::Times(times) = &mut state.remaining {
+                *times -= 1;
             }
+            state.played = false;
+            state.recording = false;
+            state.elements.clone()
         };
+        // Each time starts like the first one
+        self.element_index.store(0, Ordering::SeqCst);
+        self.document_acronyms.lock().unwrap().clear();
+        self.set_tone(Tone::Normal)?;
+        *self.speech_source.lock().unwrap() = Some(SpeechSource::Iter(
+            (Box::new(elements.into_iter()) as Box<dyn Iterator<Item = _> + Send>).peekable(),
+        ));
+        self.process_queue()?;
+        Ok(true)
+    }
+
+    fn new_utterance_id(&self) -> u64 {
+        self.next_utterance_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Report that the utterance with the given id started
+    fn start_utterance(&self, id: u64) {
+        self.document_acronyms.lock().unwrap().clear();
+        if let Err(e) = self.set_tone(Tone::Normal) {
+            self.report_internal_error("Resetting the tone failed", &e);
+        }
+        *self.current_utterance.lock().unwrap() = Some(id);
+        if let Err(e) = self.update_gain() {
+            self.report_internal_error("Adjusting the volume for quiet hours failed", &e);
+        }
+        self.player.notify(|sink| sink.on_utterance_started(id));
+    }
+
+    /// Keep the current settings to restore them once the utterance ends, unless a profile
+    /// or voice switch of the utterance already did
+    fn save_profile(&self) -> NeosynthResult<()> {
+        let mut saved_profile = self.saved_profile.lock().unwrap();
+        if saved_profile.is_none() {
+            *saved_profile = Some(self.current_profile()?);
+        }
         Ok(())
     }
 
-    fn process_queue(&self) -> NeosynthResult<()> {
-        match self.speech_queue.pop() {
-            Some(elem) => self.process_speech_element(elem),
-            None => {
-                self.set_state(SynthState::Ready)?;
-                Ok(())
+    /// Undo the profiles and voice switches of the utterance which ended or was interrupted
+    fn restore_profile(&self) {
+        let saved_profile = self.saved_profile.lock().unwrap().take();
+        if let Some(profile) = saved_profile {
+            if let Err(e) = self.apply_profile(&profile) {
+                self.report_internal_error("Restoring the speech settings failed", &e);
             }
         }
     }
 
-    pub fn speak<I>(&self, utterance: I) -> NeosynthResult<()>
-    where
-        I: IntoIterator<Item = SpeechElement>,
-    {
+    /// Report that the utterance being spoken, if any, is done
+    fn finish_utterance(&self) {
+        // Profiles only apply to the utterance they are part of
+        self.restore_profile();
+        let current = self.current_utterance.lock().unwrap().take();
+        if let Some(id) = current {
+            self.player.notify(|sink| sink.on_utterance_finished(id));
+        }
+    }
+
+    /// Start speaking elements pushed to the returned feed, as they arrive
+    pub fn speak_feed(mixer: &Arc<Self>, capacity: usize) -> NeosynthResult<SpeechFeed<T>> {
+        let channel = Arc::new(FeedChannel {
+            queue: ArrayQueue::new(capacity.max(1)),
+            closed: AtomicBool::new(false),
+        });
+        mixer.clear_speech_queue()?;
+        let utterance_id = mixer.new_utterance_id();
+        mixer.start_utterance(utterance_id);
+        mixer.profiler.start(Stage::QueueWait);
+        *mixer.speech_source.lock().unwrap() = Some(SpeechSource::Feed(Arc::clone(&channel)));
+        mixer.process_queue()?;
+        Ok(SpeechFeed {
+            channel,
+            mixer: Arc::downgrade(mixer),
+            utterance_id,
+        })
+    }
+
+    /// Stop speaking and detach the event sink, no events are delivered after this returns
+    pub fn close(&self) -> NeosynthResult<()> {
+        self.closed.store(true, Ordering::SeqCst);
+        self.async_ops.cancel_all();
         self.clear_speech_queue()?;
-        utterance
-            .into_iter()
-            .for_each(|elem| self.speech_queue.push(elem));
+        self.audio_focus.lock().unwrap().take();
+        if let Some(overlay) = self.overlay.lock().unwrap().take() {
+            overlay.close()?;
+        }
+        self.tick_fingerprints();
+        let result = self.player.stop();
+        self.player.1.close();
+        result
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Stop speaking and drop the elements waiting to be spoken
+    pub fn stop(&self) -> NeosynthResult<()> {
+        self.player.stop()?;
+        self.clear_speech_queue()?;
+        self.set_state(SynthState::Ready, StateChangeReason::Stop)?;
         self.process_queue()
     }
-    pub fn clear_speech_queue(&self) -> NeosynthResult<()> {
-        loop {
-            if self.speech_queue.pop().is_none() {
+
+    /// Stop speaking as requested by the user, the deferred utterances being caught up
+    /// with are dropped too
+    pub fn stop_speech(&self) -> NeosynthResult<()> {
+        self.drop_deferred();
+        self.held.lock().unwrap().take();
+        self.drop_alerts();
+        if let Some(overlay) = self.overlay() {
+            overlay.stop()?;
+        }
+        self.stop()
+    }
+
+    /// Only speak once the identical utterances requested within `window` of the first,
+    /// None to speak every utterance
+    pub fn set_dedup_window(&self, window: Option<Duration>) {
+        *self.dedup_window.lock().unwrap() = window;
+        self.recent_announcements.lock().unwrap().clear();
+    }
+
+    /// The key shared by identical utterances, None if they aren't deduplicated
+    pub fn dedup_key(&self, utterance: &[SpeechElement]) -> Option<String> {
+        self.dedup_window.lock().unwrap().as_ref()?;
+        let mut key = String::new();
+        for element in utterance {
+            key.push_str(element.kind());
+            key.push('\0');
+            key.push_str(&dedup_content(element)?);
+            key.push('\0');
+        }
+        Some(key)
+    }
+
+    /// The id of an identical utterance requested within the deduplication window, if any.
+    /// Duplicates found are counted.
+    pub fn recent_duplicate(&self, key: &str) -> Option<u64> {
+        let window = (*self.dedup_window.lock().unwrap())?;
+        let mut recent = self.recent_announcements.lock().unwrap();
+        recent.retain(|_, (requested, _)| requested.elapsed() < window);
+        let (_, id) = recent.get(key)?;
+        self.duplicate_count.fetch_add(1, Ordering::SeqCst);
+        Some(*id)
+    }
+
+    /// Suppress the utterances identical to the one with the given key for a while
+    pub fn remember_announcement(&self, key: String, id: u64) {
+        self.recent_announcements
+            .lock()
+            .unwrap()
+            .insert(key, (Instant::now(), id));
+    }
+
+    /// The number of utterances suppressed as duplicates
+    pub fn duplicate_count(&self) -> usize {
+        self.duplicate_count.load(Ordering::SeqCst)
+    }
+
+    pub fn paused_speech_mode(&self) -> PausedSpeechMode {
+        *self.paused_speech_mode.read().unwrap()
+    }
+
+    pub fn set_paused_speech_mode(&self, mode: PausedSpeechMode) {
+        *self.paused_speech_mode.write().unwrap() = mode;
+    }
+
+    /// Keep an utterance requested while paused to speak it on resume, in place of any
+    /// other one kept so far. Returns the id it will be spoken with.
+    pub fn hold(&self, utterance: Vec<SpeechElement>) -> u64 {
+        let id = self.new_utterance_id();
+        *self.held.lock().unwrap() = Some((id, utterance));
+        if self.paused_speech_mode() == PausedSpeechMode::Warn {
+            self.player.notify(|sink| sink.on_speech_while_paused(id));
+        }
+        id
+    }
+
+    /// Resume paused speech, or speak the utterance requested while paused in its place
+    pub fn resume(&self) -> NeosynthResult<()> {
+        let held = self.held.lock().unwrap().take();
+        if let Some((id, utterance)) = held {
+            self.speak_with_id(id, utterance)?;
+            self.set_busy()?;
+            return self.player.play();
+        }
+        self.set_state(SynthState::Busy, StateChangeReason::UserResume)?;
+        self.player.resume()
+    }
+
+    /// Let the sentence being spoken finish, then stop speaking like `stop_speech`.
+    /// Elements without sentence boundaries, e.g. audio, are played to their end.
+    pub fn stop_at_sentence_end(mixer: &Arc<Self>) -> NeosynthResult<()> {
+        match mixer.get_state()? {
+            SynthState::Ready => return Ok(()),
+            SynthState::Paused => return mixer.stop_speech(),
+            SynthState::Busy => {}
+        }
+        let current = *mixer.current_element.lock().unwrap();
+        let Some((index, ..)) = current else {
+            return mixer.stop_speech();
+        };
+        let position = mixer.player.position_and_duration()?.0.as_secs_f64();
+        let boundary = mixer
+            .sentence_starts
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .find(|start| *start > position);
+        *mixer.pending_stop.lock().unwrap() = Some(index);
+        // Without a following sentence, speech stops when the element ends
+        let Some(boundary) = boundary else {
+            return Ok(());
+        };
+        let weak_mixer = Arc::downgrade(mixer);
+        thread::spawn(move || loop {
+            thread::sleep(SENTENCE_STOP_POLL_INTERVAL);
+            let Some(mixer) = weak_mixer.upgrade() else {
+                break;
+            };
+            // Cancelled by new speech, or stopped when the element ended
+            if *mixer.pending_stop.lock().unwrap() != Some(index) {
                 break;
             }
+            let reached = mixer
+                .player
+                .position_and_duration()
+                .map_or(true, |(position, _)| position.as_secs_f64() >= boundary);
+            if !reached {
+                continue;
+            }
+            mixer.pending_stop.lock().unwrap().take();
+            if let Err(e) = mixer.stop_speech() {
+                mixer.report_internal_error("Stopping at the end of the sentence failed", &e);
+            }
+            break;
+        });
+        Ok(())
+    }
+
+    /// Drop the deferred utterances being spoken one after the other since quiet hours
+    /// ended, those deferred during quiet hours are kept
+    pub fn drop_deferred(&self) {
+        if !self.quiet_active.load(Ordering::SeqCst) {
+            self.deferred.lock().unwrap().clear();
+            self.deferred_freed.notify_all();
+        }
+    }
+
+    /// Stop speaking the given utterance if it is being spoken, or drop it if it was
+    /// deferred. Returns whether it was either.
+    pub fn cancel_utterance(&self, id: u64) -> NeosynthResult<bool> {
+        {
+            let mut held = self.held.lock().unwrap();
+            if held.as_ref().is_some_and(|(held_id, _)| *held_id == id) {
+                held.take();
+                return Ok(true);
+            }
+        }
+        {
+            let mut alerts = self.alerts.lock().unwrap();
+            if let Some(idx) = alerts.iter().position(|(alert_id, _)| *alert_id == id) {
+                alerts.remove(idx);
+                return Ok(true);
+            }
+        }
+        let interrupted = self.interrupted.lock().unwrap().as_ref().map(|i| i.id);
+        if interrupted == Some(id) {
+            self.drop_interrupted();
+            return Ok(true);
+        }
+        if let Some(overlay) = self.overlay() {
+            if *overlay.current_utterance.lock().unwrap() == Some(id) {
+                overlay.stop()?;
+                return Ok(true);
+            }
+        }
+        let mut deferred = self.deferred.lock().unwrap();
+        if let Some(idx) = deferred
+            .iter()
+            .position(|(deferred_id, _)| *deferred_id == id)
+        {
+            deferred.remove(idx);
+            self.deferred_freed.notify_all();
+            return Ok(true);
+        }
+        drop(deferred);
+        if *self.current_utterance.lock().unwrap() != Some(id) {
+            return Ok(false);
+        }
+        self.stop()?;
+        Ok(true)
+    }
+
+    /// Whether the given utterance was entirely spoken or interrupted
+    pub fn is_utterance_done(&self, id: u64) -> bool {
+        let deferred = self.deferred.lock().unwrap();
+        let held = self.held.lock().unwrap();
+        let alerts = self.alerts.lock().unwrap();
+        let interrupted = self.interrupted.lock().unwrap();
+        let over = self
+            .overlay()
+            .is_some_and(|overlay| *overlay.current_utterance.lock().unwrap() == Some(id));
+        *self.current_utterance.lock().unwrap() != Some(id)
+            && !deferred.iter().any(|(deferred_id, _)| *deferred_id == id)
+            && held.as_ref().is_none_or(|(held_id, _)| *held_id != id)
+            && !alerts.iter().any(|(alert_id, _)| *alert_id == id)
+            && interrupted.as_ref().is_none_or(|i| i.id != id)
+            && !over
+    }
+
+    /// Describe the elements waiting to be spoken. Those of a lazy source, such as a
+    /// speech feed, are only known once pulled into the look-ahead queue.
+    pub fn pending_elements(&self) -> Vec<QueuedElement> {
+        let mut source = self.speech_source.lock().unwrap();
+        let mut pending: Vec<_> = self
+            .speech_queue
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, element)| QueuedElement::from(element))
+            .collect();
+        let known = source.as_ref().and_then(SpeechSource::known_len).is_some();
+        if let (true, Some(SpeechSource::Iter(iter))) = (known, source.as_mut()) {
+            let rest: Vec<_> = iter.collect();
+            pending.extend(rest.iter().map(QueuedElement::from));
+            *iter = (Box::new(rest.into_iter()) as Box<dyn Iterator<Item = _> + Send>).peekable();
         }
+        pending
+    }
+
+    /// The number of elements `pending_elements` describes
+    pub fn pending_count(&self) -> usize {
+        let source = self.speech_source.lock().unwrap();
+        let known = source.as_ref().and_then(SpeechSource::known_len);
+        self.speech_queue.lock().unwrap().len() + known.unwrap_or(0)
+    }
+
+    /// Drop the elements waiting to be spoken, the element being spoken goes on until it ends
+    pub fn clear_pending(&self) -> NeosynthResult<()> {
+        self.speech_source.lock().unwrap().take();
+        self.speech_queue.lock().unwrap().clear();
+        // Nothing is playing while waiting for a feed, end the utterance right away
+        if self.starved.swap(false, Ordering::SeqCst) {
+            self.process_queue()?;
+        }
+        Ok(())
+    }
+
+    pub fn clear_speech_queue(&self) -> NeosynthResult<()> {
+        self.speech_source.lock().unwrap().take();
+        self.device_retry.lock().unwrap().waited = None;
+        self.set_buffering(false);
+        self.finish_audio();
+        self.finish_utterance();
+        self.starved.store(false, Ordering::SeqCst);
+        self.silence_due.store(false, Ordering::SeqCst);
+        self.pending_stop.lock().unwrap().take();
+        self.element_index.store(0, Ordering::SeqCst);
+        self.speech_queue.lock().unwrap().clear();
+        self.prefetched.lock().unwrap().clear();
+        self.replay.lock().unwrap().take();
         Ok(())
     }
 }
 
+/// An utterance passed to `Neosynth.speak`, to follow or cancel it
+#[pyclass(frozen)]
+pub struct UtteranceHandle {
+    /// The id the event sink reports the utterance with
+    #[pyo3(get)]
+    id: u64,
+    mixer: Weak<SpeechMixer<PyEventSinkWrapper>>,
+}
+
+#[pymethods]
+impl UtteranceHandle {
+    /// Stop speaking the utterance, other utterances are left alone.
+    /// Returns false if it was already done.
+    #[pyo3(text_signature = "($self) -> bool")]
+    pub fn cancel(&self) -> NeosynthResult<bool> {
+        match self.mixer.upgrade() {
+            Some(mixer) => mixer.cancel_utterance(self.id),
+            None => Ok(false),
+        }
+    }
+    /// Whether the utterance was entirely spoken, interrupted or cancelled
+    #[pyo3(text_signature = "($self) -> bool")]
+    pub fn done(&self) -> bool {
+        self.mixer
+            .upgrade()
+            .is_none_or(|mixer| mixer.is_utterance_done(self.id))
+    }
+    fn __int__(&self) -> u64 {
+        self.id
+    }
+    /// Handles are equal to the other handles and the ids of their utterance
+    fn __richcmp__(&self, py: Python<'_>, other: &PyAny, op: CompareOp) -> PyObject {
+        let other_id = match other.extract::<PyRef<UtteranceHandle>>() {
+            Ok(handle) => Some(handle.id),
+            Err(_) => other.extract::<u64>().ok(),
+        };
+        match (op, other_id) {
+            (CompareOp::Eq, Some(id)) => (self.id == id).into_py(py),
+            (CompareOp::Ne, Some(id)) => (self.id != id).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+    /// Hashed like the id, as handles and ids compare equal
+    fn __hash__(&self, py: Python<'_>) -> PyResult<isize> {
+        self.id.into_py(py).as_ref(py).hash()
+    }
+    fn __repr__(&self) -> String {
+        format!("<UtteranceHandle id={}>", self.id)
+    }
+}
+
+/// Every synthesizer instance, each one is a channel which can be muted or soloed
+static CHANNELS: Mutex<Vec<Weak<SpeechMixer<PyEventSinkWrapper>>>> = Mutex::new(Vec::new());
+
 #[pyclass(subclass, frozen)]
-pub struct Neosynth(Arc<SpeechMixer<PyEventSinkWrapper>>);
+pub struct Neosynth(
+    Arc<SpeechMixer<PyEventSinkWrapper>>,
+    Mutex<Option<EchoMode>>,
+    RwLock<HashMap<String, UtteranceTemplate>>,
+);
+
+/// Speak each element twice, the second time with another voice and optionally translated
+#[derive(Clone)]
+struct EchoMode {
+    first_voice: VoiceInformation,
+    second_voice: VoiceInformation,
+    /// Called with the text of each element, returns the text for the second voice or None to skip it
+    translator: Option<PyObject>,
+}
+
+impl EchoMode {
+    /// The voice switches are undone by the mixer once the utterance ends
+    fn expand(&self, py: Python<'_>, elements: Vec<SpeechElement>) -> PyResult<Vec<SpeechElement>> {
+        let mut expanded = Vec::with_capacity(elements.len() * 4);
+        for element in elements {
+            let echo = match &element {
+                SpeechElement::Text(text) => match &self.translator {
+                    Some(translator) => translator
+                        .call1(py, (text.as_str(),))?
+                        .extract::<Option<String>>(py)?
+                        .map(SpeechElement::Text),
+                    None => Some(element.clone()),
+                },
+                SpeechElement::Ssml(_) => Some(element.clone()),
+                _ => None,
+            };
+            match echo {
+                Some(echo) => {
+                    expanded.push(SpeechElement::AutoVoice(self.first_voice.clone()));
+                    expanded.push(element);
+                    expanded.push(SpeechElement::AutoVoice(self.second_voice.clone()));
+                    expanded.push(echo);
+                }
+                None => expanded.push(element),
+            }
+        }
+        Ok(expanded)
+    }
+}
 
 impl Neosynth {
     pub fn new(
@@ -429,11 +5205,120 @@ impl Neosynth {
         speech_appended_silence: bool,
         punctuation_silence: bool,
     ) -> NeosynthResult<Self> {
-        let instance = Self(Arc::new(SpeechMixer::new(event_sink_wrapper)?));
+        let instance = Self(
+            Arc::new(SpeechMixer::new(event_sink_wrapper)?),
+            Mutex::new(None),
+            RwLock::new(HashMap::new()),
+        );
         instance.initialize(speech_appended_silence, punctuation_silence)?;
+        CHANNELS.lock().unwrap().push(Arc::downgrade(&instance.0));
+        Self::update_channels()?;
         Ok(instance)
     }
 
+    /// Apply the mute and solo state of every channel
+    fn update_channels() -> NeosynthResult<()> {
+        let mixers: Vec<_> = {
+            let mut channels = CHANNELS.lock().unwrap();
+            channels.retain(|channel| channel.strong_count() > 0);
+            channels.iter().filter_map(Weak::upgrade).collect()
+        };
+        let any_soloed = mixers.iter().any(|mixer| mixer.is_soloed());
+        for mixer in mixers {
+            mixer.update_muting(any_soloed)?;
+        }
+        Ok(())
+    }
+
+    /// Stop soloing this channel, if it is, so that the other channels are heard again
+    fn release_solo(&self) -> NeosynthResult<()> {
+        if self.0.is_soloed() {
+            self.0.set_soloed(false);
+            Self::update_channels()?;
+        }
+        Ok(())
+    }
+
+    /// Apply the defaults from a configuration file or the environment
+    pub fn apply_config(&self, config: &EngineConfig) -> NeosynthResult<()> {
+        config.validate()?;
+        if let Some(voice) = &config.voice {
+            let voices = Self::get_voices()?;
+            let found = voices
+                .into_iter()
+                .find(|v| &v.id == voice || &v.name == voice)
+                .or_else(|| Self::find_voices(voice).ok()?.into_iter().next());
+            match found {
+                Some(v) => self.set_voice(&v)?,
+                None => return Err(NeosynthError::Voice(format!("No voice matches {}", voice))),
+            }
+        }
+        if let Some(rate) = config.rate {
+            self.0.set_rate(rate)?;
+        }
+        if let Some(pitch) = config.pitch {
+            self.set_pitch(pitch)?;
+        }
+        if let Some(volume) = config.volume {
+            self.set_volume(volume)?;
+        }
+        if let Some(max_text_length) = config.max_text_length {
+            self.0.set_max_text_length(max_text_length);
+        }
+        if let Some(log_level) = config.log_level {
+            self.0.set_log_level(log_level);
+        }
+        if let Some(enabled) = config.line_joining {
+            self.0.set_line_joining(enabled);
+        }
+        if let Some(enabled) = config.bidi_cleanup {
+            self.0.set_bidi_cleanup(enabled);
+        }
+        Ok(())
+    }
+
+    /// Get the current settings, in the form accepted by `apply_config`
+    pub fn current_config(&self) -> NeosynthResult<EngineConfig> {
+        Ok(EngineConfig {
+            voice: Some(self.get_voice_str()?),
+            rate: if Self::is_prosody_supported()? {
+                Some(self.get_rate()?)
+            } else {
+                None
+            },
+            pitch: Some(self.get_pitch()?),
+            volume: Some(self.get_volume()?),
+            max_text_length: Some(self.0.get_max_text_length()),
+            log_level: Some(self.0.get_log_level()),
+            line_joining: Some(self.0.line_joining.load(Ordering::SeqCst)),
+            bidi_cleanup: Some(self.0.bidi_cleanup.load(Ordering::SeqCst)),
+        })
+    }
+
+    fn config_from_dict(settings: &PyDict) -> PyResult<EngineConfig> {
+        let mut config = EngineConfig::default();
+        for (key, value) in settings.iter() {
+            let key: &str = key.extract()?;
+            match key {
+                "voice" => {
+                    config.voice = Some(match value.extract::<PyRef<VoiceInfo>>() {
+                        Ok(voice) => voice.id.clone(),
+                        Err(_) => value.extract()?,
+                    })
+                }
+                "rate" => config.rate = Some(value.extract()?),
+                "pitch" => config.pitch = Some(value.extract()?),
+                "volume" => config.volume = Some(value.extract()?),
+                "max_text_length" => config.max_text_length = Some(value.extract()?),
+                "log_level" => config.log_level = Some(value.extract::<&str>()?.parse()?),
+                "line_joining" => config.line_joining = Some(value.extract()?),
+                "bidi_cleanup" => config.bidi_cleanup = Some(value.extract()?),
+                _ => return Err(PyValueError::new_err(format!("Unknown setting: {}", key))),
+            }
+        }
+        Ok(config)
+    }
+
     fn initialize(
         &self,
         speech_appended_silence: bool,
@@ -458,64 +5343,66 @@ impl Neosynth {
                     .SetPunctuationSilence(SpeechPunctuationSilence::Min)?;
             }
         }
-        self.register_events()?;
+        // Deliver word and sentence boundaries to the event sink's
+        // `on_word_reached` and `on_sentence_reached`
+        if ApiInformation::IsApiContractPresentByMajorAndMinor(
+            &HSTRING::from("Windows.Foundation.UniversalApiContract"),
+            4,
+            0,
+        )? {
+            let options = self.0.synthesizer.Options()?;
+            options.SetIncludeWordBoundaryMetadata(true)?;
+            options.SetIncludeSentenceBoundaryMetadata(true)?;
+        }
+        SpeechMixer::register_events(&self.0)?;
         Ok(())
     }
+}
 
-    fn register_events(&self) -> NeosynthResult<()> {
-        let mixer = Arc::clone(&self.0);
-        self.0
-            .player
-            .0
-            .MediaEnded(&TypedEventHandler::<MediaPlayer, _>::new(move |_, _| {
-                mixer.process_queue().ok();
-                Ok(())
-            }))?;
-        let mixer = Arc::clone(&self.0);
-        self.0
-            .player
-            .0
-            .MediaFailed(&TypedEventHandler::<MediaPlayer, _>::new(move |_, _| {
-                mixer.process_queue().ok();
-                Ok(())
-            }))?;
-        Ok(())
+impl Drop for Neosynth {
+    fn drop(&mut self) {
+        // Let the other channels be heard again, if the instance wasn't closed
+        if let Err(e) = self.release_solo() {
+            self.0
+                .report_internal_error("Unmuting the other channels failed", &e);
+        }
+        // Nothing is left to wait for the synthesis and file operations still running
+        self.0.async_ops.cancel_all();
+        if let Some(overlay) = self.0.overlay() {
+            overlay.async_ops.cancel_all();
+        }
     }
 }
 
 #[pymethods]
 impl Neosynth {
     #[new]
-    #[args(speech_appended_silence = "false", punctuation_silence = false)]
+    #[args(
+        speech_appended_silence = "false",
+        punctuation_silence = false,
+        config_path = "None"
+    )]
     pub fn py_init(
-        py: Python<'_>,
         event_sink: PyObject,
         speech_appended_silence: bool,
         punctuation_silence: bool,
+        config_path: Option<String>,
     ) -> PyResult<Self> {
-        let obj: &PyAny = event_sink.as_ref(py);
-        if (!obj.hasattr(intern!(py, "on_state_changed"))?)
-            || (!obj.hasattr(intern!(py, "on_bookmark_reached"))?)
-        {
-            Err(PyTypeError::new_err(
-                "The provided object does not have the required method handlers.",
-            ))
-        } else {
-            Ok(Self::new(
-                PyEventSinkWrapper::new(event_sink),
-                speech_appended_silence,
-                punctuation_silence,
-            )?)
-        }
+        // Every handler is optional, events without a handler in the sink are dropped
+        let instance = Self::new(
+            PyEventSinkWrapper::new(event_sink),
+            speech_appended_silence,
+            punctuation_silence,
+        )?;
+        instance.apply_config(&EngineConfig::load(
+            config_path.as_deref().map(std::path::Path::new),
+        )?)?;
+        Ok(instance)
     }
     /// Indicates if the prosody option is supported
     #[staticmethod]
     pub fn is_prosody_supported() -> NeosynthResult<bool> {
-        Ok(ApiInformation::IsApiContractPresentByMajorAndMinor(
-            &HSTRING::from("Windows.Foundation.UniversalApiContract"),
-            5,
-            0,
-        )?)
+        SpeechMixer::<PyEventSinkWrapper>::is_prosody_supported()
     }
     /// Get the current state of the synthesizer
     #[pyo3(text_signature = "($self) -> neosynth.SynthState")]
@@ -534,40 +5421,93 @@ impl Neosynth {
     pub fn set_volume(&self, volume: f64) -> NeosynthResult<()> {
         self.0.player.set_volume(volume)
     }
+    /// Silence this synthesizer without stopping its speech
+    #[pyo3(text_signature = "($self)")]
+    pub fn mute(&self) -> NeosynthResult<()> {
+        self.0.set_muted(true);
+        Self::update_channels()
+    }
+    /// Make this synthesizer audible again after `mute()`
+    #[pyo3(text_signature = "($self)")]
+    pub fn unmute(&self) -> NeosynthResult<()> {
+        self.0.set_muted(false);
+        Self::update_channels()
+    }
+    #[pyo3(text_signature = "($self) -> bool")]
+    pub fn is_muted(&self) -> bool {
+        self.0.is_muted()
+    }
+    /// Silence every synthesizer instance which isn't soloed
+    #[pyo3(text_signature = "($self)")]
+    pub fn solo(&self) -> NeosynthResult<()> {
+        self.0.set_soloed(true);
+        Self::update_channels()
+    }
+    /// Stop soloing this synthesizer, other instances are audible again once none is soloed
+    #[pyo3(text_signature = "($self)")]
+    pub fn unsolo(&self) -> NeosynthResult<()> {
+        self.0.set_soloed(false);
+        Self::update_channels()
+    }
+    #[pyo3(text_signature = "($self) -> bool")]
+    pub fn is_soloed(&self) -> bool {
+        self.0.is_soloed()
+    }
     /// Get the current speaking rate
     #[pyo3(text_signature = "($self) -> float")]
     pub fn get_rate(&self) -> NeosynthResult<f64> {
-        if !Self::is_prosody_supported()? {
-            Ok(-1.0)
-        } else {
-            Ok(self.0.synthesizer.Options()?.SpeakingRate()? / 0.06)
-        }
+        self.0.get_rate()
     }
     /// Set the current speaking rate
     #[pyo3(text_signature = "($self, rate: float)")]
     pub fn set_rate(&self, value: f64) -> NeosynthResult<()> {
-        if Self::is_prosody_supported()? {
-            Ok(self
-                .0
-                .synthesizer
-                .Options()?
-                .SetSpeakingRate(value * 0.06)?)
-        } else {
-            Err(NeosynthError::OperationError(
-                "The current version of OneCore synthesizer does not support the prosody option"
-                    .to_string(),
-            ))
-        }
+        self.0.set_rate(value)
+    }
+    /// Gradually increase the speaking rate by `step` after every given number of elements
+    /// or seconds, until it reaches `max_rate`
+    #[pyo3(
+        text_signature = "($self, step: float, max_rate: float, every_elements: int = None, every_seconds: float = None)"
+    )]
+    #[args(every_elements = "None", every_seconds = "None")]
+    pub fn set_rate_ramp(
+        &self,
+        step: f64,
+        max_rate: f64,
+        every_elements: Option<usize>,
+        every_seconds: Option<f64>,
+    ) -> PyResult<()> {
+        let interval = match (every_elements, every_seconds) {
+            (Some(count), None) => RampInterval::Elements(count),
+            (None, Some(seconds)) if seconds >= 0.0 => {
+                RampInterval::Time(Duration::from_secs_f64(seconds))
+            }
+            _ => {
+                return Err(PyValueError::new_err(
+                    "Exactly one of every_elements or every_seconds must be given",
+                ))
+            }
+        };
+        self.0.set_rate_ramp(Some(RateRamp {
+            step,
+            max_rate,
+            interval,
+        }));
+        Ok(())
+    }
+    /// Stop increasing the speaking rate, the current rate is kept
+    #[pyo3(text_signature = "($self)")]
+    pub fn clear_rate_ramp(&self) {
+        self.0.set_rate_ramp(None);
     }
     /// Get the voice pitch
     #[pyo3(text_signature = "($self) -> float")]
     pub fn get_pitch(&self) -> NeosynthResult<f64> {
-        Ok(self.0.synthesizer.Options()?.AudioPitch()? * 50.0)
+        self.0.get_pitch()
     }
     /// Set the voice pitch
     #[pyo3(text_signature = "($self, pitch: float)")]
     pub fn set_pitch(&self, value: f64) -> NeosynthResult<()> {
-        Ok(self.0.synthesizer.Options()?.SetAudioPitch(value / 50.0)?)
+        self.0.set_pitch(value)
     }
     /// Get the current voice
     #[pyo3(text_signature = "($self) -> neosynth.VoiceInfo")]
@@ -582,6 +5522,16 @@ impl Neosynth {
             .synthesizer
             .SetVoice(&VoiceInformation::from(voice))?)
     }
+    /// Get the current voice and prosody settings as a neosynth.SpeechProfile
+    #[pyo3(text_signature = "($self) -> neosynth.SpeechProfile")]
+    pub fn get_profile(&self) -> NeosynthResult<SpeechProfile> {
+        self.0.current_profile()
+    }
+    /// Apply the settings present in a neosynth.SpeechProfile
+    #[pyo3(text_signature = "($self, profile: neosynth.SpeechProfile)")]
+    pub fn set_profile(&self, profile: &SpeechProfile) -> NeosynthResult<()> {
+        self.0.apply_profile(profile)
+    }
     /// Get the current voice's string representation
     #[pyo3(text_signature = "($self) -> str")]
     pub fn get_voice_str(&self) -> NeosynthResult<String> {
@@ -593,9 +5543,31 @@ impl Neosynth {
         let voice = Self::get_voices()?.into_iter().find(|v| v.id == id);
         match voice {
             Some(v) => self.set_voice(&v),
-            None => Err(OperationError("Invalid voice token given".to_string())),
+            None => Err(NeosynthError::Voice(
+                "Invalid voice token given".to_string(),
+            )),
         }
     }
+    /// Voices to speak with, tried in order, when the current voice turns out to be
+    /// unavailable, e.g. restricted to other applications or locales. The voice which
+    /// speaks becomes the current voice and `on_voice_fallback` is called. Once none can,
+    /// `VoiceUnavailableError` is raised or reported.
+    #[pyo3(text_signature = "($self, voices: list[neosynth.VoiceInfo])")]
+    pub fn set_voice_fallbacks(&self, voices: Vec<PyRef<VoiceInfo>>) {
+        self.0.set_voice_fallbacks(
+            voices
+                .iter()
+                .map(|voice| VoiceInformation::from(&**voice))
+                .collect(),
+        );
+    }
+    /// Keep a hash of the audio synthesized for each element in the file at `path`, and call
+    /// `on_synthesis_changed` when the same text, voice and settings produce different
+    /// audio than in previous runs, e.g. after a Windows update. None stops fingerprinting.
+    #[pyo3(text_signature = "($self, path: str | None)")]
+    pub fn set_fingerprint_file(&self, path: Option<PathBuf>) -> NeosynthResult<()> {
+        self.0.set_fingerprint_file(path.as_deref())
+    }
     /// Get a list of installed voices
     #[staticmethod]
     #[pyo3(text_signature = "() -> list[neosynth.VoiceInfo]")]
@@ -606,44 +5578,869 @@ impl Neosynth {
             .collect();
         Ok(voices)
     }
-    /// Speak a neosynth.SpeechUtterance
-    #[pyo3(text_signature = "($self, utterance: neosynth.SpeechUtterance)")]
-    pub fn speak(&self, utterance: SpeechUtterance) -> NeosynthResult<()> {
-        self.0.speak(utterance.0)?;
-        self.0.set_state(SynthState::Busy)?;
-        self.0.player.play()?;
+    /// Forget the audio formats of the voices cached on disk, they are probed again when needed
+    #[staticmethod]
+    #[pyo3(text_signature = "()")]
+    pub fn clear_voice_cache() {
+        if let Some(cache) = voice_cache() {
+            cache.clear();
+        }
+    }
+    /// Get the installed voices for a language, falling back to other regions of the same language
+    #[staticmethod]
+    #[pyo3(text_signature = "(language: str) -> list[neosynth.VoiceInfo]")]
+    pub fn find_voices(language: &str) -> NeosynthResult<Vec<VoiceInfo>> {
+        let candidates: Vec<_> = Self::get_voices()?
+            .into_iter()
+            .filter_map(|v| match_language(language, &v.language).map(|m| (m, v)))
+            .collect();
+        let best_match = candidates.iter().map(|(m, _)| *m).max();
+        Ok(candidates
+            .into_iter()
+            .filter(|(m, _)| Some(*m) == best_match)
+            .map(|(_, v)| v)
+            .collect())
+    }
+    /// Assign distinct installed voices to the speakers of a dialogue. `speakers` maps each
+    /// speaker label to the wanted `gender` and `language`, the language defaulting to `language`.
+    /// A speaker gets the same voice across sessions as long as the installed voices don't change.
+    #[staticmethod]
+    #[pyo3(
+        text_signature = "(speakers: dict[str, dict[str, str]], language: str = None) -> dict[str, neosynth.VoiceInfo]"
+    )]
+    #[args(language = "None")]
+    pub fn cast_voices(
+        speakers: HashMap<String, HashMap<String, String>>,
+        language: Option<String>,
+    ) -> NeosynthResult<HashMap<String, VoiceInfo>> {
+        let roles = speakers
+            .into_iter()
+            .map(|(label, attributes)| {
+                let mut role = casting::Role::from_attributes(attributes)?;
+                role.language = role.language.or_else(|| language.clone());
+                Ok((label, role))
+            })
+            .collect::<NeosynthResult<_>>()?;
+        casting::cast_voices(roles, &Self::get_voices()?)
+    }
+    /// Get the synthesis success and failure counts of a voice, the current voice by default
+    #[pyo3(text_signature = "($self, voice_id: str = None) -> neosynth.VoiceHealth")]
+    #[args(voice_id = "None")]
+    pub fn get_voice_health(&self, voice_id: Option<String>) -> NeosynthResult<VoiceHealth> {
+        let voice_id = match voice_id {
+            Some(voice_id) => voice_id,
+            None => self.get_voice_str()?,
+        };
+        Ok(self.0.voice_health.get(&voice_id))
+    }
+    /// Get the synthesis success and failure counts of every voice used so far
+    #[pyo3(text_signature = "($self) -> list[neosynth.VoiceHealth]")]
+    pub fn get_all_voice_health(&self) -> Vec<VoiceHealth> {
+        self.0.voice_health.all()
+    }
+    /// Estimate the time in seconds needed to speak a text or a neosynth.SpeechUtterance at the current settings.
+    /// Estimates are refined for each voice as it speaks.
+    #[pyo3(text_signature = "($self, content: str | neosynth.SpeechUtterance) -> float")]
+    pub fn estimate_duration(&self, content: &PyAny) -> PyResult<f64> {
+        let seconds = if let Ok(utterance) = content.extract::<PyRef<SpeechUtterance>>() {
+            self.0.estimate_duration(&utterance.0)?
+        } else {
+            let text: String = content.extract()?;
+            self.0.estimate_duration(&[SpeechElement::Text(text)])?
+        };
+        Ok(seconds)
+    }
+    /// Speak a neosynth.SpeechUtterance in place of the current one, returns a handle
+    /// carrying the id the event sink reports it with. During quiet hours, utterances
+    /// which aren't a `priority` may be deferred until they end. Utterances identical to
+    /// one spoken within the deduplication window are dropped, returning its handle.
+    /// `level` is "normal", "alert" to interrupt normal speech until alerts are spoken,
+    /// or "over" to speak on top of other speech, both of which are a priority. Deferred
+    /// and held utterances are spoken as normal speech.
+    #[pyo3(
+        text_signature = "($self, utterance: neosynth.SpeechUtterance, priority: bool = False, level: str = \"normal\") -> neosynth.UtteranceHandle"
+    )]
+    #[args(priority = "false", level = "\"normal\"")]
+    pub fn speak(
+        &self,
+        py: Python<'_>,
+        utterance: SpeechUtterance,
+        priority: bool,
+        level: &str,
+    ) -> PyResult<UtteranceHandle> {
+        let level = SpeechPriority::from_str(level)?;
+        let priority = priority || level != SpeechPriority::Normal;
+        if self.0.is_closed() {
+            return Err(OperationError("The synthesizer has been closed".to_string()).into());
+        }
+        let dedup_key = self.0.dedup_key(&utterance.0);
+        if let Some(id) = dedup_key
+            .as_deref()
+            .and_then(|key| self.0.recent_duplicate(key))
+        {
+            return Ok(UtteranceHandle {
+                id,
+                mixer: Arc::downgrade(&self.0),
+            });
+        }
+        // Not locked while calling the translator, which may change the echo mode
+        let echo_mode = self.1.lock().unwrap().clone();
+        let elements = match echo_mode {
+            Some(echo_mode) => echo_mode.expand(py, utterance.0)?,
+            None => utterance.0,
+        };
+        let paused = self.0.get_state()? == SynthState::Paused;
+        let id = if !priority && self.0.should_defer()? {
+            // Deferring may wait for room among the deferred utterances
+            let mixer = Arc::clone(&self.0);
+            py.allow_threads(move || mixer.defer(elements))?
+        } else if level == SpeechPriority::Over {
+            self.0.speak_over(elements)?
+        } else if paused && self.0.paused_speech_mode() != PausedSpeechMode::Resume {
+            if self.0.paused_speech_mode() == PausedSpeechMode::Raise {
+                return Err(OperationError("Speech was requested while paused".to_string()).into());
+            }
+            self.0.hold(elements)
+        } else if level == SpeechPriority::Alert {
+            self.0.speak_alert(elements)?
+        } else {
+            let id = self.0.speak(elements)?;
+            self.0.set_busy()?;
+            self.0.player.play()?;
+            id
+        };
+        if priority {
+            self.0.set_priority(id)?;
+        }
+        if let Some(key) = dedup_key {
+            self.0.remember_announcement(key, id);
+        }
+        Ok(UtteranceHandle {
+            id,
+            mixer: Arc::downgrade(&self.0),
+        })
+    }
+    /// Interrupt the current speech right away and speak the utterance in its place.
+    /// Unlike `speak`, the current element is silenced before the new utterance is
+    /// synthesized, and unlike `stop` followed by `speak` no event of the interrupted
+    /// element can advance the new utterance.
+    #[pyo3(
+        text_signature = "($self, utterance: neosynth.SpeechUtterance, priority: bool = False) -> neosynth.UtteranceHandle"
+    )]
+    #[args(priority = "false")]
+    pub fn speak_now(
+        &self,
+        py: Python<'_>,
+        utterance: SpeechUtterance,
+        priority: bool,
+    ) -> PyResult<UtteranceHandle> {
+        // Suppressed duplicates don't interrupt anything either
+        let dedup_key = self.0.dedup_key(&utterance.0);
+        if let Some(id) = dedup_key
+            .as_deref()
+            .and_then(|key| self.0.recent_duplicate(key))
+        {
+            return Ok(UtteranceHandle {
+                id,
+                mixer: Arc::downgrade(&self.0),
+            });
+        }
+        // Deferred speech doesn't interrupt anything
+        let interrupts = priority || !self.0.should_defer()?;
+        if interrupts {
+            // Pausing, unlike stopping, doesn't make the player report the end of the element
+            self.0.player.pause()?;
+        }
+        let result = self.speak(py, utterance, priority, "normal");
+        // The interrupted speech goes on if the new utterance couldn't replace it
+        if result.is_err() && interrupts && self.0.get_state()? == SynthState::Busy {
+            self.0.player.resume()?;
+        }
+        result
+    }
+    /// Keep an utterance whose text, SSML, structured text and code elements have `{name}`
+    /// placeholders to speak it with `speak_template`. Braces are doubled to be kept as is.
+    /// `name` and `priority` can't be placeholders, they are arguments of `speak_template`.
+    /// Returns the names of the placeholders.
+    #[pyo3(text_signature = "($self, name: str, utterance: neosynth.SpeechUtterance) -> list[str]")]
+    pub fn register_template(
+        &self,
+        name: String,
+        utterance: SpeechUtterance,
+    ) -> PyResult<Vec<String>> {
+        let template = UtteranceTemplate::new(utterance.0)?;
+        let slots = template.slots();
+        if let Some(slot) = slots
+            .iter()
+            .find(|slot| ["name", "priority"].contains(&slot.as_str()))
+        {
+            return Err(OperationError(format!("Reserved template slot name: {}", slot)).into());
+        }
+        self.2.write().unwrap().insert(name, template);
+        Ok(slots)
+    }
+    /// Forget a template, returns whether it was registered
+    #[pyo3(text_signature = "($self, name: str) -> bool")]
+    pub fn unregister_template(&self, name: &str) -> bool {
+        self.2.write().unwrap().remove(name).is_some()
+    }
+    /// Speak a registered template with its placeholders filled by the keyword arguments,
+    /// converted to strings: `speak_template("page_turn", page=5)`
+    #[pyo3(
+        text_signature = "($self, name: str, priority: bool = False, **slots) -> neosynth.UtteranceHandle"
+    )]
+    #[args(priority = "false", slots = "**")]
+    pub fn speak_template(
+        &self,
+        py: Python<'_>,
+        name: &str,
+        priority: bool,
+        slots: Option<&PyDict>,
+    ) -> PyResult<UtteranceHandle> {
+        let mut values = HashMap::new();
+        for (slot, value) in slots.into_iter().flatten() {
+            values.insert(
+                slot.extract::<String>()?,
+                value.str()?.to_str()?.to_string(),
+            );
+        }
+        let elements = match self.2.read().unwrap().get(name) {
+            Some(template) => template.render(&values)?,
+            None => {
+                return Err(OperationError(format!("No template named {}", name)).into());
+            }
+        };
+        self.speak(py, SpeechUtterance(elements), priority, "normal")
+    }
+    /// Only report the Busy state once audio actually starts playing, so slow audio
+    /// sources don't cause misleading state changes
+    #[pyo3(text_signature = "($self, wait: bool)")]
+    pub fn set_wait_for_buffering(&self, wait: bool) {
+        self.0.set_wait_for_buffering(wait);
+    }
+    /// Speak each text and SSML element twice, first with `first_voice` then with `second_voice`.
+    /// If given, `translator` is called with the text of each text element and returns
+    /// the text spoken by the second voice, or None to speak it only once.
+    #[pyo3(
+        text_signature = "($self, first_voice: neosynth.VoiceInfo, second_voice: neosynth.VoiceInfo, translator: Callable[[str], str | None] = None)"
+    )]
+    #[args(translator = "None")]
+    pub fn set_echo_mode(
+        &self,
+        first_voice: &VoiceInfo,
+        second_voice: &VoiceInfo,
+        translator: Option<PyObject>,
+    ) {
+        *self.1.lock().unwrap() = Some(EchoMode {
+            first_voice: first_voice.into(),
+            second_voice: second_voice.into(),
+            translator,
+        });
+    }
+    /// Speak each element once again
+    #[pyo3(text_signature = "($self)")]
+    pub fn clear_echo_mode(&self) {
+        self.1.lock().unwrap().take();
+    }
+    /// Preview a structured utterance by only speaking its headings, landmarks and
+    /// the first sentence of each paragraph
+    #[pyo3(text_signature = "($self, enabled: bool)")]
+    pub fn set_preview_mode(&self, enabled: bool) {
+        self.0.set_preview_mode(enabled);
+    }
+    /// Remove the directional marks, embeddings and isolates some voices stumble on, and
+    /// separate right-to-left words directly followed by left-to-right ones or the other
+    /// way round. Word and sentence offsets then refer to the cleaned up text.
+    #[pyo3(text_signature = "($self, enabled: bool)")]
+    pub fn set_bidi_cleanup(&self, enabled: bool) {
+        self.0.set_bidi_cleanup(enabled);
+    }
+    /// Rejoin the words and sentences of text elements broken across lines, as in text
+    /// extracted from PDF documents. Soft hyphens are removed, words hyphenated at the end
+    /// of a line are joined and line breaks within paragraphs become spaces.
+    #[pyo3(text_signature = "($self, enabled: bool)")]
+    pub fn set_line_joining(&self, enabled: bool) {
+        self.0.set_line_joining(enabled);
+    }
+    /// Rewrite text elements for the voice's language: Roman numerals are read as numbers
+    /// (`Chapter XIV`, `Henry VIII`), abbreviated ordinals as words (`21st`) and ranges and
+    /// page references in full (`pp. 10–12`). English, French, German and Spanish have rules.
+    #[pyo3(
+        text_signature = "($self, roman_numerals: bool = False, ordinals: bool = False, ranges: bool = False)"
+    )]
+    #[args(roman_numerals = "false", ordinals = "false", ranges = "false")]
+    pub fn set_reading_rules(&self, roman_numerals: bool, ordinals: bool, ranges: bool) {
+        self.0.set_reading_rules(ReadingRules {
+            roman_numerals,
+            ordinals,
+            ranges,
+        });
+    }
+    /// Read the acronyms of text elements, words of two to six capitals, as given by
+    /// `expansions` or, if `spell` is true, letter by letter. Utterances may add their own
+    /// expansions with `SpeechUtterance.add_acronyms`.
+    #[pyo3(text_signature = "($self, spell: bool = True, expansions: dict[str, str] = None)")]
+    #[args(spell = "true", expansions = "None")]
+    pub fn set_acronym_filter(&self, spell: bool, expansions: Option<HashMap<String, String>>) {
+        self.0.set_acronym_filter(Some(AcronymFilter {
+            spell,
+            expansions: expansions.unwrap_or_default(),
+        }));
+    }
+    /// Read runs of the same punctuation character repeated at least `min_length` times
+    /// in text elements, such as separators, as a short description like "line" if
+    /// `describe` is true or skip them otherwise. `min_length` is at least 3, and an
+    /// ellipsis of three dots is always read as is. None reads every character again.
+    #[pyo3(text_signature = "($self, min_length: int | None, describe: bool = True)")]
+    #[args(describe = "true")]
+    pub fn set_punctuation_runs(&self, min_length: Option<usize>, describe: bool) -> PyResult<()> {
+        if let Some(min_length @ 0..=2) = min_length {
+            return Err(PyValueError::new_err(format!(
+                "min_length must be at least 3, got {}",
+                min_length
+            )));
+        }
+        self.0
+            .set_punctuation_runs(min_length.map(|min_length| PunctuationRuns {
+                min_length,
+                describe,
+            }));
+        Ok(())
+    }
+    /// Leave acronyms to the voice again, except those given by the utterance
+    #[pyo3(text_signature = "($self)")]
+    pub fn clear_acronym_filter(&self) {
+        self.0.set_acronym_filter(None);
+    }
+    /// Speak emoji and symbols, which voices skip or garble, according to `mode`: "ignore"
+    /// removes them, "name" replaces them with their name in the language of the voice, or
+    /// in English if it has none, and "name with category" adds their category. None leaves them to the voice.
+    #[pyo3(text_signature = "($self, mode: str | None)")]
+    pub fn set_symbol_mode(&self, mode: Option<&str>) -> NeosynthResult<()> {
+        self.0.set_symbol_mode(mode.map(str::parse).transpose()?);
         Ok(())
     }
+    /// Drop audio elements, including the voice change cue, if `skip_audio` is true, and
+    /// spoken elements if `skip_speech` is true, e.g. for users who dislike earcons.
+    /// Bookmarks are still reported. Takes effect from the next element.
+    #[pyo3(text_signature = "($self, skip_audio: bool = False, skip_speech: bool = False)")]
+    #[args(skip_audio = "false", skip_speech = "false")]
+    pub fn set_skipped_elements(&self, skip_audio: bool, skip_speech: bool) {
+        self.0.set_skipped_elements(skip_audio, skip_speech);
+    }
+    /// Drop the elements added as skippable instead of speaking them, takes effect immediately
+    #[pyo3(text_signature = "($self, enabled: bool)")]
+    pub fn set_skim_mode(&self, enabled: bool) {
+        self.0.set_skim_mode(enabled);
+    }
+    /// Drop skippable elements whenever the speaking rate is at least `rate`, None to disable
+    #[pyo3(text_signature = "($self, rate: float | None)")]
+    pub fn set_skim_rate(&self, rate: Option<f64>) {
+        self.0.set_skim_rate(rate);
+    }
+    /// Pass the samples of synthesized speech through `transform` before playing them, None to stop.
+    /// It is called with `(pcm: bytes, sample_rate: int, channels: int, bits_per_sample: int)` and
+    /// returns the processed samples as bytes. It runs on a thread of its own, if it fails
+    /// or takes more than 2 seconds the unprocessed speech is played.
+    #[pyo3(text_signature = "($self, transform: Callable[[bytes, int, int, int], bytes] | None)")]
+    pub fn set_audio_transform(&self, transform: Option<PyObject>) {
+        self.0.set_audio_transform(
+            transform.map(|t| Arc::new(PyAudioTransform::new(t)) as Arc<dyn AudioTransform>),
+        );
+    }
+    /// Play an audio file whenever the voice is switched automatically, e.g. by echo mode,
+    /// so the change isn't mistaken for a glitch. Pass None to disable the cue.
+    #[pyo3(text_signature = "($self, audio_path: str | None)")]
+    pub fn set_voice_change_cue(&self, audio_path: Option<String>) -> NeosynthResult<()> {
+        if let Some(path) = &audio_path {
+            std::fs::metadata(path)
+                .map_err(|e| NeosynthError::Io(format!("Failed to open audio file {}", path), e))?;
+        }
+        self.0.set_voice_change_cue(audio_path);
+        Ok(())
+    }
+    /// Synthesize an utterance ahead of time without speaking it.
+    /// Returns a new utterance which starts speaking instantly when passed to `speak()`.
+    #[pyo3(
+        text_signature = "($self, utterance: neosynth.SpeechUtterance) -> neosynth.SpeechUtterance"
+    )]
+    pub fn prefetch(
+        &self,
+        py: Python<'_>,
+        utterance: SpeechUtterance,
+    ) -> NeosynthResult<SpeechUtterance> {
+        let elements = py.allow_threads(|| self.0.prefetch(utterance.0))?;
+        Ok(SpeechUtterance(elements))
+    }
+    /// Synthesize up to `count` upcoming elements in the background while the current one
+    /// plays, removing the pause between sentences. Streams synthesized before a setting
+    /// was changed are discarded. 0, the default, disables it.
+    #[pyo3(text_signature = "($self, count: int)")]
+    pub fn set_prefetch_count(&self, count: usize) {
+        self.0.set_prefetch_count(count);
+    }
     /// Pause the speech
     #[pyo3(text_signature = "($self)")]
     pub fn pause(&self) -> NeosynthResult<()> {
-        self.0.set_state(SynthState::Paused)?;
-        self.0.player.pause()?;
-        Ok(())
+        self.0.pause(StateChangeReason::UserPause)
+    }
+    /// Move to the given position in milliseconds within the element being played,
+    /// e.g. a long audio file or synthesized chapter. Returns False if nothing is
+    /// playing or the element can't seek.
+    #[pyo3(text_signature = "($self, position_ms: int) -> bool")]
+    pub fn seek(&self, position_ms: u64) -> NeosynthResult<bool> {
+        self.0.seek(Duration::from_millis(position_ms))
+    }
+    /// Position in milliseconds within the element being played, 0 when nothing is
+    #[pyo3(text_signature = "($self) -> int")]
+    pub fn get_position(&self) -> NeosynthResult<u64> {
+        if self.0.get_state()? == SynthState::Ready {
+            return Ok(0);
+        }
+        Ok(self.0.player.position_and_duration()?.0.as_millis() as u64)
+    }
+    /// Duration in milliseconds of the element being played, 0 when nothing is
+    #[pyo3(text_signature = "($self) -> int")]
+    pub fn get_duration(&self) -> NeosynthResult<u64> {
+        if self.0.get_state()? == SynthState::Ready {
+            return Ok(0);
+        }
+        Ok(self.0.player.position_and_duration()?.1.as_millis() as u64)
+    }
+    /// Go back the given number of seconds within the element being played, stopping at
+    /// its start. Returns False if nothing is playing or the element can't seek.
+    #[pyo3(text_signature = "($self, seconds: float) -> bool")]
+    pub fn rewind(&self, seconds: f64) -> PyResult<bool> {
+        let delta = Duration::try_from_secs_f64(seconds)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(self.0.seek_by(delta, false)?)
+    }
+    /// Skip the given number of seconds within the element being played, stopping at
+    /// its end. Returns False if nothing is playing or the element can't seek.
+    #[pyo3(text_signature = "($self, seconds: float) -> bool")]
+    pub fn fast_forward(&self, seconds: f64) -> PyResult<bool> {
+        let delta = Duration::try_from_secs_f64(seconds)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(self.0.seek_by(delta, true)?)
     }
     /// Resume the speech
     #[pyo3(text_signature = "($self)")]
     pub fn resume(&self) -> NeosynthResult<()> {
-        self.0.set_state(SynthState::Busy)?;
-        self.0.player.resume()?;
-        Ok(())
+        self.0.resume()
     }
     /// Stop the speech
     #[pyo3(text_signature = "($self)")]
     pub fn stop(&self) -> NeosynthResult<()> {
-        self.0.player.stop()?;
-        self.0.clear_speech_queue()?;
-        self.0.process_queue()?;
+        self.0.stop_speech()
+    }
+    /// Stop once the sentence being spoken is finished rather than in the middle of a
+    /// word. Audio and speech without sentence boundaries are played to the end of the
+    /// current element. Speaking again cancels the pending stop.
+    #[pyo3(text_signature = "($self)")]
+    pub fn stop_at_sentence_end(&self) -> NeosynthResult<()> {
+        SpeechMixer::stop_at_sentence_end(&self.0)
+    }
+    /// Cancel the elements waiting to be spoken but let the one being spoken finish,
+    /// the utterance then ends as usual
+    #[pyo3(text_signature = "($self)")]
+    pub fn clear_queue(&self) -> NeosynthResult<()> {
+        self.0.clear_pending()
+    }
+    /// List the elements waiting to be spoken, not including the one being spoken.
+    /// Elements pushed to a speech feed are listed once pulled ahead of playback.
+    #[pyo3(text_signature = "($self) -> list[neosynth.QueuedElement]")]
+    pub fn get_queue(&self) -> Vec<QueuedElement> {
+        self.0.pending_elements()
+    }
+    /// Get the number of elements waiting to be spoken, as listed by `get_queue`
+    #[pyo3(text_signature = "($self) -> int")]
+    pub fn queue_length(&self) -> usize {
+        self.0.pending_count()
+    }
+    /// Skip the element being spoken and go on with the next one in the queue
+    #[pyo3(text_signature = "($self)")]
+    pub fn next(&self) -> NeosynthResult<()> {
+        self.0.skip_element()
+    }
+    /// Skip to the bookmark with the given name further in the utterance and go on speaking
+    /// from there, e.g. to go to a section. The bookmark is reported when speech gets there.
+    /// Returns False if the utterance has no such bookmark ahead.
+    #[pyo3(text_signature = "($self, name: str) -> bool")]
+    pub fn skip_to_bookmark(&self, name: &str) -> NeosynthResult<bool> {
+        self.0.skip_to_bookmark(name)
+    }
+    /// Go back to the text or audio element before the one being spoken and go on from
+    /// there, or speak the last element again once speech is done
+    #[pyo3(text_signature = "($self)")]
+    pub fn previous(&self) -> NeosynthResult<()> {
+        self.0.previous_element()
+    }
+    /// Get the maximum number of characters synthesized at once, 0 means no limit
+    #[pyo3(text_signature = "($self) -> int")]
+    pub fn get_max_text_length(&self) -> usize {
+        self.0.get_max_text_length()
+    }
+    /// Set the maximum number of characters synthesized at once, longer text is split into several parts
+    #[pyo3(text_signature = "($self, max_length: int)")]
+    pub fn set_max_text_length(&self, max_length: usize) {
+        self.0.set_max_text_length(max_length)
+    }
+    /// Pause the speech after speaking for the given number of seconds or characters,
+    /// and call the event sink's `on_reading_limit_reached`
+    #[pyo3(text_signature = "($self, seconds: float = None, characters: int = None)")]
+    #[args(seconds = "None", characters = "None")]
+    pub fn set_reading_limit(
+        &self,
+        seconds: Option<f64>,
+        characters: Option<usize>,
+    ) -> PyResult<()> {
+        let limit = match (seconds, characters) {
+            (Some(seconds), None) if seconds >= 0.0 => {
+                ReadingLimit::Time(Duration::from_secs_f64(seconds))
+            }
+            (None, Some(characters)) => ReadingLimit::Characters(characters),
+            _ => {
+                return Err(PyValueError::new_err(
+                    "Exactly one of seconds or characters must be given",
+                ))
+            }
+        };
+        self.0.set_reading_limit(Some(limit));
+        Ok(())
+    }
+    /// Remove the reading limit
+    #[pyo3(text_signature = "($self)")]
+    pub fn clear_reading_limit(&self) {
+        self.0.set_reading_limit(None);
+    }
+    /// Call `on_bookmark_reached` after every given number of seconds of played audio, None to stop.
+    /// The bookmarks are named `auto:<element index>:<seconds into the element>`.
+    #[pyo3(text_signature = "($self, seconds: float | None)")]
+    pub fn set_auto_bookmark_interval(&self, seconds: Option<f64>) -> PyResult<()> {
+        let interval = match seconds {
+            Some(seconds) => Some(
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?,
+            ),
+            None => None,
+        };
+        self.0.set_auto_bookmark_interval(interval);
+        Ok(())
+    }
+    /// Pause for the given number of milliseconds between consecutive audible elements,
+    /// e.g. between paragraphs, up to a minute. 0 disables the pause.
+    #[pyo3(text_signature = "($self, ms: int)")]
+    pub fn set_inter_element_silence(&self, ms: u64) -> PyResult<()> {
+        let silence = Duration::from_millis(ms);
+        if silence > MAX_INTER_ELEMENT_SILENCE {
+            return Err(PyValueError::new_err(format!(
+                "The silence between elements can't exceed {} ms",
+                MAX_INTER_ELEMENT_SILENCE.as_millis()
+            )));
+        }
+        self.0.set_inter_element_silence(Some(silence));
+        Ok(())
+    }
+    /// Only speak priority utterances normally between the `start` and `end` times of day,
+    /// given as `HH:MM` in local time, e.g. `22:00` and `07:00`. In `defer` mode other
+    /// utterances are kept and spoken once quiet hours end, in `reduce` mode they are
+    /// spoken at a reduced volume. None disables quiet hours.
+    #[pyo3(text_signature = "($self, start: str | None, end: str | None, mode: str = \"defer\")")]
+    #[args(mode = "\"defer\"")]
+    pub fn set_quiet_hours(
+        &self,
+        start: Option<&str>,
+        end: Option<&str>,
+        mode: &str,
+    ) -> NeosynthResult<()> {
+        let quiet_hours = match (start, end) {
+            (Some(start), Some(end)) => Some(QuietHours::new(start, end, mode.parse()?)?),
+            (None, None) => None,
+            _ => {
+                return Err(OperationError(
+                    "Quiet hours need both a start and an end".to_string(),
+                ))
+            }
+        };
+        self.0.set_quiet_hours(quiet_hours);
+        Ok(())
+    }
+    /// Only speak the first of identical utterances requested within the given number of
+    /// seconds, e.g. repeated announcements of a chatty application. None speaks them all.
+    #[pyo3(text_signature = "($self, seconds: float | None)")]
+    pub fn set_dedup_window(&self, seconds: Option<f64>) -> PyResult<()> {
+        let window = match seconds {
+            Some(seconds) => Some(
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?,
+            ),
+            None => None,
+        };
+        self.0.set_dedup_window(window);
+        Ok(())
+    }
+    /// Choose what happens to utterances requested while paused: `resume` speaks them right
+    /// away, `queue` keeps the last one paused and speaks it in place of the paused
+    /// utterance on resume, `warn` does the same and calls `on_speech_while_paused`, and
+    /// `raise` makes `speak` raise an error
+    #[pyo3(text_signature = "($self, mode: str)")]
+    pub fn set_paused_speech_mode(&self, mode: &str) -> NeosynthResult<()> {
+        self.0.set_paused_speech_mode(mode.parse()?);
+        Ok(())
+    }
+    /// Speak each utterance `times` more times or, if `forever` is true, over and over
+    /// until stopped. Applies from the next utterance, `set_repeat()` turns it off.
+    #[pyo3(text_signature = "($self, times: int = 0, forever: bool = False)")]
+    #[args(times = "0", forever = "false")]
+    pub fn set_repeat(&self, times: u32, forever: bool) {
+        self.0.set_repeat(if forever {
+            Repeat::Forever
+        } else {
+            Repeat::Times(times)
+        });
+    }
+    /// Take turns speaking with the other processes using neosynth with the same focus
+    /// `name`: while one of them speaks, the utterances of the others are deferred until
+    /// it is done. Paused speech keeps the focus until stopped. Priority utterances speak
+    /// regardless. None speaks without regard for other processes.
+    #[pyo3(text_signature = "($self, name: str | None)")]
+    pub fn set_audio_focus(&self, name: Option<&str>) -> NeosynthResult<()> {
+        self.0.set_audio_focus(name)
+    }
+    /// Keep at most `count` utterances deferred during quiet hours, None for any number.
+    /// Beyond that `speak` raises `QueueFullError` with the `raise` policy, or waits for
+    /// a deferred utterance to be spoken or dropped with the `block` policy.
+    #[pyo3(text_signature = "($self, count: int | None, policy: str = \"raise\")")]
+    #[args(policy = "\"raise\"")]
+    pub fn set_max_deferred(&self, count: Option<usize>, policy: &str) -> NeosynthResult<()> {
+        let policy = policy.parse()?;
+        self.0.set_max_deferred(count.map(|count| (count, policy)));
+        Ok(())
+    }
+    /// Stop playback after the given number of minutes, fading the volume out over the
+    /// last 30 seconds if `fade` is true, then call `on_sleep_timer_elapsed`. None cancels
+    /// the sleep timer.
+    #[pyo3(text_signature = "($self, minutes: float | None, fade: bool = True)")]
+    #[args(fade = "true")]
+    pub fn set_sleep_timer(&self, minutes: Option<f64>, fade: bool) -> PyResult<()> {
+        let duration = match minutes {
+            Some(minutes) => Some(
+                Duration::try_from_secs_f64(minutes * 60.0)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?,
+            ),
+            None => None,
+        };
+        self.0.set_sleep_timer(duration, fade)?;
+        Ok(())
+    }
+    /// Call `on_silence_detected` when speech being played stays silent for the given
+    /// number of seconds, None to stop detecting silence
+    #[pyo3(text_signature = "($self, seconds: float | None)")]
+    pub fn set_silence_threshold(&self, seconds: Option<f64>) -> PyResult<()> {
+        let threshold = match seconds {
+            Some(seconds) => Some(
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?,
+            ),
+            None => None,
+        };
+        self.0.set_silence_threshold(threshold);
+        Ok(())
+    }
+    /// Call `on_progress` with the position and duration of the element being played
+    /// every given number of seconds of played audio, None to stop
+    #[pyo3(text_signature = "($self, seconds: float | None)")]
+    pub fn set_progress_interval(&self, seconds: Option<f64>) -> PyResult<()> {
+        let interval = match seconds {
+            Some(seconds) => Some(
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?,
+            ),
+            None => None,
+        };
+        self.0.set_progress_interval(interval);
+        Ok(())
+    }
+    /// Play an element again every given number of seconds while another application
+    /// holds the audio device exclusively, None to skip the element
+    #[pyo3(text_signature = "($self, seconds: float | None)")]
+    pub fn set_device_retry_interval(&self, seconds: Option<f64>) -> PyResult<()> {
+        let interval = match seconds {
+            Some(seconds) => Some(
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?,
+            ),
+            None => None,
+        };
+        self.0.set_device_retry_interval(interval);
+        Ok(())
+    }
+    /// Get the current settings as a dict accepted by `apply_settings`
+    #[pyo3(text_signature = "($self) -> dict")]
+    pub fn get_settings(&self) -> NeosynthResult<EngineConfig> {
+        self.current_config()
+    }
+    /// Apply several settings at once (voice, rate, pitch, volume, max_text_length, log_level,
+    /// line_joining and bidi_cleanup). The settings are validated first, and if applying any
+    /// of them fails the previous settings are restored. There is no device setting, speech
+    /// always plays on the default audio device. The event sink's `on_settings_changed` is called once on success.
+    #[pyo3(text_signature = "($self, settings: dict)")]
+    pub fn apply_settings(&self, settings: &PyDict) -> PyResult<()> {
+        let config = Self::config_from_dict(settings)?;
+        config.validate()?;
+        let previous = self.current_config()?;
+        if let Err(e) = self.apply_config(&config) {
+            if let Err(e) = self.apply_config(&previous) {
+                self.0
+                    .report_internal_error("Restoring the previous settings failed", &e);
+            }
+            return Err(e.into());
+        }
+        self.0
+            .player
+            .notify(|sink| sink.on_settings_changed(&config));
+        Ok(())
+    }
+    /// Only send messages at or above the given level (debug, info, warning, error) to the event sink
+    #[pyo3(text_signature = "($self, level: str)")]
+    pub fn set_log_level(&self, level: &str) -> NeosynthResult<()> {
+        self.0.set_log_level(level.parse()?);
+        Ok(())
+    }
+    /// Apply the settings from a configuration file, followed by any `NEOSYNTH_*` environment variables.
+    /// The file holds flat TOML `key = value` lines, without tables.
+    #[pyo3(text_signature = "($self, path: str)")]
+    pub fn load_config(&self, path: String) -> NeosynthResult<()> {
+        self.apply_config(&EngineConfig::load(Some(std::path::Path::new(&path)))?)
+    }
+    /// Stop calling the event sink, or a listener, after the given number of its callbacks
+    /// failed in a row, 0 means never. A RuntimeWarning is issued when one is suspended.
+    #[pyo3(text_signature = "($self, limit: int)")]
+    pub fn set_event_sink_failure_limit(&self, limit: usize) {
+        self.0.player.1.set_failure_limit(limit);
+    }
+    /// Indicates if the event sink was suspended because its callbacks kept failing
+    #[pyo3(text_signature = "($self) -> bool")]
+    pub fn is_event_sink_suspended(&self) -> bool {
+        self.0.player.1.is_suspended()
+    }
+    /// Deliver events to the event sink and the listeners which were suspended again
+    #[pyo3(text_signature = "($self)")]
+    pub fn resume_event_sink(&self) {
+        self.0.player.1.resume();
+    }
+    /// Issue a RuntimeWarning for event sink callbacks running longer than `seconds`, None to disable.
+    /// If `cancel` is true, such callbacks are interrupted by raising TimeoutError in them.
+    #[pyo3(text_signature = "($self, seconds: float | None, cancel: bool = False)")]
+    #[args(cancel = "false")]
+    pub fn set_callback_budget(&self, seconds: Option<f64>, cancel: bool) -> PyResult<()> {
+        let budget = match seconds {
+            Some(seconds) => Some(
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?,
+            ),
+            None => None,
+        };
+        self.0.player.1.set_callback_budget(budget, cancel);
         Ok(())
     }
+    /// Get the number of utterances dropped as duplicates of a recent one
+    #[pyo3(text_signature = "($self) -> int")]
+    pub fn get_duplicate_count(&self) -> usize {
+        self.0.duplicate_count()
+    }
+    /// Get the number of event sink callbacks which exceeded the callback budget
+    #[pyo3(text_signature = "($self) -> int")]
+    pub fn get_slow_callback_count(&self) -> usize {
+        self.0.player.1.slow_callback_count()
+    }
+    /// Get the time in seconds into the current element's audio at which the word at
+    /// `offset` is spoken, None without word boundaries
+    #[pyo3(text_signature = "($self, offset: int) -> float | None")]
+    pub fn audio_time_for_offset(&self, offset: usize) -> Option<f64> {
+        self.0.audio_time_for_offset(offset)
+    }
+    /// Get the offset and length in the current element's text of the word spoken
+    /// `seconds` into its audio, None without word boundaries
+    #[pyo3(text_signature = "($self, seconds: float) -> tuple[int, int] | None")]
+    pub fn offset_for_audio_time(&self, seconds: f64) -> Option<(usize, usize)> {
+        self.0.offset_for_audio_time(seconds)
+    }
+    /// Convert an offset in UTF-16 code units, as reported by word and sentence events,
+    /// to an index into the Python string `text`, None if it doesn't fall on a character
+    #[staticmethod]
+    #[pyo3(text_signature = "(text: str, offset: int) -> int | None")]
+    pub fn utf16_to_char_offset(text: &str, offset: usize) -> Option<usize> {
+        text::utf16_to_char_offset(text, offset)
+    }
+    /// Convert an index into the Python string `text` to an offset in UTF-16 code units,
+    /// as used by Windows text APIs, None if it is past the end of the text
+    #[staticmethod]
+    #[pyo3(text_signature = "(text: str, offset: int) -> int | None")]
+    pub fn char_to_utf16_offset(text: &str, offset: usize) -> Option<usize> {
+        text::char_to_utf16_offset(text, offset)
+    }
+    /// Send every event to `listener` as well, like the event sink it may implement any subset of the handlers
+    #[pyo3(text_signature = "($self, listener: object)")]
+    pub fn add_listener(&self, listener: PyObject) {
+        self.0.player.1.add_listener(listener);
+    }
+    /// Stop sending events to a listener added with `add_listener` or to the event sink
+    #[pyo3(text_signature = "($self, listener: object)")]
+    pub fn remove_listener(&self, py: Python<'_>, listener: &PyAny) -> PyResult<()> {
+        if self.0.player.1.remove_listener(py, listener) {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err("The object is not a listener"))
+        }
+    }
+    /// Stop the speech and release the event sink.
+    /// Once this returns, no further event sink callbacks will be invoked.
+    #[pyo3(text_signature = "($self)")]
+    pub fn close(&self, py: Python<'_>) -> NeosynthResult<()> {
+        // Release the GIL so that in-flight callbacks can finish
+        let closed = py.allow_threads(|| self.0.close());
+        // The other channels are heard again without waiting for the instance to be dropped
+        self.release_solo().and(closed)
+    }
+    /// Enable or disable recording the timing of each stage of the speech pipeline
+    #[pyo3(text_signature = "($self, enabled: bool)")]
+    pub fn set_profiling(&self, enabled: bool) {
+        self.0.profiler.set_enabled(enabled);
+    }
+    /// Get a summary of the timings recorded while profiling
+    #[pyo3(text_signature = "($self) -> str")]
+    pub fn get_profile_report(&self) -> String {
+        self.0.profiler.report()
+    }
+    /// Write the timings recorded while profiling to a file in the folded stack format used by flamegraph tools
+    #[pyo3(text_signature = "($self, path: str)")]
+    pub fn export_profile_trace(&self, path: String) -> NeosynthResult<()> {
+        std::fs::write(path, self.0.profiler.folded_stacks())
+            .map_err(|e| NeosynthError::Io("Failed to write profile trace".to_string(), e))
+    }
 }
 
 /// A wrapper around Windows OneCoreSynthesizer
 #[pymodule]
-fn neosynth(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+fn neosynth(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("QueueFullError", py.get_type::<QueueFullError>())?;
+    m.add(
+        "VoiceUnavailableError",
+        py.get_type::<VoiceUnavailableError>(),
+    )?;
     m.add_class::<Neosynth>()?;
     m.add_class::<SynthState>()?;
+    m.add_class::<StateChangeReason>()?;
     m.add_class::<SpeechUtterance>()?;
+    m.add_class::<DocumentBuilder>()?;
+    m.add_class::<QueuedElement>()?;
+    m.add_class::<UtteranceHandle>()?;
     m.add_class::<VoiceInfo>()?;
+    m.add_class::<VoiceHealth>()?;
+    m.add_class::<SpeechProfile>()?;
+    m.add_function(wrap_pyfunction!(lang::normalize_language_tag, m)?)?;
+    m.add_function(wrap_pyfunction!(lang::primary_language, m)?)?;
+    m.add_function(wrap_pyfunction!(lang::language_matches, m)?)?;
     Ok(())
 }
@@ -0,0 +1,81 @@
+use crate::config::EngineConfig;
+use crate::{NeosynthResult, VoiceInfo};
+use pyo3::prelude::*;
+use windows::Media::SpeechSynthesis::VoiceInformation;
+
+/// A voice and prosody settings applied together, either as the engine's settings or
+/// to the elements that follow it in an utterance. Settings left as None are unchanged.
+#[pyclass(
+    frozen,
+    text_signature = "(voice: neosynth.VoiceInfo = None, rate: float = None, pitch: float = None, volume: float = None)"
+)]
+#[derive(Clone, Default)]
+pub struct SpeechProfile {
+    pub voice: Option<VoiceInformation>,
+    #[pyo3(get)]
+    pub rate: Option<f64>,
+    #[pyo3(get)]
+    pub pitch: Option<f64>,
+    #[pyo3(get)]
+    pub volume: Option<f64>,
+}
+
+impl SpeechProfile {
+    /// Check prosody values as `EngineConfig::validate` does
+    pub fn validate(&self) -> NeosynthResult<()> {
+        EngineConfig {
+            rate: self.rate,
+            pitch: self.pitch,
+            volume: self.volume,
+            ..Default::default()
+        }
+        .validate()
+    }
+}
+
+#[pymethods]
+impl SpeechProfile {
+    #[new]
+    #[args(voice = "None", rate = "None", pitch = "None", volume = "None")]
+    pub fn new(
+        voice: Option<&VoiceInfo>,
+        rate: Option<f64>,
+        pitch: Option<f64>,
+        volume: Option<f64>,
+    ) -> NeosynthResult<Self> {
+        let profile = Self {
+            voice: voice.map(VoiceInformation::from),
+            rate,
+            pitch,
+            volume,
+        };
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    #[getter]
+    pub fn voice(&self) -> Option<VoiceInfo> {
+        self.voice.clone().map(VoiceInfo::from)
+    }
+
+    /// Combine two profiles, settings present in `other` take precedence
+    #[pyo3(text_signature = "($self, other: neosynth.SpeechProfile) -> neosynth.SpeechProfile")]
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            voice: other.voice.clone().or_else(|| self.voice.clone()),
+            rate: other.rate.or(self.rate),
+            pitch: other.pitch.or(self.pitch),
+            volume: other.volume.or(self.volume),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SpeechProfile(voice={:?}, rate={:?}, pitch={:?}, volume={:?})",
+            self.voice().map(|v| v.name),
+            self.rate,
+            self.pitch,
+            self.volume
+        )
+    }
+}
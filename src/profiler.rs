@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A stage of the speech pipeline whose timing is recorded by the `Profiler`
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Stage {
+    /// From `speak()` until the first element starts being processed
+    QueueWait,
+    /// Generating the speech stream for a text or SSML element
+    Synthesis,
+    /// Handing a stream or file over to the media player
+    SourceSet,
+    /// From asking the player to play until it reports it is playing
+    FirstAudio,
+    /// Handing events over to the event sink, Python sinks run their callbacks on another thread
+    Callback,
+}
+
+impl Stage {
+    const ALL: [Stage; 5] = [
+        Stage::QueueWait,
+        Stage::Synthesis,
+        Stage::SourceSet,
+        Stage::FirstAudio,
+        Stage::Callback,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::QueueWait => "queue_wait",
+            Stage::Synthesis => "synthesis",
+            Stage::SourceSet => "source_set",
+            Stage::FirstAudio => "first_audio",
+            Stage::Callback => "callback",
+        }
+    }
+}
+
+/// Records how long each stage of the speech pipeline takes, when enabled
+#[derive(Default)]
+pub struct Profiler {
+    enabled: AtomicBool,
+    records: Mutex<Vec<(Stage, Duration)>>,
+    /// Stages which started in one place and finish in another, e.g. in an event handler
+    marks: Mutex<HashMap<Stage, Instant>>,
+}
+
+impl Profiler {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable profiling, enabling it discards previous records
+    pub fn set_enabled(&self, enabled: bool) {
+        if enabled {
+            self.records.lock().unwrap().clear();
+            self.marks.lock().unwrap().clear();
+        }
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn record(&self, stage: Stage, duration: Duration) {
+        if self.is_enabled() {
+            self.records.lock().unwrap().push((stage, duration));
+        }
+    }
+
+    /// Run `func` and record how long it took
+    pub fn time<R>(&self, stage: Stage, func: impl FnOnce() -> R) -> R {
+        if !self.is_enabled() {
+            return func();
+        }
+        let start = Instant::now();
+        let result = func();
+        self.record(stage, start.elapsed());
+        result
+    }
+
+    /// Mark the start of a stage that is finished later by `finish`
+    pub fn start(&self, stage: Stage) {
+        if self.is_enabled() {
+            self.marks.lock().unwrap().insert(stage, Instant::now());
+        }
+    }
+
+    /// Record the time elapsed since the stage was started, if it was
+    pub fn finish(&self, stage: Stage) {
+        if let Some(start) = self.marks.lock().unwrap().remove(&stage) {
+            self.record(stage, start.elapsed());
+        }
+    }
+
+    fn totals(&self) -> Vec<(Stage, usize, Duration, Duration)> {
+        let records = self.records.lock().unwrap();
+        Stage::ALL
+            .iter()
+            .map(|stage| {
+                let durations = records.iter().filter(|(s, _)| s == stage).map(|(_, d)| *d);
+                let count = durations.clone().count();
+                let total = durations.clone().sum();
+                let max = durations.max().unwrap_or_default();
+                (*stage, count, total, max)
+            })
+            .collect()
+    }
+
+    /// A human readable summary of the recorded timings
+    pub fn report(&self) -> String {
+        let mut report = format!(
+            "{:<12} {:>8} {:>12} {:>10} {:>10}\n",
+            "stage", "count", "total ms", "mean ms", "max ms"
+        );
+        for (stage, count, total, max) in self.totals() {
+            let mean = if count > 0 {
+                total / count as u32
+            } else {
+                Duration::ZERO
+            };
+            writeln!(
+                report,
+                "{:<12} {:>8} {:>12.2} {:>10.2} {:>10.2}",
+                stage.name(),
+                count,
+                total.as_secs_f64() * 1000.0,
+                mean.as_secs_f64() * 1000.0,
+                max.as_secs_f64() * 1000.0
+            )
+            .unwrap();
+        }
+        report
+    }
+
+    /// The recorded timings in the folded stack format understood by flamegraph tools.
+    /// Each line is a stack followed by the total time in microseconds.
+    pub fn folded_stacks(&self) -> String {
+        self.totals()
+            .into_iter()
+            .filter(|(_, count, _, _)| *count > 0)
+            .map(|(stage, _, total, _)| {
+                format!("neosynth;{} {}\n", stage.name(), total.as_micros())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{NeosynthResult, OperationError};
+use std::str::FromStr;
+use windows::Globalization::{Calendar, ClockIdentifiers};
+
+/// Factor applied to the volume during quiet hours in `Reduce` mode
+pub const QUIET_GAIN: f64 = 0.3;
+
+/// What happens to speech which isn't a priority during quiet hours
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum QuietMode {
+    /// Keep it until quiet hours end
+    Defer,
+    /// Speak it at a reduced volume
+    Reduce,
+}
+
+impl FromStr for QuietMode {
+    type Err = crate::NeosynthError;
+
+    fn from_str(mode: &str) -> NeosynthResult<Self> {
+        match mode.to_ascii_lowercase().as_str() {
+            "defer" => Ok(QuietMode::Defer),
+            "reduce" => Ok(QuietMode::Reduce),
+            _ => Err(OperationError(format!(
+                "Invalid quiet hours mode: {}",
+                mode
+            ))),
+        }
+    }
+}
+
+/// What `defer` does when the maximum number of deferred utterances is reached
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum QueueFullPolicy {
+    /// Fail with `NeosynthError::QueueFull`
+    Raise,
+    /// Wait until a deferred utterance is spoken or dropped
+    Block,
+}
+
+impl FromStr for QueueFullPolicy {
+    type Err = crate::NeosynthError;
+
+    fn from_str(policy: &str) -> NeosynthResult<Self> {
+        match policy.to_ascii_lowercase().as_str() {
+            "raise" => Ok(QueueFullPolicy::Raise),
+            "block" => Ok(QueueFullPolicy::Block),
+            _ => Err(OperationError(format!(
+                "Invalid queue full policy: {}",
+                policy
+            ))),
+        }
+    }
+}
+
+/// A daily window of local time during which only priority speech is played normally
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct QuietHours {
+    /// Start of the window, in minutes since midnight
+    pub start: u32,
+    /// End of the window, in minutes since midnight. It is the next day if before `start`.
+    pub end: u32,
+    pub mode: QuietMode,
+}
+
+impl QuietHours {
+    /// Build quiet hours from `HH:MM` times of day, e.g. `22:00` and `07:30`
+    pub fn new(start: &str, end: &str, mode: QuietMode) -> NeosynthResult<Self> {
+        Ok(Self {
+            start: parse_time_of_day(start)?,
+            end: parse_time_of_day(end)?,
+            mode,
+        })
+    }
+
+    /// Whether the given minute of the day falls within quiet hours
+    pub fn contains(&self, minute: u32) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    /// Whether quiet hours are in effect right now
+    pub fn is_active(&self) -> NeosynthResult<bool> {
+        Ok(self.contains(local_minute_of_day()?))
+    }
+}
+
+fn parse_time_of_day(time: &str) -> NeosynthResult<u32> {
+    let invalid = || OperationError(format!("Invalid time of day: {}", time));
+    let (hours, minutes) = time.trim().split_once(':').ok_or_else(invalid)?;
+    let hours: u32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+    if hours >= 24 || minutes >= 60 {
+        return Err(invalid());
+    }
+    Ok(hours * 60 + minutes)
+}
+
+/// Minutes since midnight in the user's time zone
+fn local_minute_of_day() -> NeosynthResult<u32> {
+    let calendar = Calendar::new()?;
+    calendar.ChangeClock(&ClockIdentifiers::TwentyFourHour()?)?;
+    Ok((calendar.Hour()? * 60 + calendar.Minute()?) as u32)
+}
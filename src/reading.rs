@@ -0,0 +1,522 @@
+use crate::lang::normalize_language_tag;
+
+/// Which reading rules rewrite text before it is synthesized
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct ReadingRules {
+    /// Read Roman numerals as numbers, e.g. `Chapter XIV` and `Henry VIII`
+    pub roman_numerals: bool,
+    /// Read abbreviated ordinals as words, e.g. `21st`
+    pub ordinals: bool,
+    /// Read number ranges and page references, e.g. `pp. 10–12`
+    pub ranges: bool,
+}
+
+impl ReadingRules {
+    pub fn is_empty(&self) -> bool {
+        !self.roman_numerals && !self.ordinals && !self.ranges
+    }
+}
+
+/// The words used by the reading rules for one language
+struct RuleTable {
+    language: &'static str,
+    /// Words after which a Roman numeral is a number, e.g. `chapter`
+    numbering_words: &'static [&'static str],
+    /// Numbering words which also take letters, after which a single letter is left as is,
+    /// e.g. `Appendix C`
+    lettering_words: &'static [&'static str],
+    /// Page abbreviations and their reading, the plural ones first
+    pages: &'static [(&'static str, &'static str)],
+    /// Word read between the bounds of a range
+    range_word: &'static str,
+    /// Reading of an ordinal number, None if the language has no ordinal words
+    ordinal: Option<fn(u64) -> String>,
+    /// Suffixes marking an abbreviated ordinal, e.g. `st` in `21st`
+    ordinal_suffixes: &'static [&'static str],
+    /// Article read before the regnal number of a ruler, e.g. `the` in `Henry the eighth`
+    regnal_article: Option<&'static str>,
+    /// Names borne by numbered rulers, e.g. `Henry`
+    ruler_names: &'static [&'static str],
+    /// Titles before the name of a ruler, which make any capitalized name a ruler's
+    ruler_titles: &'static [&'static str],
+}
+
+const RULE_TABLES: &[RuleTable] = &[
+    RuleTable {
+        language: "en",
+        numbering_words: &[
+            "chapter",
+            "part",
+            "volume",
+            "vol.",
+            "book",
+            "act",
+            "scene",
+            "section",
+            "appendix",
+            "article",
+            "canto",
+            "psalm",
+            "world war",
+        ],
+        lettering_words: &["part", "section", "appendix", "article"],
+        pages: &[("pp.", "pages"), ("p.", "page")],
+        range_word: "to",
+        ordinal: Some(english_ordinal),
+        ordinal_suffixes: &["st", "nd", "rd", "th"],
+        regnal_article: Some("the"),
+        // Not Malcolm, which is more often Malcolm X than a king of Scots
+        ruler_names: &[
+            "Alexander",
+            "Alfonso",
+            "Amenhotep",
+            "Anne",
+            "Benedict",
+            "Boniface",
+            "Catherine",
+            "Charles",
+            "Christian",
+            "Clement",
+            "Constantine",
+            "Edward",
+            "Elizabeth",
+            "Ferdinand",
+            "Francis",
+            "Frederick",
+            "George",
+            "Gregory",
+            "Gustav",
+            "Haakon",
+            "Harald",
+            "Henry",
+            "Innocent",
+            "Ivan",
+            "James",
+            "John",
+            "Leo",
+            "Leopold",
+            "Louis",
+            "Ludwig",
+            "Mary",
+            "Napoleon",
+            "Nicholas",
+            "Olaf",
+            "Otto",
+            "Paul",
+            "Peter",
+            "Philip",
+            "Pius",
+            "Ptolemy",
+            "Ramesses",
+            "Richard",
+            "Robert",
+            "Rudolf",
+            "Sixtus",
+            "Stephen",
+            "Urban",
+            "Victor",
+            "Wilhelm",
+            "William",
+        ],
+        ruler_titles: &[
+            "king", "queen", "pope", "emperor", "empress", "tsar", "czar", "sultan", "pharaoh",
+            "prince", "princess", "duke", "duchess",
+        ],
+    },
+    RuleTable {
+        language: "fr",
+        numbering_words: &[
+            "chapitre", "partie", "tome", "livre", "acte", "scène", "section", "annexe", "article",
+            "siècle",
+        ],
+        lettering_words: &["partie", "section", "annexe", "article"],
+        pages: &[("pp.", "pages"), ("p.", "page")],
+        range_word: "à",
+        ordinal: None,
+        ordinal_suffixes: &[],
+        regnal_article: None,
+        ruler_names: &[],
+        ruler_titles: &[],
+    },
+    RuleTable {
+        language: "de",
+        numbering_words: &[
+            "kapitel",
+            "teil",
+            "band",
+            "buch",
+            "akt",
+            "szene",
+            "abschnitt",
+            "anhang",
+            "artikel",
+        ],
+        lettering_words: &["teil", "abschnitt", "anhang", "artikel"],
+        pages: &[("S.", "Seite")],
+        range_word: "bis",
+        ordinal: None,
+        ordinal_suffixes: &[],
+        regnal_article: None,
+        ruler_names: &[],
+        ruler_titles: &[],
+    },
+    RuleTable {
+        language: "es",
+        numbering_words: &[
+            "capítulo",
+            "parte",
+            "tomo",
+            "libro",
+            "acto",
+            "escena",
+            "sección",
+            "apéndice",
+            "artículo",
+            "siglo",
+        ],
+        lettering_words: &["parte", "sección", "apéndice", "artículo"],
+        pages: &[("págs.", "páginas"), ("pág.", "página")],
+        range_word: "a",
+        ordinal: None,
+        ordinal_suffixes: &[],
+        regnal_article: None,
+        ruler_names: &[],
+        ruler_titles: &[],
+    },
+];
+
+/// Valid Roman numerals which are more likely to be words or acronyms
+pub(crate) const ROMAN_LOOKALIKES: &[&str] = &[
+    "CD", "DC", "MD", "CV", "CC", "MM", "MIX", "MID", "DIM", "LID", "MC", "XL", "XXL", "CLI",
+    "CIV", "DIV", "LIV", "MDX", "MMX", "MCL", "XXX",
+];
+
+/// Rewrite text according to the rules and the language of the voice. Languages without
+/// a rule table are left alone, as are the rules a table has no words for.
+pub fn apply_reading_rules(text: &str, rules: ReadingRules, language: &str) -> String {
+    let language = normalize_language_tag(language);
+    let primary = language.split('-').next().unwrap_or_default();
+    let Some(table) = RULE_TABLES.iter().find(|t| t.language == primary) else {
+        return text.to_string();
+    };
+    let tokens: Vec<&str> = text.split_inclusive(char::is_whitespace).collect();
+    let mut result = String::with_capacity(text.len());
+    for (idx, token) in tokens.iter().enumerate() {
+        let word = token.trim_end();
+        let spacing = &token[word.len()..];
+        let previous = idx.checked_sub(1).map(|i| tokens[i].trim_end());
+        let next = tokens.get(idx + 1).map(|t| t.trim_end());
+        let rewritten = rules
+            .ranges
+            .then(|| read_range(word, previous, next, table))
+            .flatten()
+            .or_else(|| rules.ordinals.then(|| read_ordinal(word, table)).flatten())
+            .or_else(|| {
+                rules
+                    .roman_numerals
+                    .then(|| read_roman_numeral(word, &tokens[..idx], table))
+                    .flatten()
+            });
+        result.push_str(rewritten.as_deref().unwrap_or(word));
+        result.push_str(spacing);
+    }
+    result
+}
+
+/// Split a word into its leading punctuation, its core and its trailing punctuation
+fn split_punctuation(word: &str) -> (&str, &str, &str) {
+    let core_start = word
+        .find(|c: char| c.is_alphanumeric())
+        .unwrap_or(word.len());
+    let core_end = word
+        .rfind(|c: char| c.is_alphanumeric())
+        .map_or(core_start, |idx| {
+            idx + word[idx..].chars().next().unwrap().len_utf8()
+        });
+    (
+        &word[..core_start],
+        &word[core_start..core_end],
+        &word[core_end..],
+    )
+}
+
+/// `10–12` becomes `10 to 12`, and a page abbreviation before a number is spelled out
+fn read_range(
+    word: &str,
+    previous: Option<&str>,
+    next: Option<&str>,
+    table: &RuleTable,
+) -> Option<String> {
+    if let Some((_, reading)) = table
+        .pages
+        .iter()
+        .find(|(abbreviation, _)| *abbreviation == word)
+    {
+        let before_number = next.is_some_and(|next| next.starts_with(|c: char| c.is_ascii_digit()));
+        return before_number.then(|| reading.to_string());
+    }
+    let (lead, core, trail) = split_punctuation(word);
+    let after_pages = previous.is_some_and(|p| table.pages.iter().any(|(a, _)| *a == p));
+    // Hyphens also join dates and phone numbers, they only mark a range after a page abbreviation
+    let separators: &[char] = if after_pages { &['–', '-'] } else { &['–'] };
+    let (start, end) = core.split_once(separators)?;
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    (is_number(start) && is_number(end))
+        .then(|| format!("{}{} {} {}{}", lead, start, table.range_word, end, trail))
+}
+
+/// `21st` becomes `twenty-first`
+fn read_ordinal(word: &str, table: &RuleTable) -> Option<String> {
+    let ordinal = table.ordinal?;
+    let (lead, core, trail) = split_punctuation(word);
+    let digits_end = core.find(|c: char| !c.is_ascii_digit())?;
+    let (digits, suffix) = core.split_at(digits_end);
+    if digits.is_empty()
+        || !table
+            .ordinal_suffixes
+            .contains(&suffix.to_ascii_lowercase().as_str())
+    {
+        return None;
+    }
+    let number: u64 = digits.parse().ok()?;
+    Some(format!("{}{}{}", lead, ordinal(number), trail))
+}
+
+/// `Chapter XIV` becomes `Chapter 14`, `Henry VIII` becomes `Henry the eighth` where the
+/// language has ordinal words, and other numerals of three letters or more become numbers
+fn read_roman_numeral(word: &str, before: &[&str], table: &RuleTable) -> Option<String> {
+    let (lead, core, trail) = split_punctuation(word);
+    let number = roman_value(core)?;
+    let previous = before.last().map(|p| p.trim_end().to_lowercase());
+    if core.len() == 1
+        && previous
+            .as_deref()
+            .is_some_and(|p| table.lettering_words.contains(&p))
+    {
+        return None;
+    }
+    let previous_two = before
+        .len()
+        .checked_sub(2)
+        .map(|i| format!("{} {}", before[i].trim_end(), before[i + 1].trim_end()).to_lowercase());
+    let after_numbering_word = [previous.as_deref(), previous_two.as_deref()]
+        .into_iter()
+        .flatten()
+        .any(|p| table.numbering_words.contains(&p));
+    if after_numbering_word {
+        return Some(format!("{}{}{}", lead, number, trail));
+    }
+    // A ruler is known by name or by the title before it, as in `Generation X` there is no
+    // ruler to speak of. Rulers rarely have numbers beyond the thirties, and without a title
+    // a lone I is much more likely to be the pronoun.
+    let name = before.last().map(|p| p.trim_end()).filter(|p| {
+        p.chars().next().is_some_and(char::is_uppercase) && p.chars().all(char::is_alphabetic)
+    });
+    let after_title = before.len() >= 2
+        && table
+            .ruler_titles
+            .contains(&before[before.len() - 2].trim_end().to_lowercase().as_str());
+    let after_ruler =
+        name.is_some_and(|name| after_title || (core != "I" && table.ruler_names.contains(&name)));
+    if let (true, Some(article), Some(ordinal)) = (
+        after_ruler && number < 40,
+        table.regnal_article,
+        table.ordinal,
+    ) {
+        return Some(format!("{}{} {}{}", lead, article, ordinal(number), trail));
+    }
+    (core.len() >= 3 && !ROMAN_LOOKALIKES.contains(&core))
+        .then(|| format!("{}{}{}", lead, number, trail))
+}
+
+/// Roman numeral symbols, including the subtractive pairs, from the largest
+const ROMAN_SYMBOLS: [(&str, u64); 13] = [
+    ("M", 1000),
+    ("CM", 900),
+    ("D", 500),
+    ("CD", 400),
+    ("C", 100),
+    ("XC", 90),
+    ("L", 50),
+    ("XL", 40),
+    ("X", 10),
+    ("IX", 9),
+    ("V", 5),
+    ("IV", 4),
+    ("I", 1),
+];
+
+/// The value of an uppercase Roman numeral in canonical form, None for anything else
+pub(crate) fn roman_value(numeral: &str) -> Option<u64> {
+    if numeral.is_empty() || !numeral.chars().all(|c| "MDCLXVI".contains(c)) {
+        return None;
+    }
+    let mut rest = numeral;
+    let mut value = 0;
+    for (symbol, symbol_value) in ROMAN_SYMBOLS {
+        while rest.starts_with(symbol) {
+            rest = &rest[symbol.len()..];
+            value += symbol_value;
+        }
+    }
+    // Only canonical numerals count, e.g. not IIII or VX
+    (rest.is_empty() && value < 4000 && to_roman(value) == numeral).then_some(value)
+}
+
+fn to_roman(mut value: u64) -> String {
+    let mut numeral = String::new();
+    for (symbol, symbol_value) in ROMAN_SYMBOLS {
+        while value >= symbol_value {
+            numeral.push_str(symbol);
+            value -= symbol_value;
+        }
+    }
+    numeral
+}
+
+const ENGLISH_ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const ENGLISH_TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// English cardinal number, e.g. `one hundred twenty-one`
+fn english_cardinal(number: u64) -> String {
+    match number {
+        0..=19 => ENGLISH_ONES[number as usize].to_string(),
+        20..=99 => match number % 10 {
+            0 => ENGLISH_TENS[(number / 10) as usize].to_string(),
+            ones => format!(
+                "{}-{}",
+                ENGLISH_TENS[(number / 10) as usize],
+                ENGLISH_ONES[ones as usize]
+            ),
+        },
+        _ => {
+            let (unit, name) = [
+                (1_000_000_000, "billion"),
+                (1_000_000, "million"),
+                (1000, "thousand"),
+                (100, "hundred"),
+            ]
+            .into_iter()
+            .find(|(unit, _)| number >= *unit)
+            .unwrap();
+            match number % unit {
+                0 => format!("{} {}", english_cardinal(number / unit), name),
+                rest => format!(
+                    "{} {} {}",
+                    english_cardinal(number / unit),
+                    name,
+                    english_cardinal(rest)
+                ),
+            }
+        }
+    }
+}
+
+/// English ordinal number, e.g. `twenty-first`
+fn english_ordinal(number: u64) -> String {
+    let cardinal = english_cardinal(number);
+    // Only the last word takes the ordinal form
+    let split_at = cardinal.rfind([' ', '-']).map_or(0, |idx| idx + 1);
+    let (head, last) = cardinal.split_at(split_at);
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        word if word.ends_with('y') => format!("{}ieth", &word[..word.len() - 1]),
+        word => format!("{}th", word),
+    };
+    format!("{}{}", head, last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: ReadingRules = ReadingRules {
+        roman_numerals: true,
+        ordinals: true,
+        ranges: true,
+    };
+
+    fn read(text: &str) -> String {
+        apply_reading_rules(text, ALL, "en-US")
+    }
+
+    #[test]
+    fn roman_numerals_after_numbering_words() {
+        assert_eq!(read("Chapter XIV."), "Chapter 14.");
+        assert_eq!(read("World War II"), "World War 2");
+        assert_eq!(read("See Appendix C"), "See Appendix C");
+    }
+
+    #[test]
+    fn roman_numerals_of_rulers() {
+        assert_eq!(read("Henry VIII"), "Henry the eighth");
+        assert_eq!(read("King Arthur II"), "King Arthur the second");
+        assert_eq!(read("Generation X"), "Generation X");
+        assert_eq!(read("Then I left"), "Then I left");
+    }
+
+    #[test]
+    fn roman_lookalikes_are_left_alone() {
+        for text in [
+            "Run the CLI",
+            "Add a DIV",
+            "A CIV game",
+            "Burn a CD",
+            "XXL shirts",
+        ] {
+            assert_eq!(read(text), text);
+        }
+        assert_eq!(read("Founded in MCMXCIX"), "Founded in 1999");
+    }
+
+    #[test]
+    fn roman_values() {
+        assert_eq!(roman_value("MCMXCIX"), Some(1999));
+        assert_eq!(roman_value("IIII"), None);
+        assert_eq!(roman_value("VX"), None);
+        assert_eq!(roman_value("xiv"), None);
+    }
+
+    #[test]
+    fn ordinals_and_ranges() {
+        assert_eq!(read("the 21st century"), "the twenty-first century");
+        assert_eq!(read("see pp. 10–12"), "see pages 10 to 12");
+    }
+
+    #[test]
+    fn languages_without_a_table_are_left_alone() {
+        assert_eq!(
+            apply_reading_rules("Chapter XIV", ALL, "ja-JP"),
+            "Chapter XIV"
+        );
+    }
+}
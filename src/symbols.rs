@@ -0,0 +1,503 @@
+use crate::lang::primary_language;
+use crate::{NeosynthResult, OperationError};
+use std::str::FromStr;
+
+/// How emoji and pictographic symbols are spoken, OneCore voices skip or garble them
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SymbolMode {
+    /// Remove them
+    Ignore,
+    /// Replace them with their name, e.g. `red heart`
+    Name,
+    /// Replace them with their name followed by their category, e.g. `red heart (emotion)`
+    NameWithCategory,
+}
+
+impl FromStr for SymbolMode {
+    type Err = crate::NeosynthError;
+
+    fn from_str(mode: &str) -> NeosynthResult<Self> {
+        match mode.to_ascii_lowercase().replace(['_', '-'], " ").as_str() {
+            "ignore" => Ok(SymbolMode::Ignore),
+            "name" => Ok(SymbolMode::Name),
+            "name with category" => Ok(SymbolMode::NameWithCategory),
+            _ => Err(OperationError(format!("Invalid symbol mode: {}", mode))),
+        }
+    }
+}
+
+/// English CLDR short names and simplified categories of common emoji and symbols
+const SYMBOL_NAMES: &[(char, &str, &str)] = &[
+    ('😀', "grinning face", "smiley"),
+    ('😁', "beaming face with smiling eyes", "smiley"),
+    ('😂', "face with tears of joy", "smiley"),
+    ('🤣', "rolling on the floor laughing", "smiley"),
+    ('😃', "grinning face with big eyes", "smiley"),
+    ('😄', "grinning face with smiling eyes", "smiley"),
+    ('😅', "grinning face with sweat", "smiley"),
+    ('😆', "grinning squinting face", "smiley"),
+    ('😉', "winking face", "smiley"),
+    ('😊', "smiling face with smiling eyes", "smiley"),
+    ('😋', "face savoring food", "smiley"),
+    ('😎', "smiling face with sunglasses", "smiley"),
+    ('😍', "smiling face with heart-eyes", "smiley"),
+    ('😘', "face blowing a kiss", "smiley"),
+    ('🙂', "slightly smiling face", "smiley"),
+    ('🤗', "smiling face with open hands", "smiley"),
+    ('🤔', "thinking face", "smiley"),
+    ('😐', "neutral face", "smiley"),
+    ('😑', "expressionless face", "smiley"),
+    ('🙄', "face with rolling eyes", "smiley"),
+    ('😏', "smirking face", "smiley"),
+    ('😮', "face with open mouth", "smiley"),
+    ('😴', "sleeping face", "smiley"),
+    ('😛', "face with tongue", "smiley"),
+    ('😜', "winking face with tongue", "smiley"),
+    ('😒', "unamused face", "smiley"),
+    ('😔', "pensive face", "smiley"),
+    ('😕', "confused face", "smiley"),
+    ('🙃', "upside-down face", "smiley"),
+    ('😲', "astonished face", "smiley"),
+    ('🙁', "slightly frowning face", "smiley"),
+    ('😞', "disappointed face", "smiley"),
+    ('😢', "crying face", "smiley"),
+    ('😭', "loudly crying face", "smiley"),
+    ('😱', "face screaming in fear", "smiley"),
+    ('😡', "enraged face", "smiley"),
+    ('😠', "angry face", "smiley"),
+    ('😷', "face with medical mask", "smiley"),
+    ('🤒', "face with thermometer", "smiley"),
+    ('🥳', "partying face", "smiley"),
+    ('🥺', "pleading face", "smiley"),
+    ('😇', "smiling face with halo", "smiley"),
+    ('🤯', "exploding head", "smiley"),
+    ('💀', "skull", "smiley"),
+    ('💩', "pile of poo", "smiley"),
+    ('❤', "red heart", "emotion"),
+    ('💔', "broken heart", "emotion"),
+    ('💕', "two hearts", "emotion"),
+    ('💖', "sparkling heart", "emotion"),
+    ('💙', "blue heart", "emotion"),
+    ('💚', "green heart", "emotion"),
+    ('💛', "yellow heart", "emotion"),
+    ('💜', "purple heart", "emotion"),
+    ('🖤', "black heart", "emotion"),
+    ('💯', "hundred points", "emotion"),
+    ('💥', "collision", "emotion"),
+    ('💤', "zzz", "emotion"),
+    ('👋', "waving hand", "gesture"),
+    ('👌', "OK hand", "gesture"),
+    ('✌', "victory hand", "gesture"),
+    ('🤞', "crossed fingers", "gesture"),
+    ('👍', "thumbs up", "gesture"),
+    ('👎', "thumbs down", "gesture"),
+    ('👏', "clapping hands", "gesture"),
+    ('🙌', "raising hands", "gesture"),
+    ('🙏', "folded hands", "gesture"),
+    ('💪', "flexed biceps", "gesture"),
+    ('👉', "backhand index pointing right", "gesture"),
+    ('👈', "backhand index pointing left", "gesture"),
+    ('👆', "backhand index pointing up", "gesture"),
+    ('👇', "backhand index pointing down", "gesture"),
+    ('👀', "eyes", "person"),
+    ('👶', "baby", "person"),
+    ('👦', "boy", "person"),
+    ('👧', "girl", "person"),
+    ('👨', "man", "person"),
+    ('👩', "woman", "person"),
+    ('👪', "family", "person"),
+    ('🐶', "dog face", "animal"),
+    ('🐱', "cat face", "animal"),
+    ('🐭', "mouse face", "animal"),
+    ('🐻', "bear", "animal"),
+    ('🐼', "panda", "animal"),
+    ('🐵', "monkey face", "animal"),
+    ('🐔', "chicken", "animal"),
+    ('🐦', "bird", "animal"),
+    ('🐍', "snake", "animal"),
+    ('🐢', "turtle", "animal"),
+    ('🐟', "fish", "animal"),
+    ('🦋', "butterfly", "animal"),
+    ('🐝', "honeybee", "animal"),
+    ('🌸', "cherry blossom", "nature"),
+    ('🌹', "rose", "nature"),
+    ('🌻', "sunflower", "nature"),
+    ('🌲', "evergreen tree", "nature"),
+    ('🍀', "four leaf clover", "nature"),
+    ('🔥', "fire", "nature"),
+    ('🌈', "rainbow", "nature"),
+    ('☀', "sun", "weather"),
+    ('🌙', "crescent moon", "weather"),
+    ('⭐', "star", "weather"),
+    ('🌟', "glowing star", "weather"),
+    ('☁', "cloud", "weather"),
+    ('⚡', "high voltage", "weather"),
+    ('❄', "snowflake", "weather"),
+    ('☔', "umbrella with rain drops", "weather"),
+    ('🍎', "red apple", "food"),
+    ('🍌', "banana", "food"),
+    ('🍕', "pizza", "food"),
+    ('🍔', "hamburger", "food"),
+    ('🍰', "shortcake", "food"),
+    ('🎂', "birthday cake", "food"),
+    ('☕', "hot beverage", "food"),
+    ('🍺', "beer mug", "food"),
+    ('🍷', "wine glass", "food"),
+    ('🏠', "house", "place"),
+    ('🚗', "automobile", "travel"),
+    ('✈', "airplane", "travel"),
+    ('🚀', "rocket", "travel"),
+    ('⏰', "alarm clock", "time"),
+    ('⌛', "hourglass done", "time"),
+    ('🎉', "party popper", "activity"),
+    ('🎁', "wrapped gift", "activity"),
+    ('🏆', "trophy", "activity"),
+    ('⚽', "soccer ball", "activity"),
+    ('🎵', "musical note", "activity"),
+    ('🎶', "musical notes", "activity"),
+    ('📱', "mobile phone", "object"),
+    ('💻', "laptop", "object"),
+    ('📧', "e-mail", "object"),
+    ('📅', "calendar", "object"),
+    ('📌', "pushpin", "object"),
+    ('📎', "paperclip", "object"),
+    ('🔒', "locked", "object"),
+    ('🔑', "key", "object"),
+    ('💡', "light bulb", "object"),
+    ('📝', "memo", "object"),
+    ('📚', "books", "object"),
+    ('💰', "money bag", "object"),
+    ('🔔', "bell", "object"),
+    ('✅', "check mark button", "symbol"),
+    ('✔', "check mark", "symbol"),
+    ('❌', "cross mark", "symbol"),
+    ('❗', "red exclamation mark", "symbol"),
+    ('❓', "red question mark", "symbol"),
+    ('⚠', "warning", "symbol"),
+    ('⛔', "no entry", "symbol"),
+    ('🚫', "prohibited", "symbol"),
+    ('♻', "recycling symbol", "symbol"),
+    ('✨', "sparkles", "symbol"),
+    ('➡', "right arrow", "symbol"),
+    ('⬅', "left arrow", "symbol"),
+    ('⬆', "up arrow", "symbol"),
+    ('⬇', "down arrow", "symbol"),
+    ('🔴', "red circle", "symbol"),
+    ('🟢', "green circle", "symbol"),
+    ('🔵', "blue circle", "symbol"),
+    ('©', "copyright", "symbol"),
+    ('®', "registered", "symbol"),
+    ('™', "trade mark", "symbol"),
+];
+
+/// The CLDR names of symbols and the words for their categories in a language other
+/// than English
+struct SymbolTable {
+    language: &'static str,
+    /// Read before the region of a flag, e.g. `flag` in `flag US`
+    flag: &'static str,
+    /// The words for the categories of `SYMBOL_NAMES`, and for `emoji` and `symbol`
+    categories: &'static [(&'static str, &'static str)],
+    names: &'static [(char, &'static str)],
+}
+
+const SYMBOL_TABLES: &[SymbolTable] = &[
+    SymbolTable {
+        language: "fr",
+        flag: "drapeau",
+        categories: &[
+            ("smiley", "smiley"),
+            ("emotion", "émotion"),
+            ("gesture", "geste"),
+            ("person", "personne"),
+            ("animal", "animal"),
+            ("nature", "nature"),
+            ("weather", "météo"),
+            ("food", "nourriture"),
+            ("place", "lieu"),
+            ("travel", "voyage"),
+            ("time", "temps"),
+            ("activity", "activité"),
+            ("object", "objet"),
+            ("symbol", "symbole"),
+            ("emoji", "emoji"),
+        ],
+        names: &[
+            ('😀', "visage rieur"),
+            ('😂', "visage riant aux larmes"),
+            ('😉', "visage faisant un clin d’œil"),
+            ('😊', "visage souriant avec yeux rieurs"),
+            ('😍', "visage souriant avec yeux en forme de cœur"),
+            ('🙂', "visage avec un léger sourire"),
+            ('🤔', "visage en pleine réflexion"),
+            ('😢', "visage qui pleure"),
+            ('😭', "visage qui pleure à chaudes larmes"),
+            ('❤', "cœur rouge"),
+            ('💔', "cœur brisé"),
+            ('👋', "main qui fait coucou"),
+            ('👍', "pouce vers le haut"),
+            ('👎', "pouce vers le bas"),
+            ('👏', "applaudissements"),
+            ('🙏', "mains en prière"),
+            ('🐶', "tête de chien"),
+            ('🐱', "tête de chat"),
+            ('🔥', "feu"),
+            ('🌈', "arc-en-ciel"),
+            ('☀', "soleil"),
+            ('⭐', "étoile"),
+            ('🍕', "pizza"),
+            ('🎂', "gâteau d’anniversaire"),
+            ('☕', "boisson chaude"),
+            ('🚀', "fusée"),
+            ('🎉', "cotillons"),
+            ('💡', "ampoule"),
+            ('📧', "e-mail"),
+            ('❌', "croix"),
+            ('⚠', "avertissement"),
+            ('➡', "flèche droite"),
+            ('⬅', "flèche gauche"),
+            ('⬆', "flèche haut"),
+            ('⬇', "flèche bas"),
+        ],
+    },
+    SymbolTable {
+        language: "de",
+        flag: "Flagge",
+        categories: &[
+            ("smiley", "Smiley"),
+            ("emotion", "Gefühl"),
+            ("gesture", "Geste"),
+            ("person", "Person"),
+            ("animal", "Tier"),
+            ("nature", "Natur"),
+            ("weather", "Wetter"),
+            ("food", "Essen"),
+            ("place", "Ort"),
+            ("travel", "Reisen"),
+            ("time", "Zeit"),
+            ("activity", "Aktivität"),
+            ("object", "Objekt"),
+            ("symbol", "Symbol"),
+            ("emoji", "Emoji"),
+        ],
+        names: &[
+            ('😀', "grinsendes Gesicht"),
+            ('😂', "Gesicht mit Freudentränen"),
+            ('😉', "zwinkerndes Gesicht"),
+            ('😊', "lächelndes Gesicht mit lachenden Augen"),
+            ('😍', "lächelndes Gesicht mit herzförmigen Augen"),
+            ('🙂', "leicht lächelndes Gesicht"),
+            ('🤔', "nachdenkendes Gesicht"),
+            ('😢', "weinendes Gesicht"),
+            ('❤', "rotes Herz"),
+            ('💔', "gebrochenes Herz"),
+            ('👋', "winkende Hand"),
+            ('👍', "Daumen hoch"),
+            ('👎', "Daumen runter"),
+            ('👏', "klatschende Hände"),
+            ('🐶', "Hundegesicht"),
+            ('🐱', "Katzengesicht"),
+            ('🔥', "Feuer"),
+            ('🌈', "Regenbogen"),
+            ('☀', "Sonne"),
+            ('⭐', "Stern"),
+            ('🍕', "Pizza"),
+            ('🎂', "Geburtstagskuchen"),
+            ('☕', "Heißgetränk"),
+            ('🚀', "Rakete"),
+            ('🎉', "Konfettibombe"),
+            ('💡', "Glühbirne"),
+            ('⚠', "Warnung"),
+            ('➡', "Pfeil nach rechts"),
+            ('⬅', "Pfeil nach links"),
+            ('⬆', "Pfeil nach oben"),
+            ('⬇', "Pfeil nach unten"),
+        ],
+    },
+    SymbolTable {
+        language: "es",
+        flag: "bandera",
+        categories: &[
+            ("smiley", "emoticono"),
+            ("emotion", "emoción"),
+            ("gesture", "gesto"),
+            ("person", "persona"),
+            ("animal", "animal"),
+            ("nature", "naturaleza"),
+            ("weather", "clima"),
+            ("food", "comida"),
+            ("place", "lugar"),
+            ("travel", "viaje"),
+            ("time", "hora"),
+            ("activity", "actividad"),
+            ("object", "objeto"),
+            ("symbol", "símbolo"),
+            ("emoji", "emoji"),
+        ],
+        names: &[
+            ('😀', "cara sonriendo"),
+            ('😂', "cara llorando de risa"),
+            ('😉', "cara guiñando el ojo"),
+            ('😍', "cara sonriendo con ojos de corazón"),
+            ('🙂', "cara sonriendo ligeramente"),
+            ('🤔', "cara pensativa"),
+            ('😢', "cara llorando"),
+            ('😭', "cara llorando fuerte"),
+            ('❤', "corazón rojo"),
+            ('💔', "corazón roto"),
+            ('👋', "mano saludando"),
+            ('👍', "pulgar hacia arriba"),
+            ('👎', "pulgar hacia abajo"),
+            ('👏', "manos aplaudiendo"),
+            ('🐶', "cara de perro"),
+            ('🐱', "cara de gato"),
+            ('🔥', "fuego"),
+            ('🌈', "arcoíris"),
+            ('☀', "sol"),
+            ('⭐', "estrella"),
+            ('🍕', "pizza"),
+            ('🎂', "tarta de cumpleaños"),
+            ('☕', "bebida caliente"),
+            ('🚀', "cohete"),
+            ('🎉', "cañón de confeti"),
+            ('💡', "bombilla"),
+            ('⚠', "advertencia"),
+            ('➡', "flecha hacia la derecha"),
+            ('⬅', "flecha hacia la izquierda"),
+            ('⬆', "flecha hacia arriba"),
+            ('⬇', "flecha hacia abajo"),
+        ],
+    },
+];
+
+/// Whether a character is an emoji or a pictographic symbol
+fn is_symbol(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x2300..=0x23FF
+    ) || SYMBOL_NAMES.iter().any(|(symbol, _, _)| *symbol == c)
+}
+
+/// Whether a character only modifies the emoji before it: variation selectors,
+/// skin tone modifiers, tags and the keycap. The zero width joiner isn't one, it also
+/// forms the conjuncts of Indic scripts.
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(
+        c as u32,
+        0xFE0E..=0xFE0F | 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F | 0x20E3
+    )
+}
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// The letter of a regional indicator symbol, two of which form a flag
+fn regional_indicator(c: char) -> Option<char> {
+    let offset = (c as u32)
+        .checked_sub(0x1F1E6)
+        .filter(|offset| *offset < 26)?;
+    char::from_u32('A' as u32 + offset)
+}
+
+/// Remove emoji and pictographic symbols from `text` or replace them with their names in
+/// the language of the voice. Symbols the language has no name for are read with their
+/// English name. The replacements are surrounded by spaces, so offsets into the result
+/// don't match the text.
+pub fn verbalize_symbols(text: &str, mode: SymbolMode, language: &str) -> String {
+    let primary = primary_language(language);
+    let table = SYMBOL_TABLES.iter().find(|t| t.language == primary);
+    let category_word = |category: &'static str| {
+        table
+            .and_then(|t| {
+                t.categories
+                    .iter()
+                    .find(|(english, _)| *english == category)
+            })
+            .map_or(category, |(_, word)| *word)
+    };
+    let mut verbalized = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    // Whether the last character was an emoji, which a joiner may join to the next one
+    let mut after_symbol = false;
+    while let Some(c) = chars.next() {
+        let (name, category) = if let Some(first) = regional_indicator(c) {
+            let second = chars.next_if(|c| regional_indicator(*c).is_some());
+            let region: String = [Some(first), second.and_then(regional_indicator)]
+                .into_iter()
+                .flatten()
+                .collect();
+            let flag = table.map_or("flag", |t| t.flag);
+            (format!("{} {}", flag, region), flag)
+        } else if is_emoji_modifier(c) {
+            continue;
+        } else if c == ZERO_WIDTH_JOINER
+            && after_symbol
+            && chars.peek().is_some_and(|next| is_symbol(*next))
+        {
+            // Each emoji of a sequence such as a family is named
+            continue;
+        } else if is_symbol(c) {
+            let localized = table.and_then(|t| t.names.iter().find(|(symbol, _)| *symbol == c));
+            match SYMBOL_NAMES.iter().find(|(symbol, _, _)| *symbol == c) {
+                Some((_, name, category)) => (
+                    localized.map_or(*name, |(_, name)| *name).to_string(),
+                    category_word(category),
+                ),
+                None if (c as u32) >= 0x1F000 => {
+                    (category_word("emoji").to_string(), category_word("emoji"))
+                }
+                None => (category_word("symbol").to_string(), category_word("symbol")),
+            }
+        } else {
+            after_symbol = false;
+            verbalized.push(c);
+            continue;
+        };
+        after_symbol = true;
+        match mode {
+            SymbolMode::Ignore => verbalized.push(' '),
+            SymbolMode::Name => verbalized.push_str(&format!(" {} ", name)),
+            // Don't say "flag US (flag)"
+            SymbolMode::NameWithCategory if name.starts_with(category) => {
+                verbalized.push_str(&format!(" {} ", name))
+            }
+            SymbolMode::NameWithCategory => {
+                verbalized.push_str(&format!(" {} ({}) ", name, category))
+            }
+        }
+    }
+    verbalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbalize_symbols_keeps_joiners_of_conjuncts() {
+        // Malayalam chillu and Devanagari half forms are written with a joiner
+        for text in [
+            "\u{0D28}\u{0D4D}\u{200D}",
+            "\u{0915}\u{094D}\u{200D}\u{0937}",
+        ] {
+            assert_eq!(verbalize_symbols(text, SymbolMode::Name, "hi-IN"), text);
+        }
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(
+            verbalize_symbols(family, SymbolMode::Name, "en-US"),
+            " man  woman  girl "
+        );
+    }
+
+    #[test]
+    fn verbalize_symbols_in_the_language_of_the_voice() {
+        let text = "\u{1F44D}\u{1F1EB}\u{1F1F7}\u{1F355}\u{1F41D}";
+        assert_eq!(
+            verbalize_symbols(text, SymbolMode::NameWithCategory, "fr-CA"),
+            " pouce vers le haut (geste)  drapeau FR  pizza (nourriture)  honeybee (animal) "
+        );
+        assert_eq!(
+            verbalize_symbols("\u{1F44D}", SymbolMode::Name, "ja-JP"),
+            " thumbs up "
+        );
+    }
+}
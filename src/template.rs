@@ -0,0 +1,196 @@
+use crate::code::CodeBlock;
+use crate::text::escape_xml;
+use crate::{NeosynthResult, OperationError, SpeechElement, TextRole};
+use std::collections::HashMap;
+
+/// A piece of a text with placeholders
+#[derive(Clone)]
+enum Segment {
+    Literal(String),
+    Slot(String),
+}
+
+/// Text with `{name}` placeholders, braces are doubled to be kept as is: `{{` and `}}`
+#[derive(Clone)]
+struct Pattern(Vec<Segment>);
+
+impl Pattern {
+    fn parse(text: &str) -> NeosynthResult<Self> {
+        let invalid = || OperationError(format!("Invalid template text: {}", text));
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => return Err(invalid()),
+                            Some(c) => name.push(c),
+                        }
+                    }
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Err(invalid());
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Slot(name.to_string()));
+                }
+                '}' => return Err(invalid()),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self(segments))
+    }
+
+    fn slots(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|segment| match segment {
+            Segment::Slot(name) => Some(name.as_str()),
+            Segment::Literal(_) => None,
+        })
+    }
+
+    /// Fill the placeholders with `values`, escaped for SSML if `escape` is true
+    fn render(&self, values: &HashMap<String, String>, escape: bool) -> NeosynthResult<String> {
+        let mut rendered = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Slot(name) => {
+                    let value = values.get(name).ok_or_else(|| {
+                        OperationError(format!("No value for template slot: {}", name))
+                    })?;
+                    if escape {
+                        rendered.push_str(&escape_xml(value));
+                    } else {
+                        rendered.push_str(value);
+                    }
+                }
+            }
+        }
+        Ok(rendered)
+    }
+}
+
+#[derive(Clone)]
+enum TemplateElement {
+    /// An element without placeholders
+    Fixed(SpeechElement),
+    Text(Pattern),
+    Ssml(Pattern),
+    Structured(TextRole, Pattern),
+    MarkedText(String, Pattern),
+    /// A code block whose code has placeholders
+    Code(CodeBlock, Pattern),
+    Skippable(Box<TemplateElement>),
+}
+
+impl TemplateElement {
+    fn parse(element: SpeechElement) -> NeosynthResult<Self> {
+        Ok(match element {
+            SpeechElement::Text(text) => TemplateElement::Text(Pattern::parse(&text)?),
+            SpeechElement::Ssml(ssml) => TemplateElement::Ssml(Pattern::parse(&ssml)?),
+            SpeechElement::Structured(role, text) => {
+                TemplateElement::Structured(role, Pattern::parse(&text)?)
+            }
+            SpeechElement::MarkedText(bookmark, text) => {
+                TemplateElement::MarkedText(bookmark, Pattern::parse(&text)?)
+            }
+            SpeechElement::Code(block) => {
+                let pattern = Pattern::parse(&block.code)?;
+                TemplateElement::Code(block, pattern)
+            }
+            SpeechElement::Skippable(element) => {
+                TemplateElement::Skippable(Box::new(Self::parse(*element)?))
+            }
+            element => TemplateElement::Fixed(element),
+        })
+    }
+
+    fn pattern(&self) -> Option<&Pattern> {
+        match self {
+            TemplateElement::Fixed(_) => None,
+            TemplateElement::Text(pattern)
+            | TemplateElement::Ssml(pattern)
+            | TemplateElement::Structured(_, pattern)
+            | TemplateElement::MarkedText(_, pattern)
+            | TemplateElement::Code(_, pattern) => Some(pattern),
+            TemplateElement::Skippable(element) => element.pattern(),
+        }
+    }
+
+    fn render(&self, values: &HashMap<String, String>) -> NeosynthResult<SpeechElement> {
+        Ok(match self {
+            TemplateElement::Fixed(element) => element.clone(),
+            TemplateElement::Text(pattern) => SpeechElement::Text(pattern.render(values, false)?),
+            TemplateElement::Ssml(pattern) => SpeechElement::Ssml(pattern.render(values, true)?),
+            TemplateElement::Structured(role, pattern) => {
+                SpeechElement::Structured(*role, pattern.render(values, false)?)
+            }
+            TemplateElement::MarkedText(bookmark, pattern) => {
+                SpeechElement::MarkedText(bookmark.clone(), pattern.render(values, false)?)
+            }
+            TemplateElement::Code(block, pattern) => SpeechElement::Code(CodeBlock {
+                code: pattern.render(values, false)?,
+                ..block.clone()
+            }),
+            TemplateElement::Skippable(element) => {
+                SpeechElement::Skippable(Box::new(element.render(values)?))
+            }
+        })
+    }
+}
+
+/// An utterance whose text, SSML, structured text and code elements have `{name}`
+/// placeholders, parsed once and filled in each time it is spoken
+#[derive(Clone)]
+pub struct UtteranceTemplate {
+    elements: Vec<TemplateElement>,
+}
+
+impl UtteranceTemplate {
+    pub fn new(elements: Vec<SpeechElement>) -> NeosynthResult<Self> {
+        let elements = elements
+            .into_iter()
+            .map(TemplateElement::parse)
+            .collect::<NeosynthResult<_>>()?;
+        Ok(Self { elements })
+    }
+
+    /// The names of the placeholders, in the order they first appear
+    pub fn slots(&self) -> Vec<String> {
+        let mut slots: Vec<String> = Vec::new();
+        for pattern in self.elements.iter().filter_map(TemplateElement::pattern) {
+            for slot in pattern.slots() {
+                if !slots.iter().any(|s| s == slot) {
+                    slots.push(slot.to_string());
+                }
+            }
+        }
+        slots
+    }
+
+    /// The elements of the utterance with the placeholders filled, every one of them
+    /// must have a value
+    pub fn render(&self, values: &HashMap<String, String>) -> NeosynthResult<Vec<SpeechElement>> {
+        self.elements
+            .iter()
+            .map(|element| element.render(values))
+            .collect()
+    }
+}
@@ -0,0 +1,963 @@
+/// Characters after which a sentence is considered finished
+const SENTENCE_TERMINATORS: &[char] = &['.', '!', '?', ';', '…', '。', '！', '？', '؟', '।'];
+/// Terminators of scripts written without spaces, which end a sentence without one
+const UNSPACED_TERMINATORS: &[char] = &['。', '！', '？'];
+
+/// Split text into chunks of at most `max_chars` characters (Unicode scalar values).
+///
+/// Chunks are broken at the last sentence end within the limit, then at the last
+/// whitespace, and only as a last resort in the middle of a word, though never
+/// inside a grapheme cluster such as an accented letter or an emoji sequence.
+pub fn split_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    if max_chars == 0 {
+        chunks.push(text.to_string());
+        return chunks;
+    }
+    let mut rest = text.trim();
+    while rest.chars().count() > max_chars {
+        let window_end = rest
+            .char_indices()
+            .nth(max_chars)
+            .map(|(idx, _)| idx)
+            .unwrap_or(rest.len());
+        let window = &rest[..window_end];
+        let sentence_end = window
+            .char_indices()
+            .rev()
+            .find(|(idx, c)| {
+                SENTENCE_TERMINATORS.contains(c)
+                    && (UNSPACED_TERMINATORS.contains(c)
+                        || rest[idx + c.len_utf8()..]
+                            .chars()
+                            .next()
+                            .is_none_or(char::is_whitespace))
+            })
+            .map(|(idx, c)| idx + c.len_utf8());
+        let split_at = sentence_end
+            .or_else(|| window.rfind(char::is_whitespace).filter(|idx| *idx > 0))
+            .unwrap_or_else(|| grapheme_boundary(rest, window_end));
+        let (chunk, remainder) = rest.split_at(split_at);
+        chunks.push(chunk.trim_end().to_string());
+        rest = remainder.trim_start();
+    }
+    if !rest.is_empty() {
+        chunks.push(rest.to_string());
+    }
+    chunks
+}
+
+/// Whether a character continues the grapheme cluster of the character before it.
+/// Approximates the Unicode grapheme extension rules with the combining marks of common
+/// scripts, joiners, variation selectors, emoji modifiers and tags.
+fn extends_grapheme(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0300..=0x036F // Combining diacritical marks
+            | 0x0483..=0x0489 // Cyrillic
+            | 0x0591..=0x05BD | 0x05BF | 0x05C1..=0x05C2 | 0x05C4..=0x05C5 | 0x05C7 // Hebrew
+            | 0x0610..=0x061A | 0x064B..=0x065F | 0x0670 | 0x06D6..=0x06DC | 0x06DF..=0x06E4
+            | 0x06E7..=0x06E8 | 0x06EA..=0x06ED // Arabic
+            | 0x0900..=0x0903 | 0x093A..=0x094F | 0x0951..=0x0957 | 0x0962..=0x0963 // Devanagari
+            | 0x0E31 | 0x0E34..=0x0E3A | 0x0E47..=0x0E4E // Thai
+            | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F
+            | 0x200C..=0x200D // Joiners
+            | 0xFE00..=0xFE0F | 0xE0100..=0xE01EF // Variation selectors
+            | 0x1F3FB..=0x1F3FF // Emoji skin tone modifiers
+            | 0xE0020..=0xE007F // Tags
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1F1E6..=0x1F1FF)
+}
+
+/// The last byte index not after `idx` at which `text` can be split without breaking
+/// a grapheme cluster, or `idx` itself if a single cluster spans everything before it
+fn grapheme_boundary(text: &str, idx: usize) -> usize {
+    let mut boundary = idx;
+    while boundary > 0 {
+        let before = text[..boundary].chars().next_back();
+        let after = text[boundary..].chars().next();
+        // Regional indicators pair up into flags, from the start of their run
+        let inside_flag = after.is_some_and(is_regional_indicator)
+            && text[..boundary]
+                .chars()
+                .rev()
+                .take_while(|c| is_regional_indicator(*c))
+                .count()
+                % 2
+                == 1;
+        // A zero width joiner binds the characters on both of its sides, as in emoji sequences
+        if !after.is_some_and(extends_grapheme) && before != Some('\u{200D}') && !inside_flag {
+            return boundary;
+        }
+        boundary -= before.map_or(0, char::len_utf8);
+    }
+    idx
+}
+
+/// Convert an offset in UTF-16 code units, the unit of the offsets reported by word and
+/// sentence events, to an offset in characters (Unicode scalar values) as used by Python.
+/// Returns None if the offset is past the end of the text or inside a surrogate pair.
+pub fn utf16_to_char_offset(text: &str, offset: usize) -> Option<usize> {
+    let mut units = 0;
+    for (idx, c) in text.chars().enumerate() {
+        if units >= offset {
+            return (units == offset).then_some(idx);
+        }
+        units += c.len_utf16();
+    }
+    (units == offset).then(|| text.chars().count())
+}
+
+/// Convert an offset in characters (Unicode scalar values) to one in UTF-16 code units,
+/// None if the offset is past the end of the text
+pub fn char_to_utf16_offset(text: &str, offset: usize) -> Option<usize> {
+    let mut chars = text.chars();
+    let units = chars.by_ref().take(offset).map(char::len_utf16).sum();
+    let taken = text.chars().count() - chars.count();
+    (taken == offset).then_some(units)
+}
+
+/// The first sentence of a text, or the whole text if it has a single sentence
+pub fn first_sentence(text: &str) -> &str {
+    let text = text.trim();
+    text.char_indices()
+        .find(|(idx, c)| {
+            SENTENCE_TERMINATORS.contains(c)
+                && text[idx + c.len_utf8()..]
+                    .chars()
+                    .next()
+                    .is_none_or(char::is_whitespace)
+        })
+        .map(|(idx, c)| &text[..idx + c.len_utf8()])
+        .unwrap_or(text)
+}
+
+/// Read a table row as a sentence, e.g. `Row 2, Price: 10 dollars`. Each cell is preceded
+/// by the header of its column if there is one, empty cells are read as `blank`.
+pub fn linearize_table_row(
+    number: usize,
+    cells: &[String],
+    headers: &[String],
+    cell_separator: &str,
+) -> String {
+    let cells = cells.iter().enumerate().map(|(idx, cell)| {
+        let cell = match cell.trim() {
+            "" => "blank",
+            cell => cell,
+        };
+        match headers.get(idx).map(|header| header.trim()) {
+            Some(header) if !header.is_empty() => format!("{}: {}", header, cell),
+            _ => cell.to_string(),
+        }
+    });
+    std::iter::once(format!("Row {}", number))
+        .chain(cells)
+        .collect::<Vec<_>>()
+        .join(cell_separator)
+}
+
+/// How the items of a list are introduced
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ListMarker {
+    None,
+    /// `Bullet`, for unordered lists
+    Bullet,
+    /// The number of the item, for ordered lists
+    Number(usize),
+}
+
+/// Read a list item preceded by its marker, e.g. `2. Preheat the oven.`. The item is
+/// ended as a sentence, so that the voice pauses before the next one.
+pub fn list_item_text(marker: ListMarker, item: &str) -> String {
+    let item = item.trim();
+    let mut text = match marker {
+        ListMarker::None => item.to_string(),
+        ListMarker::Bullet => format!("Bullet, {}", item),
+        ListMarker::Number(number) => format!("{}. {}", number, item),
+    };
+    if !text.ends_with(SENTENCE_TERMINATORS) {
+        text.push('.');
+    }
+    text
+}
+
+/// Rejoin the words and sentences broken across lines, as in text extracted from PDF
+/// documents: soft hyphens are removed, a word hyphenated at the end of a line is joined
+/// with its end on the next line and line breaks become spaces. Blank lines separating
+/// paragraphs are kept.
+pub fn join_broken_lines(text: &str) -> String {
+    let text = text.replace('\u{AD}', "");
+    let mut joined = String::with_capacity(text.len());
+    let mut lines = text
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .peekable();
+    let mut continued = false;
+    while let Some(line) = lines.next() {
+        let line = if continued { line.trim_start() } else { line };
+        let next = match lines.peek() {
+            Some(next) if !line.trim().is_empty() && !next.trim().is_empty() => next,
+            Some(_) => {
+                joined.push_str(line);
+                joined.push('\n');
+                continued = false;
+                continue;
+            }
+            None => {
+                joined.push_str(line);
+                break;
+            }
+        };
+        let line = line.trim_end();
+        let continues_word = next.trim_start().starts_with(char::is_lowercase);
+        match line.strip_suffix(['-', '\u{2010}']) {
+            Some(head) if continues_word && head.ends_with(char::is_alphabetic) => {
+                joined.push_str(head)
+            }
+            _ => {
+                joined.push_str(line);
+                joined.push(' ');
+            }
+        }
+        continued = true;
+    }
+    joined
+}
+
+/// Whether a character is an explicit directional formatting character: the
+/// directional marks, embeddings, overrides and isolates
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Whether a letter belongs to a right-to-left script, e.g. Hebrew, Arabic, Syriac or Thaana
+fn is_rtl_letter(c: char) -> bool {
+    c.is_alphabetic()
+        && matches!(
+            c as u32,
+            0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF
+        )
+}
+
+/// Prepare bidirectional text for the voices, which may pronounce directional formatting
+/// characters or pause at them. Those characters are removed, and a space is put wherever
+/// a right-to-left word directly meets a left-to-right one so they aren't read as one word.
+pub fn clean_bidi_text(text: &str) -> String {
+    let mut cleaned = String::with_capacity(text.len());
+    let mut previous: Option<char> = None;
+    for c in text.chars().filter(|c| !is_bidi_control(*c)) {
+        if let Some(previous) = previous {
+            if c.is_alphabetic()
+                && previous.is_alphabetic()
+                && is_rtl_letter(c) != is_rtl_letter(previous)
+                && !extends_grapheme(c)
+            {
+                cleaned.push(' ');
+            }
+        }
+        cleaned.push(c);
+        previous = Some(c);
+    }
+    cleaned
+}
+
+/// How runs of punctuation, such as separators and ASCII art, are read
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PunctuationRuns {
+    /// Runs of at least this many times the same punctuation character are collapsed,
+    /// never less than 3
+    pub min_length: usize,
+    /// Read a short description such as `line`, rather than nothing
+    pub describe: bool,
+}
+
+/// Short description of a run of a character
+fn describe_punctuation(c: char) -> &'static str {
+    match c {
+        '-' | '_' | '=' | '—' | '–' | '─' | '━' | '═' => "line",
+        '*' | '★' | '☆' => "stars",
+        '.' | '·' | '•' => "dots",
+        '#' => "hashes",
+        '~' => "tildes",
+        '+' | '|' | '/' | '\\' | '<' | '>' | '^' => "drawing",
+        _ => "symbols",
+    }
+}
+
+/// Punctuation and the symbols separators and ASCII art are drawn with
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(
+            c as u32,
+            0x00B7 | 0x2010..=0x2027 | 0x2500..=0x25FF | 0x2605..=0x2606
+        )
+}
+
+/// Collapse runs of a repeated punctuation character into a short description or a
+/// space, so that voices don't read separators such as `-----` character by character.
+/// Mixed punctuation such as `?!...` or `:-)` is left as is, it is meaningful.
+pub fn collapse_punctuation_runs(text: &str, runs: PunctuationRuns) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut run: Option<(char, usize)> = None;
+    for c in text.chars() {
+        match &mut run {
+            Some((repeated, count)) if *repeated == c => *count += 1,
+            _ => {
+                flush_punctuation_run(run.take(), &mut collapsed, runs);
+                if is_punctuation(c) {
+                    run = Some((c, 1));
+                } else {
+                    collapsed.push(c);
+                }
+            }
+        }
+    }
+    flush_punctuation_run(run, &mut collapsed, runs);
+    collapsed
+}
+
+fn flush_punctuation_run(
+    run: Option<(char, usize)>,
+    collapsed: &mut String,
+    runs: PunctuationRuns,
+) {
+    let Some((c, count)) = run else {
+        return;
+    };
+    // Pairs such as `--` and ellipses are never collapsed
+    if count < runs.min_length.max(3) || (c == '.' && count == 3) {
+        collapsed.extend(std::iter::repeat_n(c, count));
+        return;
+    }
+    collapsed.push(' ');
+    if runs.describe {
+        collapsed.push_str(describe_punctuation(c));
+        collapsed.push(' ');
+    }
+}
+
+/// The entity standing for a character with a special meaning in XML
+fn xml_entity(c: char) -> Option<&'static str> {
+    match c {
+        '&' => Some("&amp;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        '"' => Some("&quot;"),
+        '\'' => Some("&apos;"),
+        _ => None,
+    }
+}
+
+/// Escape the characters with a special meaning in XML
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match xml_entity(c) {
+            Some(entity) => escaped.push_str(entity),
+            None => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Maps offsets in UTF-16 code units in a rewritten text back to the text it was
+/// rewritten from, the offsets of word and sentence events are in the synthesized text
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct OffsetMap {
+    /// Pieces kept unchanged: offset in the rewritten text, offset in the original, length
+    runs: Vec<(usize, usize, usize)>,
+}
+
+impl OffsetMap {
+    /// The offset in the original text of an offset in the rewritten one. Offsets in
+    /// inserted or replacing text map to the end of the preceding unchanged piece.
+    pub fn map(&self, offset: usize) -> usize {
+        let idx = self.runs.partition_point(|(start, _, _)| *start <= offset);
+        match idx.checked_sub(1).map(|idx| self.runs[idx]) {
+            Some((start, original, len)) => original + (offset - start).min(len),
+            None => 0,
+        }
+    }
+
+    /// The offset and length in the original text of a span of the rewritten one
+    pub fn map_span(&self, offset: usize, length: usize) -> (usize, usize) {
+        let start = self.map(offset);
+        let end = self.map(offset + length);
+        (start, end.saturating_sub(start))
+    }
+
+    /// The map of a text rewritten from `original` by filters which keep most of it, found
+    /// by diffing both. Past `MAX_DIFF_EDITS` edits only their common start and end are mapped.
+    pub fn diff(original: &str, rewritten: &str) -> OffsetMap {
+        let original: Vec<u16> = original.encode_utf16().collect();
+        let rewritten: Vec<u16> = rewritten.encode_utf16().collect();
+        let prefix = original
+            .iter()
+            .zip(&rewritten)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = original[prefix..]
+            .iter()
+            .rev()
+            .zip(rewritten[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let middle = (
+            &original[prefix..original.len() - suffix],
+            &rewritten[prefix..rewritten.len() - suffix],
+        );
+        let mut map = MappedOffsets::default();
+        map.keep(0, 0, prefix);
+        for (original_idx, rewritten_idx) in diff_matches(middle.0, middle.1).unwrap_or_default() {
+            map.keep(prefix + rewritten_idx, prefix + original_idx, 1);
+        }
+        map.keep(rewritten.len() - suffix, original.len() - suffix, suffix);
+        map.0
+    }
+
+    /// The map of a text rewritten with this map from one rewritten with `earlier`
+    pub fn compose(&self, earlier: &OffsetMap) -> OffsetMap {
+        let mut runs = Vec::new();
+        for &(start, middle, len) in &self.runs {
+            for &(earlier_start, original, earlier_len) in &earlier.runs {
+                let from = middle.max(earlier_start);
+                let to = (middle + len).min(earlier_start + earlier_len);
+                if from < to {
+                    runs.push((
+                        start + from - middle,
+                        original + from - earlier_start,
+                        to - from,
+                    ));
+                }
+            }
+        }
+        OffsetMap { runs }
+    }
+}
+
+/// Diffs with more edits than this aren't worth the time and memory they take
+const MAX_DIFF_EDITS: usize = 2000;
+
+/// Runs being added to an offset map, merged when they follow each other
+#[derive(Debug, Default)]
+struct MappedOffsets(OffsetMap);
+
+impl MappedOffsets {
+    fn keep(&mut self, rewritten: usize, original: usize, len: usize) {
+        match self.0.runs.last_mut() {
+            _ if len == 0 => {}
+            Some((start, run_original, run_len))
+                if *start + *run_len == rewritten && *run_original + *run_len == original =>
+            {
+                *run_len += len
+            }
+            _ => self.0.runs.push((rewritten, original, len)),
+        }
+    }
+}
+
+/// The pairs of indices of the units `original` and `rewritten` have in common, in order,
+/// with Myers' algorithm. None past `MAX_DIFF_EDITS` edits.
+fn diff_matches(original: &[u16], rewritten: &[u16]) -> Option<Vec<(usize, usize)>> {
+    let (n, m) = (original.len() as isize, rewritten.len() as isize);
+    let max = n + m;
+    // The furthest x reached on each diagonal k = x - y, offset by max
+    let mut v = vec![0isize; 2 * max as usize + 2];
+    // The diagonals from -d to d before each step d, to walk the path back
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let at = |k: isize| (k + max) as usize;
+    'search: for d in 0..=max.min(MAX_DIFF_EDITS as isize) {
+        trace.push(v[at(-d)..=at(d)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[at(k - 1)] < v[at(k + 1)]) {
+                v[at(k + 1)]
+            } else {
+                v[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && original[x as usize] == rewritten[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[at(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+        if d == max.min(MAX_DIFF_EDITS as isize) {
+            return None;
+        }
+    }
+    let mut matches = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (0..trace.len() as isize).rev() {
+        let previous = &trace[d as usize];
+        let get = |k: isize| previous[(k + d) as usize];
+        let k = x - y;
+        let (previous_x, previous_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let previous_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) {
+                k + 1
+            } else {
+                k - 1
+            };
+            (get(previous_k), get(previous_k) - previous_k)
+        };
+        while x > previous_x && y > previous_y {
+            x -= 1;
+            y -= 1;
+            matches.push((x as usize, y as usize));
+        }
+        (x, y) = (previous_x, previous_y);
+    }
+    matches.reverse();
+    Some(matches)
+}
+
+/// Builds a rewritten text along with the map of its offsets back to the original
+#[derive(Debug, Default)]
+pub struct MappedText {
+    text: String,
+    map: MappedOffsets,
+    /// Length of the text so far in UTF-16 code units
+    len: usize,
+    /// Offset reached in the original text in UTF-16 code units
+    original: usize,
+}
+
+impl MappedText {
+    /// Append a piece of the original text unchanged
+    pub fn copy(&mut self, piece: &str) {
+        let len = piece.encode_utf16().count();
+        self.map.keep(self.len, self.original, len);
+        self.text.push_str(piece);
+        self.len += len;
+        self.original += len;
+    }
+
+    /// Append text which isn't part of the original
+    pub fn insert(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.len += text.encode_utf16().count();
+    }
+
+    /// Append text in place of a piece of the original
+    pub fn replace(&mut self, piece: &str, text: &str) {
+        self.insert(text);
+        self.original += piece.encode_utf16().count();
+    }
+
+    /// Append a piece of the original text escaped for XML
+    pub fn escape_xml(&mut self, piece: &str) {
+        let mut kept = 0;
+        for (idx, c) in piece.char_indices() {
+            if let Some(entity) = xml_entity(c) {
+                self.copy(&piece[kept..idx]);
+                self.replace(&piece[idx..idx + 1], entity);
+                kept = idx + 1;
+            }
+        }
+        self.copy(&piece[kept..]);
+    }
+
+    pub fn finish(self) -> (String, OffsetMap) {
+        (self.text, self.map.0)
+    }
+}
+
+/// An SSML document speaking `text` preceded by a mark named `mark`, with the map of its
+/// offsets back to the text
+pub fn marked_ssml(language: &str, mark: &str, text: &str) -> (String, OffsetMap) {
+    let mut ssml = MappedText::default();
+    ssml.insert(&format!(
+        r#"<speak version="1.0" xmlns="http://www.w3.org/2001/10/synthesis" xml:lang="{}"><mark name="{}"/>"#,
+        escape_xml(language),
+        escape_xml(mark),
+    ));
+    ssml.escape_xml(text);
+    ssml.insert("</speak>");
+    ssml.finish()
+}
+
+/// Remove markup tags, leaving only the text content of an SSML document
+pub fn strip_tags(markup: &str) -> String {
+    let mut text = String::with_capacity(markup.len());
+    let mut in_tag = false;
+    for c in markup.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Number of characters that will be spoken for a text or SSML element
+pub fn spoken_len(content: &str, is_ssml: bool) -> usize {
+    if is_ssml {
+        strip_tags(content).chars().count()
+    } else {
+        content.chars().count()
+    }
+}
+
+/// SSML elements after which a document may be split
+const SSML_BLOCK_ELEMENTS: [&str; 4] = ["p", "s", "paragraph", "sentence"];
+
+/// A piece of an SSML document between two split points. The prefix reopens the
+/// elements enclosing the piece and the suffix closes those still open after it.
+struct SsmlPiece {
+    prefix: String,
+    body: String,
+    suffix: String,
+}
+
+/// Split an SSML document into standalone documents of at most `max_chars` characters.
+///
+/// Documents are split after sentences and paragraphs and before marks, the enclosing
+/// elements such as `speak`, `voice` or `prosody` are repeated in every part. A part may
+/// still exceed the limit if it holds a single long sentence. Returns None if the
+/// document can't be parsed or has nowhere to be split.
+pub fn split_ssml(ssml: &str, max_chars: usize) -> Option<Vec<String>> {
+    let mut parts = Vec::new();
+    let mut group: Option<SsmlPiece> = None;
+    for piece in split_ssml_pieces(ssml)? {
+        group = match group {
+            Some(mut group)
+                if group.prefix.chars().count()
+                    + group.body.chars().count()
+                    + piece.body.chars().count()
+                    + piece.suffix.chars().count()
+                    <= max_chars =>
+            {
+                group.body.push_str(&piece.body);
+                group.suffix = piece.suffix;
+                Some(group)
+            }
+            previous => {
+                if let Some(previous) = previous {
+                    parts.push(previous.prefix + &previous.body + &previous.suffix);
+                }
+                Some(piece)
+            }
+        };
+    }
+    if let Some(group) = group {
+        parts.push(group.prefix + &group.body + &group.suffix);
+    }
+    (parts.len() > 1).then_some(parts)
+}
+
+/// Cut an SSML document at every split point
+fn split_ssml_pieces(ssml: &str) -> Option<Vec<SsmlPiece>> {
+    let mut pieces = Vec::new();
+    // Name and opening tag of the elements enclosing the current position
+    let mut open: Vec<(&str, &str)> = Vec::new();
+    let mut prefix = String::new();
+    let mut body = String::new();
+    let mut rest = ssml;
+    while let Some(start) = rest.find('<') {
+        let (text, markup) = rest.split_at(start);
+        if open.is_empty() && !text.trim().is_empty() {
+            return None;
+        }
+        body.push_str(text);
+        let (tag, remainder) = markup.split_at(tag_end(markup)?);
+        rest = remainder;
+        // The XML declaration, comments and other declarations are dropped
+        if tag.starts_with("<?") || tag.starts_with("<!") {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix("</") {
+            let name = name.trim_end_matches('>').trim();
+            if open.pop()?.0 != name {
+                return None;
+            }
+            body.push_str(tag);
+            if SSML_BLOCK_ELEMENTS.contains(&name) {
+                flush_ssml_piece(&mut pieces, &mut prefix, &mut body, &open);
+            }
+        } else {
+            let name = tag_name(tag);
+            if tag.ends_with("/>") {
+                if name == "mark" {
+                    flush_ssml_piece(&mut pieces, &mut prefix, &mut body, &open);
+                }
+                body.push_str(tag);
+            } else {
+                body.push_str(tag);
+                open.push((name, tag));
+            }
+        }
+    }
+    if !open.is_empty() || !rest.trim().is_empty() {
+        return None;
+    }
+    flush_ssml_piece(&mut pieces, &mut prefix, &mut body, &open);
+    Some(pieces)
+}
+
+/// End the current piece, unless it has nothing to speak yet
+fn flush_ssml_piece(
+    pieces: &mut Vec<SsmlPiece>,
+    prefix: &mut String,
+    body: &mut String,
+    open: &[(&str, &str)],
+) {
+    if strip_tags(body).trim().is_empty() && !body.contains("/>") {
+        return;
+    }
+    let next_prefix = open.iter().map(|(_, tag)| *tag).collect();
+    pieces.push(SsmlPiece {
+        prefix: std::mem::replace(prefix, next_prefix),
+        body: std::mem::take(body),
+        suffix: open
+            .iter()
+            .rev()
+            .map(|(name, _)| format!("</{}>", name))
+            .collect(),
+    });
+}
+
+/// Length of the tag or comment at the start of `markup`, None if it isn't terminated
+fn tag_end(markup: &str) -> Option<usize> {
+    if markup.starts_with("<!--") {
+        return markup.find("-->").map(|idx| idx + 3);
+    }
+    let mut quote = None;
+    for (idx, c) in markup.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('>', None) => return Some(idx + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The element name of an opening or self-closing tag
+fn tag_name(tag: &str) -> &str {
+    let name = &tag[1..];
+    let end = name
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .unwrap_or(name.len());
+    &name[..end]
+}
+
+/// Wrap the content of the speak element of an SSML document in the given tags, with the
+/// map of the offsets back to the document. None if the document has no speak element.
+pub fn wrap_speak_content(ssml: &str, open: &str, close: &str) -> Option<(String, OffsetMap)> {
+    let start = ssml
+        .match_indices("<speak")
+        .map(|(idx, _)| idx)
+        .find(|idx| tag_name(&ssml[*idx..]) == "speak")?;
+    let content_start = start + tag_end(&ssml[start..])?;
+    let content_end = ssml.rfind("</speak>").filter(|end| *end >= content_start)?;
+    let mut wrapped = MappedText::default();
+    wrapped.copy(&ssml[..content_start]);
+    wrapped.insert(open);
+    wrapped.copy(&ssml[content_start..content_end]);
+    wrapped.insert(close);
+    wrapped.copy(&ssml[content_end..]);
+    Some(wrapped.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_text_keeps_emoji_sequences_whole() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let text = format!("{}{}", family, family);
+        // A shorter limit leaves no choice but to split the sequence
+        for max_chars in family.chars().count()..family.chars().count() * 2 {
+            for chunk in split_text(&text, max_chars) {
+                assert_eq!(chunk.replace(family, ""), "", "split at {}", max_chars);
+            }
+        }
+        let thumbs = "\u{1F44D}\u{1F3FD}";
+        assert_eq!(
+            split_text(&format!("{}{}", thumbs, thumbs), 3),
+            vec![thumbs, thumbs]
+        );
+    }
+
+    #[test]
+    fn split_text_keeps_flags_whole() {
+        // France, Germany and Japan
+        let flags = "\u{1F1EB}\u{1F1F7}\u{1F1E9}\u{1F1EA}\u{1F1EF}\u{1F1F5}";
+        assert_eq!(
+            split_text(flags, 3),
+            vec![
+                "\u{1F1EB}\u{1F1F7}",
+                "\u{1F1E9}\u{1F1EA}",
+                "\u{1F1EF}\u{1F1F5}"
+            ]
+        );
+        assert_eq!(
+            split_text(flags, 5),
+            vec!["\u{1F1EB}\u{1F1F7}\u{1F1E9}\u{1F1EA}", "\u{1F1EF}\u{1F1F5}"]
+        );
+    }
+
+    #[test]
+    fn split_text_breaks_cjk_at_sentence_ends() {
+        assert_eq!(
+            split_text("今日は晴れです。明日は雨です。", 10),
+            vec!["今日は晴れです。", "明日は雨です。"]
+        );
+        assert_eq!(split_text("你好世界", 3), vec!["你好世", "界"]);
+    }
+
+    #[test]
+    fn split_text_keeps_rtl_marks_with_their_letters() {
+        // Arabic with short vowels and a question mark, Hebrew with points
+        assert_eq!(
+            split_text("مَرْحَبًا؟ كَيْفَ حَالُكَ", 14),
+            vec!["مَرْحَبًا؟", "كَيْفَ حَالُكَ"]
+        );
+        // The longest cluster is a letter with two points
+        let hebrew = "שָׁלוֹם";
+        for max_chars in 3..hebrew.chars().count() {
+            let chunks = split_text(hebrew, max_chars);
+            assert_eq!(chunks.concat(), hebrew);
+            for chunk in chunks {
+                assert!(
+                    !chunk.starts_with(extends_grapheme),
+                    "split at {}",
+                    max_chars
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rtl_letters() {
+        for c in ['\u{05D0}', '\u{0628}', '\u{0710}', '\u{0780}', '\u{FB50}'] {
+            assert!(is_rtl_letter(c), "{:?}", c);
+        }
+        // Latin, CJK, and Arabic-Indic digits and punctuation
+        for c in ['a', '\u{00E9}', '\u{6F22}', '\u{0663}', '\u{060C}'] {
+            assert!(!is_rtl_letter(c), "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn clean_bidi_text_separates_mixed_runs() {
+        assert_eq!(
+            clean_bidi_text("abc\u{05E9}\u{05DC}\u{05D5}\u{05DD}"),
+            "abc \u{05E9}\u{05DC}\u{05D5}\u{05DD}"
+        );
+        assert_eq!(
+            clean_bidi_text("\u{0645}\u{0631}\u{062D}\u{0628}\u{0627}abc"),
+            "\u{0645}\u{0631}\u{062D}\u{0628}\u{0627} abc"
+        );
+        // Marks stay on their letter, the space goes after them
+        assert_eq!(
+            clean_bidi_text("\u{0628}\u{064E}abc"),
+            "\u{0628}\u{064E} abc"
+        );
+        assert_eq!(
+            clean_bidi_text("caf\u{00E9}\u{05D0}"),
+            "caf\u{00E9} \u{05D0}"
+        );
+        // Digits and punctuation don't belong to either direction
+        assert_eq!(
+            clean_bidi_text("\u{05D0}\u{05D1}123abc"),
+            "\u{05D0}\u{05D1}123abc"
+        );
+        assert_eq!(clean_bidi_text("\u{05D0}, abc."), "\u{05D0}, abc.");
+        assert_eq!(clean_bidi_text("plain text"), "plain text");
+    }
+
+    #[test]
+    fn clean_bidi_text_strips_controls() {
+        assert_eq!(
+            clean_bidi_text("Hello\u{200F}\u{05E9}\u{05DC}"),
+            "Hello \u{05E9}\u{05DC}"
+        );
+        assert_eq!(
+            clean_bidi_text("\u{202B}\u{0645}\u{0631}\u{202C} world\u{200E}"),
+            "\u{0645}\u{0631} world"
+        );
+        assert_eq!(clean_bidi_text("\u{2067}abc\u{2069}\u{061C}1"), "abc1");
+        assert_eq!(
+            clean_bidi_text("\u{202A}\u{202D}\u{202E}\u{2066}\u{2068}"),
+            ""
+        );
+    }
+
+    #[test]
+    fn utf16_offsets_round_trip() {
+        let text = "a\u{1F600}b漢字\u{05E9}";
+        assert_eq!(utf16_to_char_offset(text, 0), Some(0));
+        assert_eq!(utf16_to_char_offset(text, 1), Some(1));
+        // Inside the surrogate pair of the emoji
+        assert_eq!(utf16_to_char_offset(text, 2), None);
+        assert_eq!(utf16_to_char_offset(text, 3), Some(2));
+        assert_eq!(utf16_to_char_offset(text, 5), Some(4));
+        assert_eq!(utf16_to_char_offset(text, 7), Some(6));
+        assert_eq!(utf16_to_char_offset(text, 8), None);
+        for offset in 0..=6 {
+            let units = char_to_utf16_offset(text, offset).unwrap();
+            assert_eq!(utf16_to_char_offset(text, units), Some(offset));
+        }
+        assert_eq!(char_to_utf16_offset(text, 7), None);
+    }
+
+    #[test]
+    fn offset_map_maps_filtered_words_back() {
+        let original = "Read \u{1F600} & ask";
+        let rewritten = "Read grinning face and ask";
+        let map = OffsetMap::diff(original, rewritten);
+        let ask = rewritten.find("ask").unwrap();
+        assert_eq!(
+            map.map_span(ask, 3),
+            (original.encode_utf16().count() - 3, 3)
+        );
+        assert_eq!(map.map_span(0, 4), (0, 4));
+    }
+
+    #[test]
+    fn offset_map_maps_marked_ssml_back() {
+        let (ssml, offsets) = marked_ssml("en-US", "start", "Tom & Jerry");
+        let jerry = ssml.find("Jerry").unwrap();
+        assert_eq!(offsets.map_span(jerry, 5), (6, 5));
+        let ampersand = ssml.find("&amp;").unwrap();
+        assert_eq!(offsets.map_span(ampersand, 5), (4, 1));
+        let (wrapped, prosody) = wrap_speak_content(&ssml, "<prosody>", "</prosody>").unwrap();
+        let jerry = wrapped.find("Jerry").unwrap();
+        assert_eq!(prosody.compose(&offsets).map_span(jerry, 5), (6, 5));
+    }
+
+    #[test]
+    fn collapse_punctuation_runs_only_collapses_repeated_characters() {
+        let runs = PunctuationRuns {
+            min_length: 3,
+            describe: true,
+        };
+        let collapse = |text| collapse_punctuation_runs(text, runs);
+        assert_eq!(collapse("Title\n-----\nBody"), "Title\n line \nBody");
+        assert_eq!(collapse("What?!...."), "What?! dots ");
+        assert_eq!(collapse("Wait... what"), "Wait... what");
+        assert_eq!(collapse("Really?!?"), "Really?!?");
+        assert_eq!(collapse("Hi :-) --"), "Hi :-) --");
+        assert_eq!(collapse("a=*=*=*b"), "a=*=*=*b");
+        let silent = PunctuationRuns {
+            describe: false,
+            ..runs
+        };
+        assert_eq!(collapse_punctuation_runs("a***b", silent), "a b");
+    }
+}
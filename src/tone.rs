@@ -0,0 +1,85 @@
+use crate::text::{escape_xml, wrap_speak_content, MappedText, OffsetMap};
+use crate::{NeosynthResult, OperationError};
+use std::str::FromStr;
+
+/// Prosody presets conveying a tone of voice, applied through SSML and the player's gain
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum Tone {
+    #[default]
+    Normal,
+    /// Soft, low and slightly slower speech
+    Whisper,
+    /// Loud, raised and slightly slower speech
+    Announcement,
+}
+
+impl Tone {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tone::Normal => "normal",
+            Tone::Whisper => "whisper",
+            Tone::Announcement => "announcement",
+        }
+    }
+
+    /// Attributes of the SSML prosody element speaking with this tone
+    fn prosody(&self) -> Option<&'static str> {
+        match self {
+            Tone::Normal => None,
+            Tone::Whisper => Some(r#"volume="x-soft" pitch="low" rate="-10%""#),
+            Tone::Announcement => Some(r#"volume="x-loud" pitch="+10%" rate="-5%""#),
+        }
+    }
+
+    /// Factor applied to the player's volume, which can't go above the user's volume
+    pub fn gain(&self) -> f64 {
+        match self {
+            Tone::Whisper => 0.6,
+            Tone::Normal | Tone::Announcement => 1.0,
+        }
+    }
+
+    /// Speak text or SSML content with this tone, returns the content as SSML unless the
+    /// tone is normal, with the map of its offsets back to the content if it was rewritten.
+    /// SSML without a speak element is left as is.
+    pub fn apply(
+        &self,
+        content: &str,
+        is_ssml: bool,
+        language: &str,
+    ) -> (String, bool, Option<OffsetMap>) {
+        let Some(prosody) = self.prosody() else {
+            return (content.to_string(), is_ssml, None);
+        };
+        let open = format!("<prosody {}>", prosody);
+        if is_ssml {
+            return match wrap_speak_content(content, &open, "</prosody>") {
+                Some((ssml, offsets)) => (ssml, true, Some(offsets)),
+                None => (content.to_string(), true, None),
+            };
+        }
+        let mut ssml = MappedText::default();
+        ssml.insert(&format!(
+            r#"<speak version="1.0" xmlns="http://www.w3.org/2001/10/synthesis" xml:lang="{}">{}"#,
+            escape_xml(language),
+            open,
+        ));
+        ssml.escape_xml(content);
+        ssml.insert("</prosody></speak>");
+        let (ssml, offsets) = ssml.finish();
+        (ssml, true, Some(offsets))
+    }
+}
+
+impl FromStr for Tone {
+    type Err = crate::NeosynthError;
+
+    fn from_str(tone: &str) -> NeosynthResult<Self> {
+        match tone.to_ascii_lowercase().as_str() {
+            "normal" => Ok(Tone::Normal),
+            "whisper" | "soft" => Ok(Tone::Whisper),
+            "announcement" | "loud" => Ok(Tone::Announcement),
+            _ => Err(OperationError(format!("Invalid tone: {}", tone))),
+        }
+    }
+}
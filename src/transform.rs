@@ -0,0 +1,68 @@
+use crate::wave::WaveFormat;
+use crate::{NeosynthResult, OperationError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Time a Python transform gets to process an element before it is played unprocessed
+const PY_TRANSFORM_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Processes the samples of synthesized speech before they are played
+pub trait AudioTransform: Send + Sync {
+    /// Return the processed samples, `pcm` holds the raw samples in the given format
+    fn process(&self, format: &WaveFormat, pcm: Vec<u8>) -> NeosynthResult<Vec<u8>>;
+}
+
+/// Samples to process and where to send the result
+type TransformRequest = (WaveFormat, Vec<u8>, mpsc::Sender<PyResult<Vec<u8>>>);
+
+/// An audio transform calling a Python callable with
+/// `(pcm: bytes, sample_rate: int, channels: int, bits_per_sample: int)` which returns bytes.
+///
+/// The callable runs on a thread of its own rather than on the media thread, which waits
+/// for it for a limited time. A Python thread holding the GIL while waiting on the media
+/// thread therefore delays speech rather than deadlocking it.
+pub struct PyAudioTransform {
+    requests: mpsc::Sender<TransformRequest>,
+}
+
+impl PyAudioTransform {
+    pub fn new(callable: PyObject) -> Self {
+        let (requests, receiver) = mpsc::channel::<TransformRequest>();
+        // Ends once the transform, and with it the sending half, is dropped
+        thread::spawn(move || {
+            for (format, pcm, reply) in receiver {
+                let processed = Python::with_gil(|py| {
+                    let args = (
+                        PyBytes::new(py, &pcm),
+                        format.sample_rate,
+                        format.channels,
+                        format.bits_per_sample,
+                    );
+                    callable
+                        .call1(py, args)
+                        .and_then(|processed| Ok(processed.extract::<&[u8]>(py)?.to_vec()))
+                });
+                reply.send(processed).ok();
+            }
+        });
+        Self { requests }
+    }
+}
+
+impl AudioTransform for PyAudioTransform {
+    fn process(&self, format: &WaveFormat, pcm: Vec<u8>) -> NeosynthResult<Vec<u8>> {
+        let (reply, response) = mpsc::channel();
+        self.requests
+            .send((*format, pcm, reply))
+            .map_err(|_| OperationError("The audio transform has stopped".to_string()))?;
+        match response.recv_timeout(PY_TRANSFORM_TIMEOUT) {
+            Ok(processed) => {
+                processed.map_err(|e| OperationError(format!("Audio transform failed: {}", e)))
+            }
+            Err(_) => Err(OperationError("The audio transform timed out".to_string())),
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::fingerprint::fnv1a;
+use crate::wave::WaveFormat;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Environment variable overriding the location of the voice cache file
+pub const VOICE_CACHE_PATH_VAR: &str = "NEOSYNTH_VOICE_CACHE";
+const CACHE_FILE_NAME: &str = "voices.cache";
+
+/// Audio format and content type of each voice, kept on disk so that they are only
+/// probed once rather than by every process.
+///
+/// The cache is discarded when the set of installed voices changes. Several processes
+/// may share the cache file, writers hold a lock file and replace the cache atomically.
+pub struct VoiceCache {
+    path: Option<PathBuf>,
+    /// Fingerprint of the installed voices the entries were probed with
+    fingerprint: u64,
+    entries: Mutex<HashMap<String, (WaveFormat, String)>>,
+}
+
+impl VoiceCache {
+    fn default_path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os(VOICE_CACHE_PATH_VAR) {
+            return Some(PathBuf::from(path));
+        }
+        env::var_os("LOCALAPPDATA")
+            .map(|dir| PathBuf::from(dir).join("neosynth").join(CACHE_FILE_NAME))
+    }
+
+    /// Load the cache for the given installed voice ids, discarding it if they changed
+    pub fn load<'a>(voice_ids: impl IntoIterator<Item = &'a str>) -> Self {
+        let path = Self::default_path();
+        let fingerprint = fingerprint(voice_ids);
+        let entries = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| parse(&content, fingerprint))
+            .unwrap_or_default();
+        Self {
+            path,
+            fingerprint,
+            entries: Mutex::new(entries),
+        }
+    }
+
+    /// Look up a voice, picking up the entries saved by other processes on a miss
+    pub fn get(&self, voice_id: &str) -> Option<(WaveFormat, String)> {
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(voice_id) {
+            self.merge_saved(&mut entries);
+        }
+        entries.get(voice_id).cloned()
+    }
+
+    /// Remember the audio format of a voice, the cache file is updated on a best effort basis
+    pub fn insert(&self, voice_id: &str, format: WaveFormat, content_type: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.insert(voice_id.to_string(), (format, content_type.to_string()));
+        if let Some(path) = &self.path {
+            self.save(path, &mut entries).ok();
+        }
+    }
+
+    /// Forget every entry and remove the cache file
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+        if let Some(path) = &self.path {
+            let _lock = lock(path);
+            fs::remove_file(path).ok();
+        }
+    }
+
+    /// Add the entries saved in the cache file which are missing from `entries`
+    fn merge_saved(&self, entries: &mut HashMap<String, (WaveFormat, String)>) {
+        let saved = self
+            .path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| parse(&content, self.fingerprint))
+            .unwrap_or_default();
+        for (id, entry) in saved {
+            entries.entry(id).or_insert(entry);
+        }
+    }
+
+    /// Write the entries along with those saved meanwhile by other processes.
+    /// The file is replaced by renaming, so readers never see a partly written cache.
+    fn save(
+        &self,
+        path: &Path,
+        entries: &mut HashMap<String, (WaveFormat, String)>,
+    ) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let _lock = lock(path)?;
+        self.merge_saved(entries);
+        let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temp_path, serialize(entries, self.fingerprint))?;
+        fs::rename(&temp_path, path).inspect_err(|_| {
+            fs::remove_file(&temp_path).ok();
+        })
+    }
+}
+
+/// Hold an exclusive lock on the lock file next to the cache file until the returned file is dropped
+fn lock(path: &Path) -> io::Result<File> {
+    let lock_file = File::create(path.with_extension("lock"))?;
+    lock_file.lock()?;
+    Ok(lock_file)
+}
+
+/// Hash of the sorted voice ids, each terminated by a newline
+fn fingerprint<'a>(voice_ids: impl IntoIterator<Item = &'a str>) -> u64 {
+    let mut ids: Vec<_> = voice_ids.into_iter().collect();
+    ids.sort_unstable();
+    let mut bytes = Vec::new();
+    for id in ids {
+        bytes.extend_from_slice(id.as_bytes());
+        bytes.push(b'\n');
+    }
+    fnv1a(&bytes)
+}
+
+/// The first line holds the fingerprint, then one tab separated line per voice:
+/// id, channels, sample rate, byte rate, bits per sample and content type
+fn serialize(entries: &HashMap<String, (WaveFormat, String)>, fingerprint: u64) -> String {
+    let mut content = format!("voices={:016x}\n", fingerprint);
+    for (id, (format, content_type)) in entries {
+        content.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\n",
+            id,
+            format.channels,
+            format.sample_rate,
+            format.byte_rate,
+            format.bits_per_sample,
+            content_type
+        ));
+    }
+    content
+}
+
+fn parse(content: &str, fingerprint: u64) -> Option<HashMap<String, (WaveFormat, String)>> {
+    let mut lines = content.lines();
+    let cached_fingerprint = lines.next()?.strip_prefix("voices=")?;
+    if u64::from_str_radix(cached_fingerprint, 16).ok()? != fingerprint {
+        return None;
+    }
+    let mut entries = HashMap::new();
+    for line in lines {
+        let fields: Vec<_> = line.split('\t').collect();
+        let [id, channels, sample_rate, byte_rate, bits_per_sample, content_type] = fields[..]
+        else {
+            return None;
+        };
+        let format = WaveFormat {
+            channels: channels.parse().ok()?,
+            sample_rate: sample_rate.parse().ok()?,
+            byte_rate: byte_rate.parse().ok()?,
+            bits_per_sample: bits_per_sample.parse().ok()?,
+        };
+        entries.insert(id.to_string(), (format, content_type.to_string()));
+    }
+    Some(entries)
+}
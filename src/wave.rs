@@ -0,0 +1,200 @@
+use crate::async_ops::AsyncOps;
+use crate::{NeosynthResult, OperationError};
+use std::time::Duration;
+use windows::Storage::Streams::{DataReader, DataWriter, IInputStream, InMemoryRandomAccessStream};
+
+/// Number of bytes read from the start of a stream when looking for the `fmt ` chunk
+const HEADER_PROBE_SIZE: u32 = 512;
+/// Content type of RIFF/WAVE audio, the format produced by OneCore voices
+pub const WAVE_CONTENT_TYPE: &str = "audio/wav";
+/// Content types reported by streams which don't know what they hold
+const GENERIC_CONTENT_TYPES: [&str; 2] = ["", "application/octet-stream"];
+
+/// The audio format of a RIFF/WAVE stream
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct WaveFormat {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub byte_rate: u32,
+    pub bits_per_sample: u16,
+}
+
+impl WaveFormat {
+    /// Parse the `fmt ` chunk out of the header of a RIFF/WAVE file
+    pub fn parse(header: &[u8]) -> Option<Self> {
+        let (body, _) = find_chunk(header, b"fmt ")?;
+        let fmt = header.get(body..body + 16)?;
+        Some(Self {
+            channels: u16::from_le_bytes([fmt[2], fmt[3]]),
+            sample_rate: u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]),
+            byte_rate: u32::from_le_bytes([fmt[8], fmt[9], fmt[10], fmt[11]]),
+            bits_per_sample: u16::from_le_bytes([fmt[14], fmt[15]]),
+        })
+    }
+
+    /// Read and parse the header of the given input stream
+    pub fn from_stream(input: &IInputStream, ops: &AsyncOps) -> NeosynthResult<Self> {
+        let header = read_bytes(input, HEADER_PROBE_SIZE, ops)?;
+        Self::parse(&header).ok_or_else(|| {
+            OperationError("The audio stream is not a valid wave stream".to_string())
+        })
+    }
+}
+
+/// Build a RIFF/WAVE file of 16 bit mono silence lasting `duration`, None if it is too
+/// long for the sizes of a RIFF file
+pub fn silence(sample_rate: u32, duration: Duration) -> Option<Vec<u8>> {
+    let samples = (duration.as_secs_f64() * sample_rate as f64) as u64;
+    let data_size = u32::try_from(samples.checked_mul(2)?).ok()?;
+    let riff_size = data_size.checked_add(36)?;
+    let byte_rate = sample_rate.checked_mul(2)?;
+    let mut wave = Vec::with_capacity(44 + data_size as usize);
+    wave.extend_from_slice(b"RIFF");
+    wave.extend_from_slice(&riff_size.to_le_bytes());
+    wave.extend_from_slice(b"WAVEfmt ");
+    wave.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, one channel
+    wave.extend_from_slice(&1u16.to_le_bytes());
+    wave.extend_from_slice(&1u16.to_le_bytes());
+    wave.extend_from_slice(&sample_rate.to_le_bytes());
+    wave.extend_from_slice(&byte_rate.to_le_bytes());
+    // Block align and bits per sample
+    wave.extend_from_slice(&2u16.to_le_bytes());
+    wave.extend_from_slice(&16u16.to_le_bytes());
+    wave.extend_from_slice(b"data");
+    wave.extend_from_slice(&data_size.to_le_bytes());
+    wave.resize(44 + data_size as usize, 0);
+    Some(wave)
+}
+
+/// Find a chunk of a RIFF/WAVE file, returns the offset of its body and its declared size
+fn find_chunk(wave: &[u8], id: &[u8; 4]) -> Option<(usize, usize)> {
+    if wave.len() < 12 || &wave[0..4] != b"RIFF" || &wave[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut pos: usize = 12;
+    while pos.checked_add(8)? <= wave.len() {
+        let chunk_size = u32::from_le_bytes(wave[pos + 4..pos + 8].try_into().ok()?) as usize;
+        let body = pos + 8;
+        if &wave[pos..pos + 4] == id {
+            return Some((body, chunk_size));
+        }
+        // Chunks are padded to an even number of bytes, a corrupt size can't wrap around
+        pos = body.checked_add(chunk_size)?.checked_add(chunk_size & 1)?;
+    }
+    None
+}
+
+/// The audio samples of a RIFF/WAVE file
+pub fn samples(wave: &[u8]) -> Option<&[u8]> {
+    let (body, size) = find_chunk(wave, b"data")?;
+    wave.get(body..body.saturating_add(size).min(wave.len()))
+}
+
+/// Build a RIFF/WAVE file with the header of `wave` and the given samples
+pub fn replace_samples(wave: &[u8], pcm: &[u8]) -> Option<Vec<u8>> {
+    let (body, _) = find_chunk(wave, b"data")?;
+    let mut replaced = Vec::with_capacity(body + pcm.len());
+    replaced.extend_from_slice(&wave[..body]);
+    replaced.extend_from_slice(pcm);
+    let riff_size = u32::try_from(replaced.len() - 8).ok()?;
+    let data_size = u32::try_from(pcm.len()).ok()?;
+    replaced[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    replaced[body - 4..body].copy_from_slice(&data_size.to_le_bytes());
+    Some(replaced)
+}
+
+/// Recognize the content type of audio data from its first bytes
+pub fn sniff_content_type(header: &[u8]) -> Option<&'static str> {
+    if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE" {
+        Some(WAVE_CONTENT_TYPE)
+    } else if header.starts_with(b"OggS") {
+        Some("audio/ogg")
+    } else if header.starts_with(b"fLaC") {
+        Some("audio/flac")
+    } else if header.starts_with(b"ID3")
+        || (header.len() >= 2 && header[0] == 0xFF && header[1] & 0xE0 == 0xE0)
+    {
+        Some("audio/mpeg")
+    } else {
+        None
+    }
+}
+
+/// The content type of an audio stream, recognized from its header when possible.
+/// Falls back to the reported content type, then to `WAVE_CONTENT_TYPE`.
+pub fn content_type(reported: &str, header: &[u8]) -> String {
+    let reported = reported.trim();
+    sniff_content_type(header)
+        .or_else(|| (!GENERIC_CONTENT_TYPES.contains(&reported)).then_some(reported))
+        .unwrap_or(WAVE_CONTENT_TYPE)
+        .to_string()
+}
+
+/// Read and recognize the content type of the given input stream
+pub fn stream_content_type(
+    reported: &str,
+    input: &IInputStream,
+    ops: &AsyncOps,
+) -> NeosynthResult<String> {
+    Ok(content_type(reported, &read_bytes(input, 12, ops)?))
+}
+
+/// Create an in-memory stream holding the given bytes
+pub fn memory_stream(bytes: &[u8], ops: &AsyncOps) -> NeosynthResult<InMemoryRandomAccessStream> {
+    let stream = InMemoryRandomAccessStream::new()?;
+    let writer = DataWriter::CreateDataWriter(&stream.GetOutputStreamAt(0)?)?;
+    writer.WriteBytes(bytes)?;
+    ops.wait::<u32>(&writer.StoreAsync()?)?;
+    ops.wait::<bool>(&writer.FlushAsync()?)?;
+    writer.DetachStream()?;
+    Ok(stream)
+}
+
+/// Read up to `count` bytes from the start of the given input stream
+pub fn read_bytes(input: &IInputStream, count: u32, ops: &AsyncOps) -> NeosynthResult<Vec<u8>> {
+    let reader = DataReader::CreateDataReader(input)?;
+    let loaded = ops.wait::<u32>(&reader.LoadAsync(count)?)?;
+    let mut buf = vec![0u8; loaded as usize];
+    reader.ReadBytes(&mut buf)?;
+    Ok(buf)
+}
+
+/// Samples quieter than this are considered silent, about -54 dBFS for 16 bit audio
+pub const SILENCE_LEVEL: u16 = 64;
+
+/// Start and end in seconds of the silent stretches of PCM samples lasting at least
+/// `min_duration`. Only 16 bit samples are analyzed, other formats have no silence.
+pub fn silent_stretches(format: &WaveFormat, pcm: &[u8], min_duration: f64) -> Vec<(f64, f64)> {
+    if format.bits_per_sample != 16 || format.channels == 0 || format.sample_rate == 0 {
+        return Vec::new();
+    }
+    let rate = format.sample_rate as f64;
+    let mut stretches = Vec::new();
+    let mut push_stretch = |first: usize, end: usize| {
+        let (start, end) = (first as f64 / rate, end as f64 / rate);
+        if end - start >= min_duration {
+            stretches.push((start, end));
+        }
+    };
+    let frames = pcm.chunks_exact(2 * format.channels as usize);
+    let frame_count = frames.len();
+    let mut silence_start = None;
+    for (idx, frame) in frames.enumerate() {
+        let is_silent = frame.chunks_exact(2).all(|sample| {
+            i16::from_le_bytes([sample[0], sample[1]]).unsigned_abs() < SILENCE_LEVEL
+        });
+        match (is_silent, silence_start) {
+            (true, None) => silence_start = Some(idx),
+            (false, Some(first)) => {
+                push_stretch(first, idx);
+                silence_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(first) = silence_start {
+        push_stretch(first, frame_count);
+    }
+    stretches
+}
@@ -28,11 +28,12 @@ def get_python_interpreters():
 
 @task
 def build_wheels(c, release=True, strip=True, sdist=True):
+    # The wheels target the stable ABI (abi3), so one interpreter per architecture is enough
     i_args = {
-        exe: "i686-pc-windows-msvc" if arch == "32" else "x86_64-pc-windows-msvc"
+        "i686-pc-windows-msvc" if arch == "32" else "x86_64-pc-windows-msvc": exe
         for (exe, arch) in get_python_interpreters()
     }
-    for (interpreter_path, target) in i_args.items():
+    for (target, interpreter_path) in i_args.items():
         print(f"Building wheel for Python {interpreter_path} using target {target}")
         build_command = " ".join(
             [
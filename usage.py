@@ -13,16 +13,22 @@ logging.basicConfig(format=FORMAT)
 
 class EventSink:
     """
-    Required to handle synthesizer events.
-    Must have the following two methods.
+    Receives synthesizer events.
+    Every handler is optional, events without a handler are ignored.
     """
 
     def on_state_changed(self, new_state):
-        print(f"New state is: {new_state}")
+        print(f"State changed to {new_state}")
+
+    def on_state_transition(self, old_state, new_state, reason):
+        print(f"State changed from {old_state} to {new_state} because of {reason}")
 
     def on_bookmark_reached(self, bookmark):
         print(f"Bookmark reached: {bookmark}")
 
+    def on_internal_error(self, message):
+        print(f"Internal error: {message}")
+
     def log(self, message, level):
         print(f"LOG {level}: {message}")
 